@@ -0,0 +1,137 @@
+use std::error;
+use std::fmt;
+
+// Diagnostic is a problem found while reading an input file, along with as
+// much as is known about where it is and what would have been accepted
+// there, so it can be reported as something like:
+//
+//   assets/scene.csv, row 4, column shape: unknown shape type, found 'circ'
+//   (accepted values are rect, tri)
+//
+// Everything but the message is optional and is filled in as the error
+// makes its way up through code that knows more about where it came from
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub file: Option<String>,
+    // row is counted the way a spreadsheet would count it (the header is row 1)
+    pub row: Option<usize>,
+    // column is a csv header, or the path to a field such as shapes[3].width
+    pub column: Option<String>,
+    pub value: Option<String>,
+    pub accepted: Vec<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(message: String) -> Diagnostic {
+        return Diagnostic {
+            file: None,
+            row: None,
+            column: None,
+            value: None,
+            accepted: Vec::new(),
+            message: message,
+        };
+    }
+
+    pub fn in_file(mut self, file: &str) -> Diagnostic {
+        self.file = Some(file.to_string());
+        return self;
+    }
+
+    pub fn at_row(mut self, row: usize) -> Diagnostic {
+        self.row = Some(row);
+        return self;
+    }
+
+    pub fn in_column(mut self, column: &str) -> Diagnostic {
+        self.column = Some(column.to_string());
+        return self;
+    }
+
+    pub fn with_value(mut self, value: &str) -> Diagnostic {
+        self.value = Some(value.to_string());
+        return self;
+    }
+
+    pub fn accepting(mut self, accepted: Vec<&str>) -> Diagnostic {
+        self.accepted = accepted.iter().map(|a| a.to_string()).collect();
+        return self;
+    }
+
+    // location is the file, row and column as far as they are known
+    pub fn location(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(ref file) = self.file {
+            parts.push(file.clone());
+        }
+        if let Some(row) = self.row {
+            parts.push(format!("row {}", row));
+        }
+        if let Some(ref column) = self.column {
+            parts.push(format!("column {}", column));
+        }
+        return parts.join(", ");
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = self.location();
+        if !location.is_empty() {
+            try!(write!(f, "{}: ", location));
+        }
+        try!(write!(f, "{}", self.message));
+        if let Some(ref value) = self.value {
+            try!(write!(f, ", found '{}'", value));
+        }
+        if !self.accepted.is_empty() {
+            try!(write!(f, " (accepted values are {})", self.accepted.join(", ")));
+        }
+        return Ok(());
+    }
+}
+
+impl error::Error for Diagnostic {
+    fn description(&self) -> &str {
+        return &self.message;
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        return None;
+    }
+}
+
+// Diagnostics are every problem a validation pass found, so they can all be
+// fixed in one go rather than one run at a time
+#[derive(Debug)]
+pub struct Diagnostics {
+    pub list: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    // check is Ok when the list is empty and the list as an error otherwise
+    pub fn check(list: Vec<Diagnostic>) -> Result<(), Diagnostics> {
+        return match list.len() {
+            0 => Ok(()),
+            _ => Err(Diagnostics { list: list }),
+        };
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.list.iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl error::Error for Diagnostics {
+    fn description(&self) -> &str {
+        return "the file has mistakes in it";
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        return self.list.first().map(|d| d as &error::Error);
+    }
+}
@@ -0,0 +1,11 @@
+extern crate csv;
+extern crate rustc_serialize;
+
+mod diagnostic;
+mod record;
+
+pub use diagnostic::Diagnostic;
+pub use diagnostic::Diagnostics;
+pub use record::Row;
+pub use record::no_accepted_values;
+pub use record::read_csv;
@@ -0,0 +1,395 @@
+use csv;
+
+use rustc_serialize::Decodable;
+use rustc_serialize::Decoder;
+
+use std::str::FromStr;
+
+use diagnostic::Diagnostic;
+
+// Row is a decoded csv row along with its (spreadsheet style) row number
+pub struct Row<T> {
+    pub number: usize,
+    pub value: T,
+}
+
+// read_csv decodes every row of a csv file. Fields are matched to columns by
+// header name, and a header that names no field or a field with no column is
+// an error, except that columns for Option fields may be missing or left
+// empty. A file whose first row has a number in it has no header row, and its
+// fields are read by position instead. Errors say which row and column they
+// were found in, and accepted gives the values that would be allowed in a
+// column, if it only takes a fixed set of names
+pub fn read_csv<T: Decodable>(filename: &str,
+                              accepted: fn(&str) -> Vec<&'static str>)
+                              -> Result<Vec<Row<T>>, Diagnostic> {
+    let in_file = |e: csv::Error| Diagnostic::new(e.to_string()).in_file(filename);
+    let mut reader = try!(csv::Reader::from_file(filename).map_err(&in_file));
+    let first = try!(reader.headers().map_err(&in_file));
+    let named = !first.iter().any(|field| field.trim().parse::<f64>().is_ok());
+    let (headers, first_row) = match named {
+        true => (first, None),
+        false => (Vec::new(), Some(Ok(first))),
+    };
+
+    let mut rows = Vec::new();
+    let mut field_names: Vec<String> = Vec::new();
+    for (index, record) in first_row.into_iter().chain(reader.records()).enumerate() {
+        let number = match named {
+            true => index + 2,
+            false => index + 1,
+        };
+        let fields = try!(record.map_err(|e| in_file(e).at_row(number)));
+        let mut decoder = RecordDecoder {
+            headers: &headers,
+            named: named,
+            fields: fields,
+            column: None,
+            field_name: String::new(),
+            field_names: field_names,
+            accepted: accepted,
+        };
+        let value = try!(T::decode(&mut decoder).map_err(|d| d.in_file(filename).at_row(number)));
+        field_names = decoder.field_names;
+        rows.push(Row {
+            number: number,
+            value: value,
+        });
+    }
+
+    // a misspelled header for an optional column would otherwise be dropped
+    match headers.iter().find(|header| unknown_header(header, &field_names)) {
+        Some(header) if !rows.is_empty() => {
+            let names: Vec<&str> = field_names.iter().map(|name| name.as_str()).collect();
+            return Err(Diagnostic::new("unknown column".to_string())
+                .in_file(filename)
+                .at_row(1)
+                .in_column(header.trim())
+                .accepting(names));
+        }
+        _ => (),
+    }
+    return Ok(rows);
+}
+
+fn unknown_header(header: &str, field_names: &[String]) -> bool {
+    let header = header.trim();
+    return !header.is_empty() && !field_names.iter().any(|name| name == header);
+}
+
+pub fn no_accepted_values(_: &str) -> Vec<&'static str> {
+    return Vec::new();
+}
+
+// RecordDecoder decodes a struct from a single csv record, keeping track of
+// which column it is reading so errors can point at it
+struct RecordDecoder<'a> {
+    headers: &'a [String],
+    // named is set when the file has a header row to match fields by
+    named: bool,
+    fields: Vec<String>,
+    // column is None when no column has the field being read
+    column: Option<usize>,
+    // field_name is the struct field being read, for columns with no header
+    field_name: String,
+    // field_names are every field read so far, to check the headers against
+    field_names: Vec<String>,
+    accepted: fn(&str) -> Vec<&'static str>,
+}
+
+impl<'a> RecordDecoder<'a> {
+    fn column_name(&self) -> String {
+        return match self.column.and_then(|column| self.headers.get(column)) {
+            Some(header) => header.trim().to_string(),
+            None => self.field_name.clone(),
+        };
+    }
+
+    fn field(&self) -> Result<String, Diagnostic> {
+        return match self.column.and_then(|column| self.fields.get(column)) {
+            Some(field) => Ok(field.trim().to_string()),
+            None => {
+                Err(Diagnostic::new("missing column".to_string()).in_column(&self.column_name()))
+            }
+        };
+    }
+
+    fn parse<T: FromStr>(&self, kind: &str) -> Result<T, Diagnostic> {
+        let field = try!(self.field());
+        return field.parse().map_err(|_| {
+            Diagnostic::new(format!("expected {}", kind))
+                .in_column(&self.column_name())
+                .with_value(&field)
+        });
+    }
+
+    fn unsupported(&self, what: &str) -> Diagnostic {
+        return Diagnostic::new(format!("{} can't be read from a csv column", what))
+            .in_column(&self.column_name());
+    }
+}
+
+impl<'a> Decoder for RecordDecoder<'a> {
+    type Error = Diagnostic;
+
+    fn error(&mut self, err: &str) -> Diagnostic {
+        let column = self.column_name();
+        let diagnostic = Diagnostic::new(err.to_string())
+            .in_column(&column)
+            .accepting((self.accepted)(&column));
+        return match self.field() {
+            Ok(field) => diagnostic.with_value(&field),
+            Err(_) => diagnostic,
+        };
+    }
+
+    fn read_nil(&mut self) -> Result<(), Diagnostic> {
+        return Ok(());
+    }
+
+    fn read_usize(&mut self) -> Result<usize, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Diagnostic> {
+        return self.parse("a whole number from 0 to 65535");
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Diagnostic> {
+        return self.parse("a whole number from 0 to 255");
+    }
+
+    fn read_isize(&mut self) -> Result<isize, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Diagnostic> {
+        return self.parse("a whole number");
+    }
+
+    fn read_bool(&mut self) -> Result<bool, Diagnostic> {
+        return self.parse("true or false");
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Diagnostic> {
+        return self.parse("a number");
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Diagnostic> {
+        return self.parse("a number");
+    }
+
+    fn read_char(&mut self) -> Result<char, Diagnostic> {
+        return self.parse("a single character");
+    }
+
+    fn read_str(&mut self) -> Result<String, Diagnostic> {
+        return self.field();
+    }
+
+    fn read_enum<T, F>(&mut self, _: &str, f: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return f(self);
+    }
+
+    // enums are written as the name of the variant
+    fn read_enum_variant<T, F>(&mut self, names: &[&str], mut f: F) -> Result<T, Diagnostic>
+        where F: FnMut(&mut Self, usize) -> Result<T, Diagnostic>
+    {
+        let field = try!(self.field());
+        return match names.iter().position(|name| *name == field) {
+            Some(index) => f(self, index),
+            None => {
+                Err(Diagnostic::new("unknown value".to_string())
+                    .in_column(&self.column_name())
+                    .with_value(&field)
+                    .accepting(names.to_vec()))
+            }
+        };
+    }
+
+    fn read_enum_variant_arg<T, F>(&mut self, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("an enum variant with values"));
+    }
+
+    fn read_enum_struct_variant<T, F>(&mut self, names: &[&str], f: F) -> Result<T, Diagnostic>
+        where F: FnMut(&mut Self, usize) -> Result<T, Diagnostic>
+    {
+        return self.read_enum_variant(names, f);
+    }
+
+    fn read_enum_struct_variant_field<T, F>(&mut self,
+                                            _: &str,
+                                            _: usize,
+                                            _: F)
+                                            -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("an enum variant with fields"));
+    }
+
+    fn read_struct<T, F>(&mut self, _: &str, _: usize, f: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return f(self);
+    }
+
+    // fields are read from the column with the same header, or from the
+    // column in the same position if the file has no header row
+    fn read_struct_field<T, F>(&mut self, name: &str, index: usize, f: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        self.field_name = name.to_string();
+        if !self.field_names.iter().any(|field_name| field_name == name) {
+            self.field_names.push(name.to_string());
+        }
+        self.column = match self.named {
+            true => self.headers.iter().position(|header| header.trim() == name),
+            false => Some(index),
+        };
+        return f(self);
+    }
+
+    fn read_tuple<T, F>(&mut self, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a tuple"));
+    }
+
+    fn read_tuple_arg<T, F>(&mut self, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a tuple"));
+    }
+
+    fn read_tuple_struct<T, F>(&mut self, _: &str, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a tuple struct"));
+    }
+
+    fn read_tuple_struct_arg<T, F>(&mut self, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a tuple struct"));
+    }
+
+    // an option is None when its column is missing or empty
+    fn read_option<T, F>(&mut self, mut f: F) -> Result<T, Diagnostic>
+        where F: FnMut(&mut Self, bool) -> Result<T, Diagnostic>
+    {
+        let field = self.column.and_then(|column| self.fields.get(column));
+        return match field.map(|field| field.trim().is_empty()) {
+            Some(false) => f(self, true),
+            _ => f(self, false),
+        };
+    }
+
+    fn read_seq<T, F>(&mut self, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self, usize) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a list"));
+    }
+
+    fn read_seq_elt<T, F>(&mut self, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a list"));
+    }
+
+    fn read_map<T, F>(&mut self, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self, usize) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a map"));
+    }
+
+    fn read_map_elt_key<T, F>(&mut self, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a map"));
+    }
+
+    fn read_map_elt_val<T, F>(&mut self, _: usize, _: F) -> Result<T, Diagnostic>
+        where F: FnOnce(&mut Self) -> Result<T, Diagnostic>
+    {
+        return Err(self.unsupported("a map"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    use super::no_accepted_values;
+    use super::read_csv;
+
+    #[derive(RustcDecodable)]
+    struct Point {
+        x: f32,
+        y: f32,
+        label: Option<String>,
+    }
+
+    fn read(name: &str, contents: &str) -> Result<Vec<Point>, String> {
+        let filename = env::temp_dir().join(format!("diagnostics-{}-test.csv", name));
+        let filename = filename.to_str().unwrap();
+        fs::File::create(filename).unwrap().write_all(contents.as_bytes()).unwrap();
+        let rows = read_csv::<Point>(filename, no_accepted_values);
+        fs::remove_file(filename).unwrap();
+        return rows.map(|rows| rows.into_iter().map(|row| row.value).collect())
+            .map_err(|d| format!("row {:?}, column {:?}: {}", d.row, d.column, d.message));
+    }
+
+    #[test]
+    fn fields_are_matched_by_header_name() {
+        let points = read("named", "label,y,x\nhere,2,1\n,4,3\n").unwrap();
+        assert_eq!((points[0].x, points[0].y), (1.0, 2.0));
+        assert_eq!(points[0].label, Some("here".to_string()));
+        assert_eq!(points[1].label, None);
+    }
+
+    #[test]
+    fn a_misspelled_required_header_is_a_missing_column() {
+        let err = read("required", "x,why\n1,2\n").err().unwrap();
+        assert_eq!(err, "row Some(2), column Some(\"y\"): missing column");
+    }
+
+    #[test]
+    fn a_misspelled_optional_header_is_an_unknown_column() {
+        let err = read("optional", "x,y,lable\n1,2,here\n").err().unwrap();
+        assert_eq!(err, "row Some(1), column Some(\"lable\"): unknown column");
+    }
+
+    #[test]
+    fn a_file_without_a_header_row_is_read_by_position() {
+        let points = read("positional", "1,2,here\n3,4,\n").unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!((points[1].x, points[1].y), (3.0, 4.0));
+        assert_eq!(points[1].label, None);
+    }
+}
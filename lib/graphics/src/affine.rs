@@ -0,0 +1,54 @@
+// Affine2D is a transform as a matrix, which unlike Transform2D can hold any
+// number of transforms combined, for shapes placed inside other shapes:
+//
+//   x' = xx * x + xy * y + x_offset
+//   y' = yx * x + yy * y + y_offset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine2D {
+    pub xx: f32,
+    pub xy: f32,
+    pub yx: f32,
+    pub yy: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+}
+
+impl Affine2D {
+    pub fn identity() -> Affine2D {
+        return Affine2D::translation(0.0, 0.0);
+    }
+
+    pub fn translation(x: f32, y: f32) -> Affine2D {
+        return Affine2D {
+            xx: 1.0,
+            xy: 0.0,
+            yx: 0.0,
+            yy: 1.0,
+            x_offset: x,
+            y_offset: y,
+        };
+    }
+
+    // compose gives the transform that applies inner first and then this one
+    pub fn compose(&self, inner: &Affine2D) -> Affine2D {
+        return Affine2D {
+            xx: self.xx * inner.xx + self.xy * inner.yx,
+            xy: self.xx * inner.xy + self.xy * inner.yy,
+            yx: self.yx * inner.xx + self.yy * inner.yx,
+            yy: self.yx * inner.xy + self.yy * inner.yy,
+            x_offset: self.xx * inner.x_offset + self.xy * inner.y_offset + self.x_offset,
+            y_offset: self.yx * inner.x_offset + self.yy * inner.y_offset + self.y_offset,
+        };
+    }
+
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        return (self.xx * x + self.xy * y + self.x_offset,
+                self.yx * x + self.yy * y + self.y_offset);
+    }
+}
+
+impl Default for Affine2D {
+    fn default() -> Affine2D {
+        return Affine2D::identity();
+    }
+}
@@ -1,17 +1,43 @@
 use gl;
+use image;
 
 use std::error;
+use std::io;
+use std::io::Write;
+use std::mem;
 use std::ptr;
+use std::time;
 
+use fill;
+use gerror;
+use headless;
+use offscreen;
 use program;
 use shader_source;
 use texture;
+use tiled_texture;
 use vertex;
 use window;
 
 pub struct App {
-    window: window::Window,
+    // the offscreen framebuffer is dropped before the context goes,
+    offscreen: Option<offscreen::Offscreen>,
+    // so is the texture of a TextureRenderingSource, which is only kept so
+    // that it lives as long as the app
+    _texture: Option<texture::Texture>,
+    // the gl context is the window's, or for an app that only draws
+    // offscreen, a headless one that is likewise only kept to live as long
+    window: Option<window::Window>,
+    _headless: Option<headless::Headless>,
     renderer: Renderer,
+    background: (f32, f32, f32),
+    // a window's frame can be read back from the front buffer once it is on
+    // screen, but some systems don't keep it there, so frames that are sure
+    // to be captured (such as a recording's) are kept as they are drawn
+    keep_frames: bool,
+    last_frame: Option<Result<image::RgbaImage, String>>,
+    // the prefix of a screenshot to save once the next frame is drawn
+    screenshot: Option<String>,
 }
 
 impl App {
@@ -22,40 +48,294 @@ impl App {
                -> Result<App, Box<error::Error>> {
 
         let mut window = try!(window::Window::new(width, height, title));
-
         window.make_main();
+        return App::with_context(Some(window), None, source, None);
+    }
+
+    // new_offscreen makes an app that draws frames of the given size without
+    // a window, for saving them with save_frame. Its gl context comes from
+    // EGL rather than a window, so it runs where there is no display server
+    pub fn new_offscreen(width: u32,
+                         height: u32,
+                         source: RenderingSource)
+                         -> Result<App, Box<error::Error>> {
+        let headless = try!(headless::Headless::new());
+        let offscreen = try!(offscreen::Offscreen::new(width, height));
+        return App::with_context(None, Some(headless), source, Some(offscreen));
+    }
+
+    fn with_context(window: Option<window::Window>,
+                    headless: Option<headless::Headless>,
+                    source: RenderingSource,
+                    offscreen: Option<offscreen::Offscreen>)
+                    -> Result<App, Box<error::Error>> {
 
+        let mut texture = None;
         let renderer = match source {
             RenderingSource::ColorRenderingSource => {
+                unsafe {
+                    // blend so that shapes with an alpha below 255 let the ones behind show through
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                }
                 Renderer::new(shader_source::color_pipeline_source())
             }
             RenderingSource::TextureRenderingSource { tex_def } => {
                 let r = Renderer::new(shader_source::texture_pipeline_source());
-                texture::texture_load(r.program.get_addr(), tex_def);
+                texture = Some(try!(texture::texture_load(r.program.get_addr(), tex_def)));
                 r
             }
         };
 
         return Ok(App {
+            offscreen: offscreen,
+            _texture: texture,
             window: window,
+            _headless: headless,
             renderer: renderer,
+            background: (0.9, 0.1, 0.2),
+            keep_frames: false,
+            last_frame: None,
+            screenshot: None,
         });
     }
 
+    pub fn set_background(&mut self, red: u8, green: u8, blue: u8) {
+        self.background = (f32::from(red) / 255.0,
+                           f32::from(green) / 255.0,
+                           f32::from(blue) / 255.0);
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        if let Some(ref mut window) = self.window {
+            window.set_title(title);
+        }
+    }
+
+    // pixel_ratio is how many pixels there are to a screen coordinate, which
+    // is more than one on high density displays
+    pub fn pixel_ratio(&self) -> f32 {
+        let window = match self.window {
+            Some(ref window) => window,
+            None => return 1.0,
+        };
+        let (width, _) = window.size();
+        let (pixel_width, _) = window.framebuffer_size();
+        if width == 0 {
+            return 1.0;
+        }
+        return pixel_width as f32 / width as f32;
+    }
+
+    // load_texture puts another image on the graphics card for a
+    // TextureRenderingSource to draw with, and binds it
+    pub fn load_texture(&mut self,
+                        tex_def: texture::TextureSetupDefinition)
+                        -> Result<texture::Texture, Box<error::Error>> {
+        return Ok(try!(texture::Texture::new(tex_def)));
+    }
+
+    // load_tiled_texture puts an image of any size on the graphics card,
+    // split across as many textures as it needs, for draw_tiled. The rgba
+    // pixels are only borrowed, so the caller can keep them
+    pub fn load_tiled_texture(&mut self,
+                              width: u32,
+                              height: u32,
+                              data: &[u8])
+                              -> Result<tiled_texture::TiledTexture, Box<error::Error>> {
+        return Ok(try!(tiled_texture::TiledTexture::new(width, height, data)));
+    }
+
+    // update_texture swaps in new rgba pixels for a texture, such as the
+    // next frame of an animation, leaving it bound
+    pub fn update_texture(&mut self,
+                          texture: &texture::Texture,
+                          data: &[u8])
+                          -> Result<(), Box<error::Error>> {
+        return Ok(try!(texture.update(data)));
+    }
+
+    pub fn update_tiled_texture(&mut self,
+                                texture: &tiled_texture::TiledTexture,
+                                data: &[u8])
+                                -> Result<(), Box<error::Error>> {
+        return Ok(try!(texture.update(data)));
+    }
+
+    // bind_texture chooses the texture to draw with
+    pub fn bind_texture(&mut self, texture: &texture::Texture) {
+        texture.bind();
+    }
+
+    // set_nearest_filter chooses blocky rather than smooth scaling for the
+    // texture that is bound
+    pub fn set_nearest_filter(&mut self, nearest: bool) {
+        texture::set_nearest_filter(nearest);
+    }
+
     pub fn draw<V: vertex::VertexSpecable + ?Sized>(&mut self, rects: &Vec<Box<V>>) {
+        self.clear();
+        self.renderer.draw(rects);
+        self.present();
+    }
+
+    // draw_tiled draws a TiledTexture stretched over the rectangle, which is
+    // shown at the scale (screen pixels to image pixels), with blocky rather
+    // than smooth scaling if nearest
+    pub fn draw_tiled(&mut self,
+                      texture: &tiled_texture::TiledTexture,
+                      lower_x: f32,
+                      upper_x: f32,
+                      lower_y: f32,
+                      upper_y: f32,
+                      scale: f32,
+                      nearest: bool) {
+        self.clear();
+        // each tile is its own texture, so they are drawn one at a time
+        for (tile, rect) in texture.parts(lower_x, upper_x, lower_y, upper_y, scale) {
+            tile.bind();
+            texture::set_nearest_filter(nearest);
+            self.renderer.draw(&vec![Box::new(rect)]);
+        }
+        self.present();
+    }
+
+    fn clear(&self) {
+        let (red, green, blue) = self.background;
         unsafe {
-            // Clear the screen to red
-            gl::ClearColor(0.9, 0.1, 0.2, 1.0);
+            // Clear the screen to the background color (red unless set)
+            gl::ClearColor(red, green, blue, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
         }
+    }
 
-        self.renderer.draw(rects);
+    fn present(&mut self) {
+        match self.offscreen {
+            Some(_) => unsafe { gl::Finish() },
+            None => {
+                if self.keep_frames || self.screenshot.is_some() {
+                    self.last_frame = Some(self.read_frame(gl::BACK));
+                }
+                if let Some(ref mut window) = self.window {
+                    window.swap_buffers();
+                }
+            }
+        }
+        if let Some(prefix) = self.screenshot.take() {
+            match self.write_screenshot(&prefix) {
+                Ok(filename) => println!("saved screenshot: {}", filename),
+                Err(err) => {
+                    let _ = writeln!(io::stderr(), "could not save screenshot: {}", err);
+                }
+            }
+            // the screenshot's frame is only kept for it
+            if !self.keep_frames {
+                self.last_frame = None;
+            }
+        }
+    }
+
+    // set_keep_frames has a window keep each frame it draws, for
+    // capture_frame and save_frame to give back even where the front buffer
+    // can't be read. An offscreen app can always capture its frames
+    pub fn set_keep_frames(&mut self, keep_frames: bool) {
+        self.keep_frames = keep_frames;
+        if !keep_frames {
+            self.last_frame = None;
+        }
+    }
+
+    // capture_frame gives back the last frame drawn, as it was shown in the
+    // window (or as it was drawn offscreen)
+    pub fn capture_frame(&self) -> Result<image::RgbaImage, Box<error::Error>> {
+        return Ok(try!(self.shown_frame()));
+    }
 
-        self.window.swap_buffers();
+    // save_frame writes the last frame drawn to a png file
+    pub fn save_frame(&self, filename: &str) -> Result<(), Box<error::Error>> {
+        try!(try!(self.shown_frame()).save(filename));
+        return Ok(());
     }
 
+    // save_screenshot saves the next frame drawn to the working directory,
+    // in a png named after the prefix and the time, and says where it went
+    // (or why it couldn't be saved)
+    pub fn save_screenshot(&mut self, prefix: &str) {
+        self.screenshot = Some(prefix.to_string());
+    }
+
+    fn write_screenshot(&self, prefix: &str) -> Result<String, Box<error::Error>> {
+        let since_epoch = try!(time::SystemTime::now().duration_since(time::UNIX_EPOCH));
+        let millis = since_epoch.as_secs() * 1000 + (since_epoch.subsec_nanos() / 1000000) as u64;
+        let filename = format!("{}-{}.png", prefix, millis);
+        try!(self.save_frame(&filename));
+        return Ok(filename);
+    }
+
+    // shown_frame is the last frame drawn: the offscreen framebuffer, the
+    // frame a window kept, or else what is in the window's front buffer
+    fn shown_frame(&self) -> Result<image::RgbaImage, gerror::Error> {
+        let frame = match (&self.offscreen, &self.last_frame) {
+            (&Some(_), _) => self.read_frame(gl::COLOR_ATTACHMENT0),
+            (&None, &Some(ref frame)) => frame.clone(),
+            (&None, &None) => self.read_frame(gl::FRONT),
+        };
+        return frame.map_err(gerror::Error::CaptureError);
+    }
+
+    // read_frame reads back a frame from one of the buffers: the back buffer
+    // of a window while it is drawn, its front buffer once it is shown, or
+    // the offscreen framebuffer. Shapes that are partly see-through leave its
+    // alpha below 255, so it is set back to opaque to match what is on screen
+    fn read_frame(&self, buffer: gl::types::GLenum) -> Result<image::RgbaImage, String> {
+        let (width, height) = match (&self.offscreen, &self.window) {
+            (&Some(ref offscreen), _) => offscreen.size(),
+            (&None, &Some(ref window)) => window.framebuffer_size(),
+            (&None, &None) => (0, 0),
+        };
+        let row = (width * 4) as usize;
+        let mut data: Vec<u8> = vec![0; row * height as usize];
+        if data.len() > 0 {
+            unsafe {
+                gl::ReadBuffer(buffer);
+                gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+                gl::ReadPixels(0,
+                               0,
+                               width as i32,
+                               height as i32,
+                               gl::RGBA,
+                               gl::UNSIGNED_BYTE,
+                               data.as_mut_ptr() as *mut _);
+            }
+        }
+        // gl gives the rows from the bottom up
+        let mut pixels: Vec<u8> = Vec::with_capacity(data.len());
+        for y in (0..height as usize).rev() {
+            pixels.extend_from_slice(&data[y * row..(y + 1) * row]);
+        }
+        for pixel in pixels.chunks_mut(4) {
+            pixel[3] = 255;
+        }
+        return image::ImageBuffer::from_raw(width, height, pixels)
+            .ok_or(format!("the {}x{} frame didn't read back whole", width, height));
+    }
+
+    // handle_events gives back the next waiting event, one a call, so it
+    // should be called until it gives None each frame or the events pile up
     pub fn handle_events(&mut self) -> Option<window::Action> {
-        return self.window.handle_events();
+        let window = match self.window {
+            Some(ref mut window) => window,
+            None => return None,
+        };
+        let action = window.handle_events();
+        if let (&Some(window::Action::Resized(_, _)), &None) = (&action, &self.offscreen) {
+            // keep drawing to the whole window
+            let (width, height) = window.framebuffer_size();
+            unsafe {
+                gl::Viewport(0, 0, width as i32, height as i32);
+            }
+        }
+        return action;
     }
 
     pub fn close(&self) {
@@ -88,9 +368,25 @@ impl Renderer {
         };
     }
 
+    // draw draws the shapes in order, as few at a time as their fills allow,
+    // since each gradient is handed to the shader on its own
     fn draw<V: vertex::VertexSpecable + ?Sized>(&self, rects: &Vec<Box<V>>) {
+        let mut batch: Vec<vertex::VertexSpecification> = Vec::new();
+        for rect in rects.iter() {
+            let spec = rect.get_vertex_specification();
+            if batch.last().map(|last| last.fill != spec.fill).unwrap_or(false) {
+                self.draw_batch(mem::replace(&mut batch, Vec::new()));
+            }
+            batch.push(spec);
+        }
+        self.draw_batch(batch);
+    }
+
+    fn draw_batch(&self, batch: Vec<vertex::VertexSpecification>) {
+        let spec = vertex::join(batch);
+        fill::load_uniforms(self.program.get_addr(), spec.fill.as_ref());
         // build and copy the vertex data
-        let element_count = self.vertices.gen_vertex_buffers(rects);
+        let element_count = self.vertices.gen_vertex_buffers(&spec);
         unsafe {
             gl::DrawElements(gl::TRIANGLES, element_count, gl::UNSIGNED_INT, ptr::null());
         }
@@ -101,3 +397,24 @@ impl Renderer {
         self.program.close();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use color_shapes;
+
+    use super::App;
+    use super::RenderingSource;
+
+    // this needs no display, only a gl driver that EGL can find
+    #[test]
+    fn offscreen_apps_draw_without_a_window() {
+        let mut app = App::new_offscreen(8, 8, RenderingSource::ColorRenderingSource).unwrap();
+        app.set_background(0, 0, 255);
+        let left_half = color_shapes::SimpleRect::new(-0.5, 0.0, 1.0, 2.0, 255, 0, 0);
+        app.draw(&vec![Box::new(left_half)]);
+        let frame = app.capture_frame().unwrap();
+        assert_eq!(frame.get_pixel(1, 4).data, [255, 0, 0, 255]);
+        assert_eq!(frame.get_pixel(6, 4).data, [0, 0, 255, 255]);
+        app.close();
+    }
+}
@@ -1,43 +1,190 @@
+use affine::Affine2D;
+use fill::Fill;
+use mesh;
+use transform::Transform2D;
 use vertex;
 
+// Updateable shapes only need to hand over their ShapeState, which the rest
+// of the methods change for them
 pub trait Updateable {
-    fn update_offset(&mut self, x: f32, y: f32);
+    fn state_mut(&mut self) -> &mut ShapeState;
+
+    // update_offset moves the shape relative to its position
+    fn update_offset(&mut self, x: f32, y: f32) {
+        self.state_mut().loc.update_offset(x, y);
+    }
+
+    // update_position moves the position the offset is relative to
+    fn update_position(&mut self, x: f32, y: f32) {
+        self.state_mut().loc.update_position(x, y);
+    }
+
+    fn update_size(&mut self, width: f32, height: f32) {
+        let state = self.state_mut();
+        state.width = width;
+        state.height = height;
+    }
+
+    // update_rotation turns the shape about its pivot (its center, unless a
+    // transform has moved it), counter-clockwise
+    fn update_rotation(&mut self, radians: f32) {
+        self.state_mut().loc.transform.rotation = radians;
+    }
+
+    // update_transform replaces the whole transform, rotation included
+    fn update_transform(&mut self, transform: Transform2D) {
+        self.state_mut().loc.transform = transform;
+    }
+
+    // update_parent places the shape inside whatever contains it in a scene
+    // graph, applying the parent's transform after the shape's own
+    fn update_parent(&mut self, parent: Affine2D) {
+        self.state_mut().loc.parent = parent;
+    }
+
+    // update_color fills the shape with a single color, replacing any
+    // gradient
+    fn update_color(&mut self, red: u8, green: u8, blue: u8, alpha: u8) {
+        self.state_mut().fill = Fill::Solid(Color {
+            red: red,
+            green: green,
+            blue: blue,
+            alpha: alpha,
+        });
+    }
+
+    fn update_fill(&mut self, fill: Fill) {
+        self.state_mut().fill = fill;
+    }
 }
 
-struct Color {
-    red: u8,
-    green: u8,
-    blue: u8,
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
 }
 
 impl Color {
-    fn get_color_floats(&self) -> (f32, f32, f32) {
+    pub fn new(red: u8, green: u8, blue: u8) -> Color {
+        return Color {
+            red: red,
+            green: green,
+            blue: blue,
+            alpha: 255,
+        };
+    }
+
+    pub fn get_color_floats(&self) -> (f32, f32, f32, f32) {
         let red = f32::from(self.red) / 255.0;
         let green = f32::from(self.green) / 255.0;
         let blue = f32::from(self.blue) / 255.0;
-        return (red, green, blue);
+        let alpha = f32::from(self.alpha) / 255.0;
+        return (red, green, blue, alpha);
+    }
+
+    // mix blends towards the other color, by a fraction from 0 to 1
+    pub fn mix(&self, other: &Color, fraction: f32) -> Color {
+        let fraction = fraction.max(0.0).min(1.0);
+        let channel = |from: u8, to: u8| {
+            (f32::from(from) + (f32::from(to) - f32::from(from)) * fraction).round() as u8
+        };
+        return Color {
+            red: channel(self.red, other.red),
+            green: channel(self.green, other.green),
+            blue: channel(self.blue, other.blue),
+            alpha: channel(self.alpha, other.alpha),
+        };
+    }
+
+    // vertex is a vertex of this color at a point on the screen, which is
+    // at fill_position in the box a gradient fill is laid out over
+    pub fn vertex(&self, (x, y): (f32, f32), fill_position: (f32, f32)) -> Box<vertex::Vertex> {
+        let (red, green, blue, alpha) = self.get_color_floats();
+        return Box::new(vertex::ColorVertex {
+            x: x,
+            y: y,
+            red: red,
+            green: green,
+            blue: blue,
+            alpha: alpha,
+            fill_x: fill_position.0,
+            fill_y: fill_position.1,
+        });
     }
 }
 
-struct LocInfo {
+pub struct LocInfo {
     x: f32,
     y: f32,
     orig_x: f32,
     orig_y: f32,
+    pub transform: Transform2D,
+    pub parent: Affine2D,
 }
 
 impl LocInfo {
-    fn update_offset(&mut self, x_offset: f32, y_offset: f32) {
+    pub fn new(x: f32, y: f32) -> LocInfo {
+        return LocInfo {
+            x: x,
+            y: y,
+            orig_x: x,
+            orig_y: y,
+            transform: Transform2D::identity(),
+            parent: Affine2D::identity(),
+        };
+    }
+
+    pub fn update_offset(&mut self, x_offset: f32, y_offset: f32) {
         self.x = self.orig_x + x_offset;
         self.y = self.orig_y + y_offset;
     }
+
+    pub fn update_position(&mut self, x: f32, y: f32) {
+        let x_offset = self.x - self.orig_x;
+        let y_offset = self.y - self.orig_y;
+        self.orig_x = x;
+        self.orig_y = y;
+        self.update_offset(x_offset, y_offset);
+    }
+
+    // place turns a point given relative to the center of the shape into
+    // screen coordinates, applying the transform and then the parent's
+    pub fn place(&self, dx: f32, dy: f32) -> (f32, f32) {
+        let (x, y) = self.transform.apply((dx, dy));
+        return self.parent.apply((self.x + x, self.y + y));
+    }
+}
+
+// ShapeState is what every colored shape has: where it is, the size of the
+// box it is drawn in and how that is filled
+pub struct ShapeState {
+    pub loc: LocInfo,
+    pub width: f32,
+    pub height: f32,
+    pub fill: Fill,
+}
+
+impl ShapeState {
+    pub fn new(x: f32, y: f32, width: f32, height: f32, color: Color) -> ShapeState {
+        return ShapeState {
+            loc: LocInfo::new(x, y),
+            width: width,
+            height: height,
+            fill: Fill::Solid(color),
+        };
+    }
+
+    // vertex_specification places and fills the shape's triangles, which
+    // are given about its center
+    pub fn vertex_specification(&self, shape: mesh::Mesh) -> vertex::VertexSpecification {
+        return self.fill.vertex_specification(shape, (self.width, self.height), &self.loc);
+    }
 }
 
 pub struct SimpleRect {
-    loc: LocInfo,
-    width: f32,
-    height: f32,
-    color: Color,
+    state: ShapeState,
 }
 
 impl SimpleRect {
@@ -50,70 +197,33 @@ impl SimpleRect {
                blue: u8)
                -> SimpleRect {
         return SimpleRect {
-            loc: LocInfo {
-                x: xloc,
-                y: yloc,
-                orig_x: xloc,
-                orig_y: yloc,
-            },
-            width: width,
-            height: height,
-            color: Color {
-                red: red,
-                green: green,
-                blue: blue,
-            },
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
         };
     }
 
     fn calc_corners(&self) -> (f32, f32, f32, f32) {
-        let top = self.loc.y + (self.height / 2.0);
-        let bottom = self.loc.y - (self.height / 2.0);
-        let right = self.loc.x + (self.width / 2.0);
-        let left = self.loc.x - (self.width / 2.0);
+        let top = self.state.height / 2.0;
+        let bottom = -(self.state.height / 2.0);
+        let right = self.state.width / 2.0;
+        let left = -(self.state.width / 2.0);
         return (top, bottom, left, right);
     }
 }
 
 impl Updateable for SimpleRect {
-    fn update_offset(&mut self, x_offset: f32, y_offset: f32) {
-        self.loc.update_offset(x_offset, y_offset)
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
     }
 }
 
 impl vertex::VertexSpecable for SimpleRect {
     fn get_vertex_specification(&self) -> vertex::VertexSpecification {
         let (top, bottom, left, right) = self.calc_corners();
-        let (red, green, blue) = self.color.get_color_floats();
-        // top-left, top-right, bottom-left, bottom-right
-        let vertices: Vec<Box<vertex::Vertex>> = vec![Box::new(vertex::ColorVertex {
-                                                          x: left,
-                                                          y: top,
-                                                          red: red,
-                                                          green: green,
-                                                          blue: blue,
-                                                      }),
-                                                      Box::new(vertex::ColorVertex {
-                                                          x: right,
-                                                          y: top,
-                                                          red: red,
-                                                          green: green,
-                                                          blue: blue,
-                                                      }),
-                                                      Box::new(vertex::ColorVertex {
-                                                          x: right,
-                                                          y: bottom,
-                                                          red: red,
-                                                          green: green,
-                                                          blue: blue,
-                                                      }),
-                                                      Box::new(vertex::ColorVertex {
-                                                          x: left,
-                                                          y: bottom,
-                                                          red: red,
-                                                          green: green,
-                                                          blue: blue,
-                                                      })];
+        // top-left, top-right, bottom-right, bottom-left
+        let mut shape = mesh::Mesh::new();
+        for &corner in [(left, top), (right, top), (right, bottom), (left, bottom)].iter() {
+            shape.add_point(corner);
+        }
 
         // the elements each point to what 3 points make up a single triangle
         // given the elements below and the vertex data, we see the triangles
@@ -124,29 +234,14 @@ impl vertex::VertexSpecable for SimpleRect {
         //  | /         |   /|
         //  |/          |  / |
         //  o           | o--o
-        let elements = vec![vertex::ElementTriangle {
-                                p1: 0,
-                                p2: 1,
-                                p3: 2,
-                            },
-                            vertex::ElementTriangle {
-                                p1: 2,
-                                p2: 3,
-                                p3: 0,
-                            }];
-
-        return vertex::VertexSpecification {
-            vertices: vertices,
-            elements: elements,
-        };
+        shape.add_triangle(0, 1, 2);
+        shape.add_triangle(2, 3, 0);
+        return self.state.vertex_specification(shape);
     }
 }
 
 pub struct SimpleTriangle {
-    loc: LocInfo,
-    width: f32,
-    height: f32,
-    color: Color,
+    state: ShapeState,
 }
 
 impl SimpleTriangle {
@@ -159,64 +254,34 @@ impl SimpleTriangle {
                blue: u8)
                -> SimpleTriangle {
         return SimpleTriangle {
-            loc: LocInfo {
-                x: xloc,
-                y: yloc,
-                orig_x: xloc,
-                orig_y: yloc,
-            },
-            width: width,
-            height: height,
-            color: Color {
-                red: red,
-                green: green,
-                blue: blue,
-            },
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
         };
     }
 
     fn calc_points(&self) -> (f32, f32, f32, f32, f32) {
-        let top = self.loc.y + (self.height / 2.0);
-        let bottom = self.loc.y - (self.height / 2.0);
-        let right = self.loc.x + (self.width / 2.0);
-        let left = self.loc.x - (self.width / 2.0);
-        let middle = self.loc.x;
+        let top = self.state.height / 2.0;
+        let bottom = -(self.state.height / 2.0);
+        let right = self.state.width / 2.0;
+        let left = -(self.state.width / 2.0);
+        let middle = 0.0;
         return (top, bottom, left, right, middle);
     }
 }
 
 impl Updateable for SimpleTriangle {
-    fn update_offset(&mut self, x_offset: f32, y_offset: f32) {
-        self.loc.update_offset(x_offset, y_offset)
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
     }
 }
 
 impl vertex::VertexSpecable for SimpleTriangle {
     fn get_vertex_specification(&self) -> vertex::VertexSpecification {
         let (top, bottom, left, right, middle) = self.calc_points();
-        let (red, green, blue) = self.color.get_color_floats();
         // top-middle, bottom-right, bottom-left
-        let vertices: Vec<Box<vertex::Vertex>> = vec![Box::new(vertex::ColorVertex {
-                                                          x: middle,
-                                                          y: top,
-                                                          red: red,
-                                                          green: green,
-                                                          blue: blue,
-                                                      }),
-                                                      Box::new(vertex::ColorVertex {
-                                                          x: right,
-                                                          y: bottom,
-                                                          red: red,
-                                                          green: green,
-                                                          blue: blue,
-                                                      }),
-                                                      Box::new(vertex::ColorVertex {
-                                                          x: left,
-                                                          y: bottom,
-                                                          red: red,
-                                                          green: green,
-                                                          blue: blue,
-                                                      })];
+        let mut shape = mesh::Mesh::new();
+        for &corner in [(middle, top), (right, bottom), (left, bottom)].iter() {
+            shape.add_point(corner);
+        }
 
         // the elements each point to what 3 points make up a single triangle
         // given the elements below and the vertex data, we see the triangle
@@ -227,15 +292,7 @@ impl vertex::VertexSpecable for SimpleTriangle {
         //  | /
         //  |/
         //  o
-        let elements = vec![vertex::ElementTriangle {
-                                p1: 0,
-                                p2: 1,
-                                p3: 2,
-                            }];
-
-        return vertex::VertexSpecification {
-            vertices: vertices,
-            elements: elements,
-        };
+        shape.add_triangle(0, 1, 2);
+        return self.state.vertex_specification(shape);
     }
 }
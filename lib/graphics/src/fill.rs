@@ -0,0 +1,123 @@
+use gl;
+
+use std::cmp;
+use std::ffi::CString;
+
+use gl::types::*;
+
+use color_shapes::Color;
+use color_shapes::LocInfo;
+use mesh;
+use vertex;
+
+// MAX_STOPS is the most stops a gradient can have, as the shader that draws
+// it keeps them in arrays of a fixed size. Any after that are left out
+pub const MAX_STOPS: usize = 8;
+
+// Fill is how a shape is colored in. Gradients are laid out over the box
+// that the shape's width and height make, where (0, 0) is its bottom-left
+// corner and (1, 1) its top-right, before it is transformed
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fill {
+    Solid(Color),
+    // top-left, top-right, bottom-right and bottom-left, blended in between
+    Corners([Color; 4]),
+    // stops are (position, color) pairs, where 0 is the start and 1 the end
+    Linear {
+        start: (f32, f32),
+        end: (f32, f32),
+        stops: Vec<(f32, Color)>,
+    },
+    // stops go out from the center, where 1 is the radius
+    Radial {
+        center: (f32, f32),
+        radius: f32,
+        stops: Vec<(f32, Color)>,
+    },
+}
+
+impl Fill {
+    // vertex_specification places a shape's triangles, which fill a width by
+    // height box about its center, with its LocInfo. A solid color goes in
+    // the vertices, while a gradient goes with the triangles for the shader
+    // to work out at each pixel, from where each vertex is in the box
+    pub fn vertex_specification(&self,
+                                shape: mesh::Mesh,
+                                (width, height): (f32, f32),
+                                loc: &LocInfo)
+                                -> vertex::VertexSpecification {
+        let unit = |(x, y): (f32, f32)| {
+            (if width == 0.0 { 0.5 } else { x / width + 0.5 },
+             if height == 0.0 { 0.5 } else { y / height + 0.5 })
+        };
+        let color = match self {
+            &Fill::Solid(color) => color,
+            _ => Color::new(0, 0, 0),
+        };
+        let mut spec = shape.to_vertex_specification(|(x, y)| {
+            color.vertex(loc.place(x, y), unit((x, y)))
+        });
+        spec.fill = match self {
+            &Fill::Solid(_) => None,
+            fill => Some(fill.clone()),
+        };
+        return spec;
+    }
+}
+
+// load_uniforms gives the color shader the gradient to fill what it draws
+// next with, or has it use the vertices' own colors
+pub fn load_uniforms(program: GLuint, fill: Option<&Fill>) {
+    unsafe {
+        match fill {
+            None |
+            Some(&Fill::Solid(_)) => gl::Uniform1i(uniform(program, "fill_kind"), 0),
+            Some(&Fill::Corners(ref corners)) => {
+                gl::Uniform1i(uniform(program, "fill_kind"), 1);
+                let mut floats = Vec::with_capacity(16);
+                for corner in corners.iter() {
+                    floats.extend_from_slice(&rgba(corner));
+                }
+                gl::Uniform4fv(uniform(program, "corners"), 4, floats.as_ptr());
+            }
+            Some(&Fill::Linear { start, end, ref stops }) => {
+                gl::Uniform1i(uniform(program, "fill_kind"), 2);
+                gl::Uniform2f(uniform(program, "gradient_start"), start.0, start.1);
+                gl::Uniform2f(uniform(program, "gradient_end"), end.0, end.1);
+                load_stops(program, stops);
+            }
+            Some(&Fill::Radial { center, radius, ref stops }) => {
+                gl::Uniform1i(uniform(program, "fill_kind"), 3);
+                gl::Uniform2f(uniform(program, "gradient_start"), center.0, center.1);
+                gl::Uniform1f(uniform(program, "gradient_radius"), radius);
+                load_stops(program, stops);
+            }
+        }
+    }
+}
+
+fn load_stops(program: GLuint, stops: &[(f32, Color)]) {
+    let stops = &stops[..cmp::min(stops.len(), MAX_STOPS)];
+    let positions: Vec<f32> = stops.iter().map(|&(position, _)| position).collect();
+    let mut colors = Vec::with_capacity(stops.len() * 4);
+    for &(_, ref color) in stops.iter() {
+        colors.extend_from_slice(&rgba(color));
+    }
+    unsafe {
+        gl::Uniform1i(uniform(program, "stop_count"), stops.len() as GLint);
+        if stops.len() > 0 {
+            let count = stops.len() as GLsizei;
+            gl::Uniform1fv(uniform(program, "stop_positions"), count, positions.as_ptr());
+            gl::Uniform4fv(uniform(program, "stop_colors"), count, colors.as_ptr());
+        }
+    }
+}
+
+fn uniform(program: GLuint, name: &str) -> GLint {
+    return unsafe { gl::GetUniformLocation(program, CString::new(name).unwrap().as_ptr()) };
+}
+
+fn rgba(color: &Color) -> [f32; 4] {
+    let (red, green, blue, alpha) = color.get_color_floats();
+    return [red, green, blue, alpha];
+}
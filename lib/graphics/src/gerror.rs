@@ -6,6 +6,16 @@ use std::fmt;
 #[derive(Debug)]
 pub enum Error {
     WindowCreationError(GLFWError),
+    // a polygon that can't be filled, with the reason why
+    TessellationError(String),
+    // an offscreen framebuffer that can't be drawn to, with its gl status
+    FramebufferError(u32),
+    // an image that couldn't be put on the graphics card, with the reason why
+    TextureError(String),
+    // a frame that couldn't be read back, with the reason why
+    CaptureError(String),
+    // a gl context without a window that couldn't be made, with the reason why
+    HeadlessError(String),
 }
 
 pub fn new_init_error(e: glfw::InitError) -> Error {
@@ -14,7 +24,18 @@ pub fn new_init_error(e: glfw::InitError) -> Error {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "graphics error")
+        match *self {
+            Error::TessellationError(ref message) => write!(f, "can't fill polygon: {}", message),
+            Error::FramebufferError(status) => {
+                write!(f, "can't draw offscreen: framebuffer status {:#x}", status)
+            }
+            Error::TextureError(ref message) => write!(f, "can't load texture: {}", message),
+            Error::CaptureError(ref message) => write!(f, "can't capture frame: {}", message),
+            Error::HeadlessError(ref message) => {
+                write!(f, "can't draw without a window: {}", message)
+            }
+            _ => write!(f, "graphics error"),
+        }
     }
 }
 
@@ -22,6 +43,11 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::WindowCreationError(_) => "window creation error",
+            Error::TessellationError(_) => "tessellation error",
+            Error::FramebufferError(_) => "offscreen framebuffer error",
+            Error::TextureError(_) => "texture error",
+            Error::CaptureError(_) => "frame capture error",
+            Error::HeadlessError(_) => "headless context error",
         }
     }
 }
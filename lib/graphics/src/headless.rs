@@ -0,0 +1,159 @@
+use gl;
+
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::c_char;
+use std::os::raw::c_void;
+use std::ptr;
+
+use gerror;
+
+type EGLDisplay = *mut c_void;
+type EGLConfig = *mut c_void;
+type EGLContext = *mut c_void;
+type EGLSurface = *mut c_void;
+type EGLint = i32;
+type EGLenum = u32;
+type EGLBoolean = u32;
+
+const EGL_NONE: EGLint = 0x3038;
+const EGL_SURFACE_TYPE: EGLint = 0x3033;
+const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+const EGL_OPENGL_BIT: EGLint = 0x0008;
+const EGL_OPENGL_API: EGLenum = 0x30A2;
+const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+#[link(name = "EGL")]
+extern "C" {
+    fn eglGetProcAddress(name: *const c_char) -> *const c_void;
+    fn eglGetDisplay(native_display: *mut c_void) -> EGLDisplay;
+    fn eglInitialize(display: EGLDisplay, major: *mut EGLint, minor: *mut EGLint) -> EGLBoolean;
+    fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+    fn eglChooseConfig(display: EGLDisplay,
+                       attributes: *const EGLint,
+                       configs: *mut EGLConfig,
+                       config_size: EGLint,
+                       config_count: *mut EGLint)
+                       -> EGLBoolean;
+    fn eglCreateContext(display: EGLDisplay,
+                        config: EGLConfig,
+                        share_context: EGLContext,
+                        attributes: *const EGLint)
+                        -> EGLContext;
+    fn eglMakeCurrent(display: EGLDisplay,
+                      draw: EGLSurface,
+                      read: EGLSurface,
+                      context: EGLContext)
+                      -> EGLBoolean;
+    fn eglDestroyContext(display: EGLDisplay, context: EGLContext) -> EGLBoolean;
+    fn eglTerminate(display: EGLDisplay) -> EGLBoolean;
+    fn eglGetError() -> EGLint;
+}
+
+type GetPlatformDisplay = extern "C" fn(EGLenum, *mut c_void, *const EGLint) -> EGLDisplay;
+
+// Headless is a gl context with no window, made through EGL, so it needs no
+// display server (Mesa's llvmpipe draws with the processor alone). It has
+// no surface of its own, so everything is drawn into an Offscreen
+pub struct Headless {
+    display: EGLDisplay,
+    context: EGLContext,
+}
+
+impl Headless {
+    // new makes the context and loads the gl functions from it
+    pub fn new() -> Result<Headless, gerror::Error> {
+        let display = try!(headless_display());
+        let context = unsafe {
+            let (mut major, mut minor) = (0, 0);
+            if eglInitialize(display, &mut major, &mut minor) == 0 {
+                return Err(egl_error("can't set up the display"));
+            }
+            if eglBindAPI(EGL_OPENGL_API) == 0 {
+                eglTerminate(display);
+                return Err(egl_error("there is no desktop gl"));
+            }
+            // a surface type of 0 takes configs that can't make any surface,
+            // which is all some surfaceless drivers have
+            let attributes = [EGL_RENDERABLE_TYPE, EGL_OPENGL_BIT, EGL_SURFACE_TYPE, 0, EGL_NONE];
+            let mut config = ptr::null_mut();
+            let mut count = 0;
+            if eglChooseConfig(display, attributes.as_ptr(), &mut config, 1, &mut count) == 0 ||
+               count == 0 {
+                eglTerminate(display);
+                return Err(egl_error("there is no config for desktop gl"));
+            }
+            let attributes = [EGL_CONTEXT_MAJOR_VERSION,
+                              3,
+                              EGL_CONTEXT_MINOR_VERSION,
+                              2,
+                              EGL_CONTEXT_OPENGL_PROFILE_MASK,
+                              EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                              EGL_NONE];
+            let context = eglCreateContext(display, config, ptr::null_mut(), attributes.as_ptr());
+            if context.is_null() {
+                eglTerminate(display);
+                return Err(egl_error("can't make a gl 3.2 context"));
+            }
+            if eglMakeCurrent(display, ptr::null_mut(), ptr::null_mut(), context) == 0 {
+                eglDestroyContext(display, context);
+                eglTerminate(display);
+                return Err(egl_error("can't draw without a surface"));
+            }
+            context
+        };
+
+        gl::load_with(|symbol| {
+            let name = CString::new(symbol).unwrap();
+            unsafe { eglGetProcAddress(name.as_ptr()) as *const _ }
+        });
+        return Ok(Headless {
+            display: display,
+            context: context,
+        });
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        unsafe {
+            eglMakeCurrent(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            eglDestroyContext(self.display, self.context);
+            eglTerminate(self.display);
+        }
+    }
+}
+
+// headless_display is Mesa's surfaceless platform where there is one, and
+// otherwise whatever display EGL gives by default
+fn headless_display() -> Result<EGLDisplay, gerror::Error> {
+    let name = CString::new("eglGetPlatformDisplayEXT").unwrap();
+    let display = unsafe {
+        let get_platform_display = eglGetProcAddress(name.as_ptr());
+        let display = match get_platform_display.is_null() {
+            true => ptr::null_mut(),
+            false => {
+                let get_platform_display: GetPlatformDisplay =
+                    mem::transmute(get_platform_display);
+                get_platform_display(EGL_PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null())
+            }
+        };
+        match display.is_null() {
+            true => eglGetDisplay(ptr::null_mut()),
+            false => display,
+        }
+    };
+    if display.is_null() {
+        return Err(egl_error("there is no display"));
+    }
+    return Ok(display);
+}
+
+fn egl_error(message: &str) -> gerror::Error {
+    let code = unsafe { eglGetError() };
+    return gerror::Error::HeadlessError(format!("{} (egl error {:#x})", message, code));
+}
@@ -1,24 +1,54 @@
 extern crate gl;
 extern crate glfw;
+extern crate image;
 
+mod affine;
 mod app;
+mod fill;
 mod gerror;
+mod headless;
+mod line_shapes;
+mod mesh;
+mod offscreen;
+mod polygon_shapes;
 mod program;
+mod scene_graph;
 mod shader;
 mod shader_source;
+mod tessellate;
 mod color_shapes;
 mod texture;
 mod texture_shapes;
+mod tiled_texture;
+mod transform;
 mod vertex;
 mod window;
 
+pub use affine::Affine2D;
 pub use app::App;
 pub use app::RenderingSource;
+pub use color_shapes::Color;
 pub use color_shapes::SimpleRect;
+pub use color_shapes::ShapeState;
 pub use color_shapes::SimpleTriangle;
 pub use color_shapes::Updateable;
+pub use fill::Fill;
+pub use line_shapes::LineCap;
+pub use line_shapes::LineJoin;
+pub use line_shapes::Polyline;
+pub use polygon_shapes::ConvexPolygon;
+pub use polygon_shapes::Ellipse;
+pub use polygon_shapes::FilledPath;
+pub use polygon_shapes::RegularPolygon;
+pub use polygon_shapes::RoundedRect;
+pub use scene_graph::SceneGraph;
+pub use tessellate::Path;
+pub use tessellate::tessellate;
+pub use texture::Texture;
 pub use texture::TextureSetupDefinition;
 pub use texture_shapes::TexRect;
+pub use tiled_texture::TiledTexture;
+pub use transform::Transform2D;
 pub use vertex::VertexSpecable;
 pub use vertex::VertexSpecification;
 pub use vertex::Vertex;
@@ -26,3 +56,4 @@ pub use vertex::ColorVertex;
 pub use vertex::TextureVertex;
 pub use vertex::ElementTriangle;
 pub use window::Action;
+pub use glfw::Key;
@@ -0,0 +1,249 @@
+use std::f32;
+
+use color_shapes::Color;
+use color_shapes::ShapeState;
+use color_shapes::Updateable;
+use mesh;
+use vertex;
+
+// LineJoin is how the outside of a bend in a Polyline is filled in
+#[derive(Clone, Copy)]
+pub enum LineJoin {
+    // extend the edges until they meet in a point, falling back to a bevel
+    // for very sharp bends where the point would run a long way out
+    Miter,
+    // cut the corner off straight
+    Bevel,
+    Round,
+}
+
+// LineCap is how the two ends of a Polyline are finished
+#[derive(Clone, Copy)]
+pub enum LineCap {
+    // stop square at the end point
+    Butt,
+    // stop square half the thickness past the end point
+    Square,
+    Round,
+}
+
+// a miter longer than this many half thicknesses is beveled instead
+const MITER_LIMIT: f32 = 4.0;
+
+// segments used for each half circle of a round join or cap
+const ROUND_SEGMENTS: u32 = 8;
+
+// Polyline is a thick line through a list of points. Like ConvexPolygon,
+// the points are relative to its position and multiplied by its width and
+// height, while the thickness is left as given
+pub struct Polyline {
+    state: ShapeState,
+    points: Vec<(f32, f32)>,
+    thickness: f32,
+    join: LineJoin,
+    cap: LineCap,
+}
+
+impl Polyline {
+    pub fn new(xloc: f32,
+               yloc: f32,
+               width: f32,
+               height: f32,
+               points: Vec<(f32, f32)>,
+               thickness: f32,
+               join: LineJoin,
+               cap: LineCap,
+               red: u8,
+               green: u8,
+               blue: u8)
+               -> Polyline {
+        return Polyline {
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
+            points: points,
+            thickness: thickness,
+            join: join,
+            cap: cap,
+        };
+    }
+
+    // segment is a single straight line, positioned at its middle
+    pub fn segment(x1: f32,
+                   y1: f32,
+                   x2: f32,
+                   y2: f32,
+                   thickness: f32,
+                   cap: LineCap,
+                   red: u8,
+                   green: u8,
+                   blue: u8)
+                   -> Polyline {
+        let (mid_x, mid_y) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+        return Polyline::new(mid_x,
+                             mid_y,
+                             1.0,
+                             1.0,
+                             vec![(x1 - mid_x, y1 - mid_y), (x2 - mid_x, y2 - mid_y)],
+                             thickness,
+                             LineJoin::Miter,
+                             cap,
+                             red,
+                             green,
+                             blue);
+    }
+
+    fn scaled_points(&self) -> Vec<(f32, f32)> {
+        let mut points: Vec<(f32, f32)> = Vec::new();
+        for &(x, y) in self.points.iter() {
+            let point = (x * self.state.width, y * self.state.height);
+            // repeated points have no direction to draw along
+            if points.last().map(|last| *last != point).unwrap_or(true) {
+                points.push(point);
+            }
+        }
+        return points;
+    }
+
+    fn add_join(&self,
+                shape: &mut mesh::Mesh,
+                before: (f32, f32),
+                at: (f32, f32),
+                after: (f32, f32)) {
+        let half = self.thickness / 2.0;
+        let d0 = direction(before, at);
+        let d1 = direction(at, after);
+        let cross = d0.0 * d1.1 - d0.1 * d1.0;
+        if cross.abs() < 1e-6 && dot(d0, d1) > 0.0 {
+            // carrying straight on, so the segments already meet
+            return;
+        }
+        // the outside of a left turn is on the right of the line
+        let side = if cross > 0.0 { -half } else { half };
+        let o0 = (-d0.1 * side, d0.0 * side);
+        let o1 = (-d1.1 * side, d1.0 * side);
+
+        let mut join = self.join;
+        let mut miter = (0.0, 0.0);
+        if let LineJoin::Miter = join {
+            let bisector = normalize((o0.0 + o1.0, o0.1 + o1.1));
+            let cos_half_angle = dot(normalize(o0), bisector);
+            if cos_half_angle * MITER_LIMIT < 1.0 {
+                join = LineJoin::Bevel;
+            } else {
+                let length = half / cos_half_angle;
+                miter = (bisector.0 * length, bisector.1 * length);
+            }
+        }
+
+        match join {
+            LineJoin::Miter => {
+                shape.add_quad(at, offset(at, o0), offset(at, miter), offset(at, o1))
+            }
+            LineJoin::Bevel => {
+                let c = shape.add_point(at);
+                let a = shape.add_point(offset(at, o0));
+                let b = shape.add_point(offset(at, o1));
+                shape.add_triangle(c, a, b);
+            }
+            LineJoin::Round => {
+                let start = o0.1.atan2(o0.0);
+                let mut sweep = o1.1.atan2(o1.0) - start;
+                // go the short way round the outside
+                while sweep > f32::consts::PI {
+                    sweep -= 2.0 * f32::consts::PI;
+                }
+                while sweep < -f32::consts::PI {
+                    sweep += 2.0 * f32::consts::PI;
+                }
+                let arc = mesh::arc_points(at, (half, half), start, sweep, ROUND_SEGMENTS, false);
+                shape.add_fan(at, &arc, false);
+            }
+        }
+    }
+
+    // add_round_cap puts a half circle on the end of the line at the given
+    // point, bulging out in the given direction
+    fn add_round_cap(&self, shape: &mut mesh::Mesh, at: (f32, f32), along: (f32, f32)) {
+        let half = self.thickness / 2.0;
+        // start from the right of the line and go round the front
+        let start = (-along.0).atan2(along.1);
+        let arc = mesh::arc_points(at,
+                                   (half, half),
+                                   start,
+                                   f32::consts::PI,
+                                   ROUND_SEGMENTS,
+                                   false);
+        shape.add_fan(at, &arc, false);
+    }
+}
+
+impl Updateable for Polyline {
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
+    }
+}
+
+impl vertex::VertexSpecable for Polyline {
+    fn get_vertex_specification(&self) -> vertex::VertexSpecification {
+        let mut shape = mesh::Mesh::new();
+        let mut points = self.scaled_points();
+        let half = self.thickness / 2.0;
+        if points.len() >= 2 {
+            let last = points.len() - 1;
+            let start_direction = direction(points[0], points[1]);
+            let end_direction = direction(points[last - 1], points[last]);
+
+            if let LineCap::Square = self.cap {
+                points[0] = offset(points[0], scale(start_direction, -half));
+                points[last] = offset(points[last], scale(end_direction, half));
+            }
+
+            // each segment is a quad either side of the line:
+            //
+            //  o------------o
+            //  a  -  -  -   b
+            //  o------------o
+            for pair in points.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                let d = direction(a, b);
+                let n = (-d.1 * half, d.0 * half);
+                shape.add_quad(offset(a, n),
+                               offset(b, n),
+                               offset(b, scale(n, -1.0)),
+                               offset(a, scale(n, -1.0)));
+            }
+            for triple in points.windows(3) {
+                self.add_join(&mut shape, triple[0], triple[1], triple[2]);
+            }
+
+            if let LineCap::Round = self.cap {
+                self.add_round_cap(&mut shape, points[0], scale(start_direction, -1.0));
+                self.add_round_cap(&mut shape, points[last], end_direction);
+            }
+        }
+        return self.state.vertex_specification(shape);
+    }
+}
+
+fn direction(from: (f32, f32), to: (f32, f32)) -> (f32, f32) {
+    return normalize((to.0 - from.0, to.1 - from.1));
+}
+
+fn normalize((x, y): (f32, f32)) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length == 0.0 {
+        return (0.0, 0.0);
+    }
+    return (x / length, y / length);
+}
+
+fn dot(a: (f32, f32), b: (f32, f32)) -> f32 {
+    return a.0 * b.0 + a.1 * b.1;
+}
+
+fn scale((x, y): (f32, f32), by: f32) -> (f32, f32) {
+    return (x * by, y * by);
+}
+
+fn offset((x, y): (f32, f32), (dx, dy): (f32, f32)) -> (f32, f32) {
+    return (x + dx, y + dy);
+}
@@ -0,0 +1,137 @@
+use std::cmp;
+use std::f32;
+
+use vertex;
+
+// Mesh is a shape broken down into triangles, with its points given relative
+// to the center of the shape before it is rotated and moved into place
+pub struct Mesh {
+    pub points: Vec<(f32, f32)>,
+    pub triangles: Vec<vertex::ElementTriangle>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        return Mesh {
+            points: Vec::new(),
+            triangles: Vec::new(),
+        };
+    }
+
+    // add_point adds a point and gives back its index for add_triangle
+    pub fn add_point(&mut self, point: (f32, f32)) -> i32 {
+        self.points.push(point);
+        return (self.points.len() - 1) as i32;
+    }
+
+    pub fn add_triangle(&mut self, p1: i32, p2: i32, p3: i32) {
+        self.triangles.push(vertex::ElementTriangle {
+            p1: p1,
+            p2: p2,
+            p3: p3,
+        });
+    }
+
+    // add_quad adds two triangles covering the four points, given in order
+    // around the edge
+    pub fn add_quad(&mut self, a: (f32, f32), b: (f32, f32), c: (f32, f32), d: (f32, f32)) {
+        let a = self.add_point(a);
+        let b = self.add_point(b);
+        let c = self.add_point(c);
+        let d = self.add_point(d);
+        self.add_triangle(a, b, c);
+        self.add_triangle(c, d, a);
+    }
+
+    // add_fan fills a convex outline with triangles fanning out from its
+    // center, which also fills arcs when the center is the arc's center:
+    //
+    //     o---o
+    //    / \ / \
+    //   o---c---o
+    //    \ / \ /
+    //     o---o
+    pub fn add_fan(&mut self, center: (f32, f32), outline: &[(f32, f32)], closed: bool) {
+        if outline.len() < 2 {
+            return;
+        }
+        let c = self.add_point(center);
+        let first = self.points.len() as i32;
+        for point in outline.iter() {
+            self.add_point(*point);
+        }
+        let count = outline.len() as i32;
+        for i in 0..(count - 1) {
+            self.add_triangle(c, first + i, first + i + 1);
+        }
+        if closed {
+            self.add_triangle(c, first + count - 1, first);
+        }
+    }
+
+    // scaled is a copy of the mesh with its points stretched about the center
+    pub fn scaled(&self, x_scale: f32, y_scale: f32) -> Mesh {
+        let mut copy = Mesh::new();
+        copy.points = self.points.iter().map(|&(x, y)| (x * x_scale, y * y_scale)).collect();
+        for t in self.triangles.iter() {
+            copy.add_triangle(t.p1, t.p2, t.p3);
+        }
+        return copy;
+    }
+
+    // to_vertex_specification turns every point into a vertex with the given
+    // function, which places and colors it
+    pub fn to_vertex_specification<F>(&self, place: F) -> vertex::VertexSpecification
+        where F: Fn((f32, f32)) -> Box<vertex::Vertex>
+    {
+        return vertex::VertexSpecification {
+            vertices: self.points.iter().map(|p| place(*p)).collect(),
+            elements: self.triangles
+                .iter()
+                .map(|t| {
+                    vertex::ElementTriangle {
+                        p1: t.p1,
+                        p2: t.p2,
+                        p3: t.p3,
+                    }
+                })
+                .collect(),
+            fill: None,
+        };
+    }
+}
+
+// ellipse_points are evenly spaced points around an ellipse that fits in
+// a width by height box, going counter-clockwise from the start angle
+pub fn ellipse_points(width: f32,
+                      height: f32,
+                      segments: u32,
+                      start_angle: f32)
+                      -> Vec<(f32, f32)> {
+    return arc_points((0.0, 0.0),
+                      (width / 2.0, height / 2.0),
+                      start_angle,
+                      2.0 * f32::consts::PI,
+                      segments,
+                      false);
+}
+
+// arc_points runs counter-clockwise around an elliptical arc from the start
+// angle through the sweep, including both ends unless leave_end is set
+// (so that closed outlines don't repeat their first point)
+pub fn arc_points(center: (f32, f32),
+                  (x_radius, y_radius): (f32, f32),
+                  start_angle: f32,
+                  sweep: f32,
+                  segments: u32,
+                  leave_end: bool)
+                  -> Vec<(f32, f32)> {
+    let segments = cmp::max(segments, 1);
+    let count = if leave_end { segments } else { segments + 1 };
+    return (0..count)
+        .map(|i| {
+            let angle = start_angle + sweep * (i as f32) / (segments as f32);
+            (center.0 + x_radius * angle.cos(), center.1 + y_radius * angle.sin())
+        })
+        .collect();
+}
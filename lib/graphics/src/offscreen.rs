@@ -0,0 +1,67 @@
+use gl;
+
+use std::mem;
+
+use gl::types::*;
+
+use gerror;
+
+// Offscreen is a framebuffer of a fixed size that drawing goes to in place
+// of the window, so frames can be rendered without showing anything and
+// come out the same size whatever the screen is like
+pub struct Offscreen {
+    framebuffer: GLuint,
+    renderbuffer: GLuint,
+    width: u32,
+    height: u32,
+}
+
+impl Offscreen {
+    pub fn new(width: u32, height: u32) -> Result<Offscreen, gerror::Error> {
+        let (framebuffer, renderbuffer, status) = unsafe {
+            let mut framebuffer = mem::uninitialized();
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+
+            let mut renderbuffer = mem::uninitialized();
+            gl::GenRenderbuffers(1, &mut renderbuffer);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+            gl::RenderbufferStorage(gl::RENDERBUFFER,
+                                    gl::RGBA8,
+                                    width as i32,
+                                    height as i32);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER,
+                                        gl::COLOR_ATTACHMENT0,
+                                        gl::RENDERBUFFER,
+                                        renderbuffer);
+
+            gl::Viewport(0, 0, width as i32, height as i32);
+            (framebuffer, renderbuffer, gl::CheckFramebufferStatus(gl::FRAMEBUFFER))
+        };
+
+        let offscreen = Offscreen {
+            framebuffer: framebuffer,
+            renderbuffer: renderbuffer,
+            width: width,
+            height: height,
+        };
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(gerror::Error::FramebufferError(status));
+        }
+        return Ok(offscreen);
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        return (self.width, self.height);
+    }
+}
+
+impl Drop for Offscreen {
+    fn drop(&mut self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::DeleteRenderbuffers(1, &self.renderbuffer);
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::cmp;
+use std::f32;
+
+use color_shapes::Color;
+use color_shapes::ShapeState;
+use color_shapes::Updateable;
+use gerror;
+use mesh;
+use tessellate;
+use vertex;
+
+// Ellipse is drawn as a polygon with the given number of segments, so more
+// segments give a smoother edge at the cost of more vertices
+pub struct Ellipse {
+    state: ShapeState,
+    segments: u32,
+}
+
+impl Ellipse {
+    pub fn new(xloc: f32,
+               yloc: f32,
+               width: f32,
+               height: f32,
+               segments: u32,
+               red: u8,
+               green: u8,
+               blue: u8)
+               -> Ellipse {
+        return Ellipse {
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
+            segments: cmp::max(segments, 3),
+        };
+    }
+
+    pub fn circle(xloc: f32,
+                  yloc: f32,
+                  diameter: f32,
+                  segments: u32,
+                  red: u8,
+                  green: u8,
+                  blue: u8)
+                  -> Ellipse {
+        return Ellipse::new(xloc, yloc, diameter, diameter, segments, red, green, blue);
+    }
+}
+
+impl Updateable for Ellipse {
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
+    }
+}
+
+impl vertex::VertexSpecable for Ellipse {
+    fn get_vertex_specification(&self) -> vertex::VertexSpecification {
+        let mut shape = mesh::Mesh::new();
+        let outline = mesh::ellipse_points(self.state.width, self.state.height, self.segments, 0.0);
+        shape.add_fan((0.0, 0.0), &outline, true);
+        return self.state.vertex_specification(shape);
+    }
+}
+
+// RegularPolygon has its corners evenly spaced around the ellipse that fits
+// its width and height, with the first corner straight up
+pub struct RegularPolygon {
+    state: ShapeState,
+    sides: u32,
+}
+
+impl RegularPolygon {
+    pub fn new(xloc: f32,
+               yloc: f32,
+               width: f32,
+               height: f32,
+               sides: u32,
+               red: u8,
+               green: u8,
+               blue: u8)
+               -> RegularPolygon {
+        return RegularPolygon {
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
+            sides: cmp::max(sides, 3),
+        };
+    }
+}
+
+impl Updateable for RegularPolygon {
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
+    }
+}
+
+impl vertex::VertexSpecable for RegularPolygon {
+    fn get_vertex_specification(&self) -> vertex::VertexSpecification {
+        let mut shape = mesh::Mesh::new();
+        let outline = mesh::ellipse_points(self.state.width,
+                                           self.state.height,
+                                           self.sides,
+                                           f32::consts::PI / 2.0);
+        shape.add_fan((0.0, 0.0), &outline, true);
+        return self.state.vertex_specification(shape);
+    }
+}
+
+// ConvexPolygon is any convex outline. Its points are relative to its
+// position and are multiplied by its width and height, so a width and
+// height of 1.0 draws them as given and resizing stretches the outline
+pub struct ConvexPolygon {
+    state: ShapeState,
+    points: Vec<(f32, f32)>,
+}
+
+impl ConvexPolygon {
+    pub fn new(xloc: f32,
+               yloc: f32,
+               width: f32,
+               height: f32,
+               points: Vec<(f32, f32)>,
+               red: u8,
+               green: u8,
+               blue: u8)
+               -> ConvexPolygon {
+        return ConvexPolygon {
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
+            points: points,
+        };
+    }
+}
+
+impl Updateable for ConvexPolygon {
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
+    }
+}
+
+impl vertex::VertexSpecable for ConvexPolygon {
+    fn get_vertex_specification(&self) -> vertex::VertexSpecification {
+        let outline: Vec<(f32, f32)> = self.points
+            .iter()
+            .map(|&(x, y)| (x * self.state.width, y * self.state.height))
+            .collect();
+        // the average of the corners is inside any convex outline
+        let count = cmp::max(outline.len(), 1) as f32;
+        let center = outline.iter()
+            .fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x / count, sy + y / count));
+
+        let mut shape = mesh::Mesh::new();
+        shape.add_fan(center, &outline, true);
+        return self.state.vertex_specification(shape);
+    }
+}
+
+// segments used for each quarter circle corner of a RoundedRect
+const CORNER_SEGMENTS: u32 = 8;
+
+// RoundedRect is a rectangle with its corners rounded off to the given
+// radius, which is shrunk to fit if it is more than half the width or height
+pub struct RoundedRect {
+    state: ShapeState,
+    radius: f32,
+}
+
+impl RoundedRect {
+    pub fn new(xloc: f32,
+               yloc: f32,
+               width: f32,
+               height: f32,
+               radius: f32,
+               red: u8,
+               green: u8,
+               blue: u8)
+               -> RoundedRect {
+        return RoundedRect {
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
+            radius: radius,
+        };
+    }
+}
+
+impl Updateable for RoundedRect {
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
+    }
+}
+
+impl vertex::VertexSpecable for RoundedRect {
+    fn get_vertex_specification(&self) -> vertex::VertexSpecification {
+        let radius = self.radius
+            .max(0.0)
+            .min(self.state.width.abs() / 2.0)
+            .min(self.state.height.abs() / 2.0);
+        let inner_x = self.state.width / 2.0 - radius;
+        let inner_y = self.state.height / 2.0 - radius;
+        let quarter = f32::consts::PI / 2.0;
+
+        // top-right, top-left, bottom-left then bottom-right corner, each
+        // a quarter circle about the corner of the inner rectangle
+        let corners = [(inner_x, inner_y),
+                       (-inner_x, inner_y),
+                       (-inner_x, -inner_y),
+                       (inner_x, -inner_y)];
+        let mut outline = Vec::new();
+        for (i, corner) in corners.iter().enumerate() {
+            outline.extend(mesh::arc_points(*corner,
+                                            (radius, radius),
+                                            quarter * (i as f32),
+                                            quarter,
+                                            CORNER_SEGMENTS,
+                                            false));
+        }
+
+        let mut shape = mesh::Mesh::new();
+        shape.add_fan((0.0, 0.0), &outline, true);
+        return self.state.vertex_specification(shape);
+    }
+}
+
+// FilledPath is any outline made with a Path, including concave ones and
+// ones with holes. Like ConvexPolygon, the path is relative to its position
+// and multiplied by its width and height. Filling it is the slow part, so it
+// is only done once, when the shape is made
+pub struct FilledPath {
+    state: ShapeState,
+    shape: mesh::Mesh,
+}
+
+impl FilledPath {
+    pub fn new(xloc: f32,
+               yloc: f32,
+               width: f32,
+               height: f32,
+               path: &tessellate::Path,
+               red: u8,
+               green: u8,
+               blue: u8)
+               -> Result<FilledPath, gerror::Error> {
+        let contours = path.contours();
+        let shape = match contours.split_first() {
+            Some((outline, holes)) => try!(tessellate::triangulate(outline, holes)),
+            None => mesh::Mesh::new(),
+        };
+        return Ok(FilledPath {
+            state: ShapeState::new(xloc, yloc, width, height, Color::new(red, green, blue)),
+            shape: shape,
+        });
+    }
+}
+
+impl Updateable for FilledPath {
+    fn state_mut(&mut self) -> &mut ShapeState {
+        return &mut self.state;
+    }
+}
+
+impl vertex::VertexSpecable for FilledPath {
+    fn get_vertex_specification(&self) -> vertex::VertexSpecification {
+        let shape = self.shape.scaled(self.state.width, self.state.height);
+        return self.state.vertex_specification(shape);
+    }
+}
@@ -0,0 +1,115 @@
+use affine::Affine2D;
+use color_shapes::Updateable;
+use transform::Transform2D;
+
+// SceneGraph groups shapes into a tree of nodes, so that moving, turning or
+// hiding a node does the same to everything under it. Shapes are kept by
+// the caller and attached to nodes by their index in the caller's list
+pub struct SceneGraph {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    parent: Option<usize>,
+    // position is where the node's origin sits inside its parent, and the
+    // transform turns and stretches everything in it about that origin
+    position: (f32, f32),
+    transform: Transform2D,
+    visible: bool,
+    shapes: Vec<usize>,
+}
+
+impl SceneGraph {
+    // new makes a graph holding just the root node, which never moves
+    pub fn new() -> SceneGraph {
+        return SceneGraph {
+            nodes: vec![Node {
+                            parent: None,
+                            position: (0.0, 0.0),
+                            transform: Transform2D::identity(),
+                            visible: true,
+                            shapes: Vec::new(),
+                        }],
+        };
+    }
+
+    pub fn root(&self) -> usize {
+        return 0;
+    }
+
+    // add_node adds a node inside the given one, and gives back its index.
+    // Parents always come before their children, so the tree can be walked
+    // from the top in a single pass, and the parent must already be there
+    pub fn add_node(&mut self, parent: usize, x: f32, y: f32) -> usize {
+        assert!(parent < self.nodes.len(),
+                "node {} can't be a parent as there are only {} nodes",
+                parent,
+                self.nodes.len());
+        self.nodes.push(Node {
+            parent: Some(parent),
+            position: (x, y),
+            transform: Transform2D::identity(),
+            visible: true,
+            shapes: Vec::new(),
+        });
+        return self.nodes.len() - 1;
+    }
+
+    pub fn attach(&mut self, node: usize, shape: usize) {
+        self.nodes[node].shapes.push(shape);
+    }
+
+    pub fn set_position(&mut self, node: usize, x: f32, y: f32) {
+        self.nodes[node].position = (x, y);
+    }
+
+    pub fn set_transform(&mut self, node: usize, transform: Transform2D) {
+        self.nodes[node].transform = transform;
+    }
+
+    pub fn set_visible(&mut self, node: usize, visible: bool) {
+        self.nodes[node].visible = visible;
+    }
+
+    pub fn is_visible(&self, node: usize) -> bool {
+        return self.nodes[node].visible;
+    }
+
+    // world_transforms gives every node's transform from its own space to
+    // the screen, and whether it is shown, which it only is when all of the
+    // nodes above it are too
+    pub fn world_transforms(&self) -> Vec<(Affine2D, bool)> {
+        let mut world: Vec<(Affine2D, bool)> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let (x, y) = node.position;
+            let local = Affine2D::translation(x, y).compose(&node.transform.to_affine());
+            world.push(match node.parent {
+                Some(parent) => {
+                    let (parent_world, parent_visible) = world[parent];
+                    (parent_world.compose(&local), parent_visible && node.visible)
+                }
+                None => (local, node.visible),
+            });
+        }
+        return world;
+    }
+
+    // update gives every attached shape its node's world transform, and
+    // says which of the shapes are shown; shapes not attached to any node
+    // are left as they are and always shown. Every attached shape must be
+    // in the list
+    pub fn update<T: Updateable + ?Sized>(&self, shapes: &mut Vec<Box<T>>) -> Vec<bool> {
+        let mut shown = vec![true; shapes.len()];
+        for (node, &(world, visible)) in self.nodes.iter().zip(self.world_transforms().iter()) {
+            for &shape in node.shapes.iter() {
+                assert!(shape < shapes.len(),
+                        "shape {} is attached but there are only {} shapes",
+                        shape,
+                        shapes.len());
+                shapes[shape].update_parent(world);
+                shown[shape] = visible;
+            }
+        }
+        return shown;
+    }
+}
@@ -58,26 +58,91 @@ pub fn color_pipeline_source() -> RenderingPipelineSource {
                                },
                                VertexAttribute {
                                    var_name: "color",
-                                   stride: 3,
+                                   stride: 4,
+                               },
+                               VertexAttribute {
+                                   var_name: "fill_position",
+                                   stride: 2,
                                }],
-        vertex_width: 5, // this is the width of a ColorVertex: x, y, red, green, blue
+        // this is the width of a ColorVertex: x, y, red, green, blue, alpha, fill_x, fill_y
+        vertex_width: 8,
     };
 }
 
 const COLOR_VS_GLSL: &'static str = r#"#version 150
     in vec2 position;
-    in vec3 color;
-    out vec3 attr_color;
+    in vec4 color;
+    in vec2 fill_position;
+    out vec4 attr_color;
+    out vec2 attr_fill_position;
     void main() {
        attr_color = color;
+       attr_fill_position = fill_position;
        gl_Position = vec4(position, 0.0, 1.0);
     }"#;
 
+// the fill is worked out for each pixel from where it is in the shape's box.
+// fill_kind is 0 for the vertex colors, 1 for corners, 2 for a linear
+// gradient and 3 for a radial one (see fill::load_uniforms)
 const COLOR_FS_GLSL: &'static str = r#"#version 150
-    in vec3 attr_color;
+    in vec4 attr_color;
+    in vec2 attr_fill_position;
+    uniform int fill_kind;
+    uniform vec4 corners[4];
+    uniform vec2 gradient_start;
+    uniform vec2 gradient_end;
+    uniform float gradient_radius;
+    uniform int stop_count;
+    uniform float stop_positions[8];
+    uniform vec4 stop_colors[8];
     out vec4 out_color;
+
+    // stop_color blends between the two stops either side of the position,
+    // holding the first and last stop's colors beyond them
+    vec4 stop_color(float position) {
+       if (stop_count == 0) {
+          return vec4(0.0, 0.0, 0.0, 1.0);
+       }
+       if (position <= stop_positions[0]) {
+          return stop_colors[0];
+       }
+       for (int i = 1; i < stop_count; i++) {
+          if (position <= stop_positions[i]) {
+             float from = stop_positions[i - 1];
+             float to = stop_positions[i];
+             if (to > from) {
+                return mix(stop_colors[i - 1], stop_colors[i], (position - from) / (to - from));
+             }
+             return stop_colors[i];
+          }
+       }
+       return stop_colors[stop_count - 1];
+    }
+
     void main() {
-       out_color = vec4(attr_color, 1.0);
+       vec2 at = attr_fill_position;
+       vec2 inside = clamp(at, 0.0, 1.0);
+       if (fill_kind == 1) {
+          vec4 top = mix(corners[0], corners[1], inside.x);
+          vec4 bottom = mix(corners[3], corners[2], inside.x);
+          out_color = mix(bottom, top, inside.y);
+       } else if (fill_kind == 2) {
+          vec2 along = gradient_end - gradient_start;
+          float length_squared = dot(along, along);
+          float position = 0.0;
+          if (length_squared > 0.0) {
+             position = dot(at - gradient_start, along) / length_squared;
+          }
+          out_color = stop_color(position);
+       } else if (fill_kind == 3) {
+          float position = 1.0;
+          if (gradient_radius > 0.0) {
+             position = distance(at, gradient_start) / gradient_radius;
+          }
+          out_color = stop_color(position);
+       } else {
+          out_color = attr_color;
+       }
     }"#;
 
 // Texture Pipeline Source Definition
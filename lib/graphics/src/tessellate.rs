@@ -0,0 +1,370 @@
+use std::f32;
+
+use gerror;
+use mesh;
+use vertex;
+
+// line segments each curve of a Path is flattened into
+const CURVE_SEGMENTS: u32 = 16;
+
+enum PathCommand {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo(f32, f32, f32, f32),
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    Close,
+}
+
+// Path is an outline made of lines and bezier curves, built up the way a
+// pen would draw it. Every move_to starts a new contour; when a path is
+// filled the first contour is the outline and the rest are holes in it
+pub struct Path {
+    commands: Vec<PathCommand>,
+}
+
+impl Path {
+    pub fn new() -> Path {
+        return Path { commands: Vec::new() };
+    }
+
+    pub fn move_to(mut self, x: f32, y: f32) -> Path {
+        self.commands.push(PathCommand::MoveTo(x, y));
+        return self;
+    }
+
+    pub fn line_to(mut self, x: f32, y: f32) -> Path {
+        self.commands.push(PathCommand::LineTo(x, y));
+        return self;
+    }
+
+    // quad_to curves towards the control point on the way to x, y
+    pub fn quad_to(mut self, control_x: f32, control_y: f32, x: f32, y: f32) -> Path {
+        self.commands.push(PathCommand::QuadTo(control_x, control_y, x, y));
+        return self;
+    }
+
+    pub fn cubic_to(mut self,
+                    control1_x: f32,
+                    control1_y: f32,
+                    control2_x: f32,
+                    control2_y: f32,
+                    x: f32,
+                    y: f32)
+                    -> Path {
+        self.commands
+            .push(PathCommand::CubicTo(control1_x, control1_y, control2_x, control2_y, x, y));
+        return self;
+    }
+
+    // close ends the current contour; contours are always filled as closed
+    // shapes, so this only matters for starting the next one without a move
+    pub fn close(mut self) -> Path {
+        self.commands.push(PathCommand::Close);
+        return self;
+    }
+
+    // contours flattens the path into outlines made of straight lines
+    pub fn contours(&self) -> Vec<Vec<(f32, f32)>> {
+        let mut contours: Vec<Vec<(f32, f32)>> = Vec::new();
+        let mut current: Vec<(f32, f32)> = Vec::new();
+        let mut pen = (0.0, 0.0);
+        for command in self.commands.iter() {
+            match command {
+                &PathCommand::MoveTo(x, y) => {
+                    // a lone point, such as the start left by a close, isn't
+                    // an outline
+                    if current.len() > 1 {
+                        contours.push(current);
+                    }
+                    current = vec![(x, y)];
+                    pen = (x, y);
+                    continue;
+                }
+                &PathCommand::LineTo(x, y) => {
+                    current.push((x, y));
+                    pen = (x, y);
+                }
+                &PathCommand::QuadTo(cx, cy, x, y) => {
+                    for i in 1..(CURVE_SEGMENTS + 1) {
+                        let t = i as f32 / CURVE_SEGMENTS as f32;
+                        let u = 1.0 - t;
+                        current.push((u * u * pen.0 + 2.0 * u * t * cx + t * t * x,
+                                      u * u * pen.1 + 2.0 * u * t * cy + t * t * y));
+                    }
+                    pen = (x, y);
+                }
+                &PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) => {
+                    for i in 1..(CURVE_SEGMENTS + 1) {
+                        let t = i as f32 / CURVE_SEGMENTS as f32;
+                        let u = 1.0 - t;
+                        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                        current.push((a * pen.0 + b * c1x + c * c2x + d * x,
+                                      a * pen.1 + b * c1y + c * c2y + d * y));
+                    }
+                    pen = (x, y);
+                }
+                &PathCommand::Close => {
+                    if current.len() > 1 {
+                        pen = current[0];
+                        contours.push(current);
+                        current = vec![pen];
+                    }
+                    continue;
+                }
+            }
+        }
+        if current.len() > 1 {
+            contours.push(current);
+        }
+        // fewer than three different points enclose nothing
+        return contours.into_iter()
+            .map(|c| without_repeats(c))
+            .filter(|c| c.len() > 2)
+            .collect();
+    }
+}
+
+// tessellate fills an outline (less any holes in it) with triangles and
+// makes a VertexSpecification from them, using place to turn each point into
+// a vertex. The outline and holes can be concave and go round either way,
+// but must not cross themselves or each other
+pub fn tessellate<F>(outline: &[(f32, f32)],
+                     holes: &[Vec<(f32, f32)>],
+                     place: F)
+                     -> Result<vertex::VertexSpecification, gerror::Error>
+    where F: Fn((f32, f32)) -> Box<vertex::Vertex>
+{
+    let shape = try!(triangulate(outline, holes));
+    return Ok(shape.to_vertex_specification(place));
+}
+
+// triangulate fills a polygon by ear clipping: any corner whose triangle
+// has no other corner inside it can be cut off, and there is always one to
+// cut off until a single triangle is left. Holes are first joined up to the
+// outline by a pair of edges running there and back, which turns the
+// polygon with holes into one (touching) outline
+pub fn triangulate(outline: &[(f32, f32)],
+                   holes: &[Vec<(f32, f32)>])
+                   -> Result<mesh::Mesh, gerror::Error> {
+    let mut points = without_repeats(outline.to_vec());
+    if points.len() < 3 {
+        return Err(tessellation_error("an outline needs at least three points"));
+    }
+    // ear clipping works on counter-clockwise outlines with clockwise holes
+    if signed_area(&points) < 0.0 {
+        points.reverse();
+    }
+    let mut ring: Vec<usize> = (0..points.len()).collect();
+
+    let mut sorted_holes: Vec<Vec<(f32, f32)>> = holes.iter()
+        .map(|hole| without_repeats(hole.clone()))
+        .filter(|hole| hole.len() >= 3)
+        .collect();
+    let mut contours = vec![points.clone()];
+    contours.extend(sorted_holes.iter().cloned());
+    if any_edges_cross(&contours) {
+        return Err(tessellation_error("the outline crosses itself or one of its holes"));
+    }
+    // joining holes from right to left means each bridge can only cross
+    // holes that are already part of the outline
+    sorted_holes.sort_by(|a, b| {
+        max_x(b).partial_cmp(&max_x(a)).unwrap_or(::std::cmp::Ordering::Equal)
+    });
+    for hole in sorted_holes.iter_mut() {
+        if signed_area(hole) > 0.0 {
+            hole.reverse();
+        }
+        let start = points.len();
+        points.extend(hole.iter().cloned());
+        let hole_ring: Vec<usize> = (start..points.len()).collect();
+        ring = try!(bridge_hole(&points, ring, hole_ring));
+    }
+
+    let mut shape = mesh::Mesh::new();
+    for point in points.iter() {
+        shape.add_point(*point);
+    }
+
+    let mut remaining = ring;
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let mut clipped = false;
+        for i in 0..count {
+            let (prev, current, next) =
+                (remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]);
+            if is_ear(&points, &remaining, prev, current, next) {
+                shape.add_triangle(prev as i32, current as i32, next as i32);
+                remaining.remove(i);
+                clipped = true;
+                break;
+            }
+        }
+        if !clipped {
+            // corners in a straight line are never ears but can be dropped
+            // without losing any of the area
+            match (0..count).find(|&i| {
+                let (prev, current, next) =
+                    (remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]);
+                cross(points[prev], points[current], points[next]).abs() <= EPSILON
+            }) {
+                Some(i) => {
+                    remaining.remove(i);
+                }
+                None => return Err(tessellation_error("no corner left to cut off")),
+            }
+        }
+    }
+    if remaining.len() == 3 {
+        shape.add_triangle(remaining[0] as i32, remaining[1] as i32, remaining[2] as i32);
+    }
+    return Ok(shape);
+}
+
+const EPSILON: f32 = 1e-9;
+
+fn tessellation_error(message: &str) -> gerror::Error {
+    return gerror::Error::TessellationError(message.to_string());
+}
+
+// bridge_hole joins a hole into the outline through the hole's rightmost
+// point and a point of the outline it can see without crossing any edges
+fn bridge_hole(points: &[(f32, f32)],
+               ring: Vec<usize>,
+               hole: Vec<usize>)
+               -> Result<Vec<usize>, gerror::Error> {
+    let hole_start = (0..hole.len())
+        .fold(0, |best, i| if points[hole[i]].0 > points[hole[best]].0 { i } else { best });
+    let m = points[hole[hole_start]];
+
+    // find the closest edge straight to the right of m
+    let mut closest: Option<(f32, usize)> = None;
+    for i in 0..ring.len() {
+        let a = points[ring[i]];
+        let b = points[ring[(i + 1) % ring.len()]];
+        if (a.1 - m.1) * (b.1 - m.1) > 0.0 || a.1 == b.1 {
+            continue;
+        }
+        let x = a.0 + (m.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+        if x >= m.0 && closest.map(|(best, _)| x < best).unwrap_or(true) {
+            // of the edge's two ends, the one further right is seen first
+            let end = if a.0 > b.0 { i } else { (i + 1) % ring.len() };
+            closest = Some((x, end));
+        }
+    }
+    let (hit_x, mut visible) = try!(closest.ok_or(tessellation_error("a hole is not inside the \
+                                                                      outline")));
+
+    // a reflex corner inside the triangle between m, the hit and the chosen
+    // end would block the view, so go to the one closest to the ray instead
+    let hit = (hit_x, m.1);
+    let p = points[ring[visible]];
+    let mut best_angle = f32::INFINITY;
+    for i in 0..ring.len() {
+        let corner = points[ring[i]];
+        let prev = points[ring[(i + ring.len() - 1) % ring.len()]];
+        let next = points[ring[(i + 1) % ring.len()]];
+        let reflex = cross(prev, corner, next) < 0.0;
+        if i == visible || !reflex || !in_triangle(corner, m, hit, p) {
+            continue;
+        }
+        let angle = (corner.1 - m.1).abs().atan2(corner.0 - m.0);
+        if angle < best_angle {
+            best_angle = angle;
+            visible = i;
+        }
+    }
+
+    // outline up to the visible point, round the hole and back again
+    let mut joined = Vec::new();
+    joined.extend_from_slice(&ring[..visible + 1]);
+    for i in 0..(hole.len() + 1) {
+        joined.push(hole[(hole_start + i) % hole.len()]);
+    }
+    joined.extend_from_slice(&ring[visible..]);
+    return Ok(joined);
+}
+
+// any_edges_cross checks every edge of every contour against every other,
+// ignoring edges that only meet at a shared corner
+fn any_edges_cross(contours: &[Vec<(f32, f32)>]) -> bool {
+    let mut edges = Vec::new();
+    for contour in contours.iter() {
+        for i in 0..contour.len() {
+            edges.push((contour[i], contour[(i + 1) % contour.len()]));
+        }
+    }
+    for i in 0..edges.len() {
+        for j in (i + 1)..edges.len() {
+            let ((a, b), (c, d)) = (edges[i], edges[j]);
+            if a == c || a == d || b == c || b == d {
+                continue;
+            }
+            let (d1, d2) = (cross(a, b, c), cross(a, b, d));
+            let (d3, d4) = (cross(c, d, a), cross(c, d, b));
+            if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+fn is_ear(points: &[(f32, f32)], ring: &[usize], prev: usize, current: usize, next: usize) -> bool {
+    let (a, b, c) = (points[prev], points[current], points[next]);
+    if cross(a, b, c) <= EPSILON {
+        return false;
+    }
+    for &other in ring.iter() {
+        let p = points[other];
+        // the ends of a hole's bridge are in the ring twice, so skip any
+        // corner sitting on one of the triangle's own
+        if p == a || p == b || p == c {
+            continue;
+        }
+        if in_triangle(p, a, b, c) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// cross is positive when a, b, c turn counter-clockwise
+fn cross(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+    return (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+}
+
+fn in_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool {
+    let (d1, d2, d3) = (cross(a, b, p), cross(b, c, p), cross(c, a, p));
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    return !(has_negative && has_positive);
+}
+
+fn signed_area(points: &[(f32, f32)]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let (x1, y1) = points[i];
+        let (x2, y2) = points[(i + 1) % points.len()];
+        area += x1 * y2 - x2 * y1;
+    }
+    return area / 2.0;
+}
+
+fn max_x(points: &[(f32, f32)]) -> f32 {
+    return points.iter().fold(f32::NEG_INFINITY, |max, p| max.max(p.0));
+}
+
+// without_repeats drops points that are the same as the one before, along
+// with a last point that closes the contour back onto the first
+fn without_repeats(points: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    let mut kept: Vec<(f32, f32)> = Vec::new();
+    for point in points.into_iter() {
+        if kept.last().map(|last| *last != point).unwrap_or(true) {
+            kept.push(point);
+        }
+    }
+    if kept.len() > 1 && kept[0] == kept[kept.len() - 1] {
+        kept.pop();
+    }
+    return kept;
+}
@@ -1,42 +1,180 @@
 use gl;
 
+use std::cmp;
 use std::ffi::CString;
 use std::mem;
 
 use gl::types::*;
 
+use gerror;
+
 pub struct TextureSetupDefinition {
     pub width: u32,
     pub height: u32,
     pub data: Vec<u8>,
 }
 
-pub fn texture_load(program: GLuint, tex_def: TextureSetupDefinition) {
+// Texture is an image loaded onto the graphics card, which is freed when it
+// is dropped
+pub struct Texture {
+    id: GLuint,
+    width: u32,
+    height: u32,
+}
+
+impl Texture {
+    // new loads the image and leaves it bound for drawing with. Images
+    // larger than max_size in either direction can't be loaded as a single
+    // texture, and need a TiledTexture instead
+    pub fn new(tex_def: TextureSetupDefinition) -> Result<Texture, gerror::Error> {
+        let max = max_size();
+        if tex_def.width > max || tex_def.height > max {
+            let message = format!("{}x{} is larger than the largest texture, {}x{}",
+                                  tex_def.width,
+                                  tex_def.height,
+                                  max,
+                                  max);
+            return Err(gerror::Error::TextureError(message));
+        }
+        try!(check_length(tex_def.width, tex_def.height, &tex_def.data));
+        unsafe {
+            let mut texture = mem::uninitialized();
+            gl::GenTextures(1, &mut texture);
+
+            clear_errors();
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(gl::TEXTURE_2D,
+                           0,
+                           gl::RGBA as GLint, // GLint internalFormat,
+                           tex_def.width as i32,
+                           tex_def.height as i32,
+                           0,
+                           gl::RGBA as GLenum, // GLenum format,
+                           gl::UNSIGNED_BYTE, // GLenum type,
+                           tex_def.data.as_slice().as_ptr() as *const _);
+
+            gl::TexParameteri(gl::TEXTURE_2D,
+                              gl::TEXTURE_WRAP_S,
+                              gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D,
+                              gl::TEXTURE_WRAP_T,
+                              gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+
+            let texture = Texture {
+                id: texture,
+                width: tex_def.width,
+                height: tex_def.height,
+            };
+            // dropping the texture frees it if it couldn't be loaded
+            try!(check_upload(texture.width, texture.height));
+            return Ok(texture);
+        }
+    }
+
+    // update replaces the whole image with new pixels of the same size,
+    // which is much quicker than loading a new texture
+    pub fn update(&self, data: &[u8]) -> Result<(), gerror::Error> {
+        try!(check_length(self.width, self.height, data));
+        unsafe {
+            clear_errors();
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexSubImage2D(gl::TEXTURE_2D,
+                              0,
+                              0,
+                              0,
+                              self.width as i32,
+                              self.height as i32,
+                              gl::RGBA as GLenum,
+                              gl::UNSIGNED_BYTE,
+                              data.as_ptr() as *const _);
+        }
+        return check_upload(self.width, self.height);
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        return (self.width, self.height);
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+pub fn texture_load(program: GLuint,
+                    tex_def: TextureSetupDefinition)
+                    -> Result<Texture, gerror::Error> {
+    let texture = try!(Texture::new(tex_def));
     unsafe {
-        let mut texture = mem::uninitialized();
-        gl::GenTextures(1, &mut texture);
-
-        gl::BindTexture(gl::TEXTURE_2D, texture);
-        gl::TexImage2D(gl::TEXTURE_2D,
-                       0,
-                       gl::RGBA as GLint, // GLint internalFormat,
-                       tex_def.width as i32,
-                       tex_def.height as i32,
-                       0,
-                       gl::RGBA as GLenum, // GLenum format,
-                       gl::UNSIGNED_BYTE, // GLenum type,
-                       tex_def.data.as_slice().as_ptr() as *const _);
         gl::Uniform1i(gl::GetUniformLocation(program,
                                              CString::new("tex_sample").unwrap().as_ptr()),
                       0);
+    }
+    return Ok(texture);
+}
 
-        gl::TexParameteri(gl::TEXTURE_2D,
-                          gl::TEXTURE_WRAP_S,
-                          gl::CLAMP_TO_EDGE as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D,
-                          gl::TEXTURE_WRAP_T,
-                          gl::CLAMP_TO_EDGE as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+// max_size is the widest and tallest texture the graphics card can hold
+pub fn max_size() -> u32 {
+    let mut size: GLint = 0;
+    unsafe {
+        gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut size);
+    }
+    return cmp::max(size, 1) as u32;
+}
+
+// check_length makes sure there are enough rgba pixels for an image of the
+// size
+pub fn check_length(width: u32, height: u32, data: &[u8]) -> Result<(), gerror::Error> {
+    let needed = width as usize * height as usize * 4;
+    if data.len() < needed {
+        let message = format!("{}x{} needs {} bytes of pixels but there are {}",
+                              width,
+                              height,
+                              needed,
+                              data.len());
+        return Err(gerror::Error::TextureError(message));
+    }
+    return Ok(());
+}
+
+// clear_errors forgets any gl errors left by earlier calls, so that the
+// next check only sees its own
+fn clear_errors() {
+    unsafe {
+        while gl::GetError() != gl::NO_ERROR {}
+    }
+}
+
+// check_upload reports an error if copying pixels to a texture failed, most
+// often because the card ran out of memory
+fn check_upload(width: u32, height: u32) -> Result<(), gerror::Error> {
+    let error = unsafe { gl::GetError() };
+    let reason = match error {
+        gl::NO_ERROR => return Ok(()),
+        gl::OUT_OF_MEMORY => "out of graphics memory".to_string(),
+        gl::INVALID_VALUE => "the size isn't allowed".to_string(),
+        error => format!("gl error {:#x}", error),
+    };
+    return Err(gerror::Error::TextureError(format!("{}x{}: {}", width, height, reason)));
+}
+
+// set_nearest_filter switches the loaded texture between smooth (linear)
+// and blocky (nearest) scaling, which shows each pixel when zoomed in
+pub fn set_nearest_filter(nearest: bool) {
+    let filter = if nearest { gl::NEAREST } else { gl::LINEAR };
+    unsafe {
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter as GLint);
     }
 }
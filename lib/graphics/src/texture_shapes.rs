@@ -55,6 +55,7 @@ impl vertex::VertexSpecable for TexRect {
                                p2: 3,
                                p3: 0,
                            }],
+            fill: None,
         };
     }
 }
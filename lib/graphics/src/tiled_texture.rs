@@ -0,0 +1,198 @@
+use std::cmp;
+
+use gerror;
+use texture;
+use texture_shapes;
+
+// Tile is one texture of a level, covering a rectangle of it in pixels
+struct Tile {
+    texture: texture::Texture,
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+}
+
+// Level is the whole image at one size, split into tiles
+struct Level {
+    width: u32,
+    height: u32,
+    tiles: Vec<Tile>,
+}
+
+impl Level {
+    fn new(width: u32, height: u32, data: &[u8], tile_size: u32) -> Result<Level, gerror::Error> {
+        let mut tiles = Vec::new();
+        for row in 0..(height + tile_size - 1) / tile_size {
+            for column in 0..(width + tile_size - 1) / tile_size {
+                let (left, top) = (column * tile_size, row * tile_size);
+                let tile_width = cmp::min(tile_size, width - left);
+                let tile_height = cmp::min(tile_size, height - top);
+                let texture = try!(texture::Texture::new(texture::TextureSetupDefinition {
+                    width: tile_width,
+                    height: tile_height,
+                    data: cut(data, width, left, top, tile_width, tile_height),
+                }));
+                tiles.push(Tile {
+                    texture: texture,
+                    left: left,
+                    top: top,
+                    width: tile_width,
+                    height: tile_height,
+                });
+            }
+        }
+        return Ok(Level {
+            width: width,
+            height: height,
+            tiles: tiles,
+        });
+    }
+
+    fn update(&self, data: &[u8]) -> Result<(), gerror::Error> {
+        for tile in self.tiles.iter() {
+            let pixels = cut(data, self.width, tile.left, tile.top, tile.width, tile.height);
+            try!(tile.texture.update(&pixels));
+        }
+        return Ok(());
+    }
+}
+
+// TiledTexture is an image of any size on the graphics card. Images larger
+// than a single texture can be are split into a grid of them, and also kept
+// at half the size, a quarter and so on until one texture holds them, so
+// that zoomed out views draw fewer, smaller textures
+pub struct TiledTexture {
+    width: u32,
+    height: u32,
+    // from the full size down
+    levels: Vec<Level>,
+}
+
+impl TiledTexture {
+    // new loads rgba pixels from the top row down. They are only borrowed,
+    // as whoever has them often wants to keep them
+    pub fn new(width: u32, height: u32, data: &[u8]) -> Result<TiledTexture, gerror::Error> {
+        return TiledTexture::with_tile_size(width, height, data, texture::max_size());
+    }
+
+    // with_tile_size splits the image into tiles of at most the given size,
+    // or of the largest texture if that is smaller
+    pub fn with_tile_size(width: u32,
+                          height: u32,
+                          data: &[u8],
+                          tile_size: u32)
+                          -> Result<TiledTexture, gerror::Error> {
+        let tile_size = cmp::max(1, cmp::min(tile_size, texture::max_size()));
+        try!(texture::check_length(width, height, data));
+
+        let mut levels = vec![try!(Level::new(width, height, data, tile_size))];
+        let (mut level_width, mut level_height) = (width, height);
+        let mut level_data: Option<Vec<u8>> = None;
+        while level_width > tile_size || level_height > tile_size {
+            let (smaller_width, smaller_height, smaller) = {
+                let data = level_data.as_ref().map(|d| &d[..]).unwrap_or(data);
+                halve(level_width, level_height, data)
+            };
+            levels.push(try!(Level::new(smaller_width, smaller_height, &smaller, tile_size)));
+            level_width = smaller_width;
+            level_height = smaller_height;
+            level_data = Some(smaller);
+        }
+        return Ok(TiledTexture {
+            width: width,
+            height: height,
+            levels: levels,
+        });
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        return (self.width, self.height);
+    }
+
+    // update replaces the image with new pixels of the same size, at every
+    // level
+    pub fn update(&self, data: &[u8]) -> Result<(), gerror::Error> {
+        try!(texture::check_length(self.width, self.height, data));
+        try!(self.levels[0].update(data));
+        let mut pixels = Vec::new();
+        for pair in self.levels.windows(2) {
+            pixels = match pixels.is_empty() {
+                true => halve(pair[0].width, pair[0].height, data).2,
+                false => halve(pair[0].width, pair[0].height, &pixels).2,
+            };
+            try!(pair[1].update(&pixels));
+        }
+        return Ok(());
+    }
+
+    // parts are the textures to draw to show the image stretched over the
+    // rectangle, each with the part of the rectangle it covers. The level is
+    // the smallest that still has a pixel for each screen pixel, at the
+    // scale (screen pixels to image pixels) the image is shown at, and tiles
+    // that are off the screen are left out
+    pub fn parts(&self,
+                 lower_x: f32,
+                 upper_x: f32,
+                 lower_y: f32,
+                 upper_y: f32,
+                 scale: f32)
+                 -> Vec<(&texture::Texture, texture_shapes::TexRect)> {
+        let mut index = 0;
+        while index + 1 < self.levels.len() && scale * (2u32.pow(index as u32 + 1) as f32) <= 1.0 {
+            index += 1;
+        }
+        let level = &self.levels[index];
+
+        let mut parts = Vec::with_capacity(level.tiles.len());
+        let (level_width, level_height) = (level.width as f32, level.height as f32);
+        let across = |pixels: u32| lower_x + (upper_x - lower_x) * pixels as f32 / level_width;
+        let down = |pixels: u32| upper_y - (upper_y - lower_y) * pixels as f32 / level_height;
+        for tile in level.tiles.iter() {
+            let (left, right) = (across(tile.left), across(tile.left + tile.width));
+            let (top, bottom) = (down(tile.top), down(tile.top + tile.height));
+            if right < -1.0 || left > 1.0 || top < -1.0 || bottom > 1.0 {
+                continue;
+            }
+            parts.push((&tile.texture, texture_shapes::TexRect::new(left, right, bottom, top)));
+        }
+        return parts;
+    }
+}
+
+// cut copies out a rectangle of rgba pixels from an image the given width
+fn cut(data: &[u8], width: u32, left: u32, top: u32, cut_width: u32, cut_height: u32) -> Vec<u8> {
+    // sizes are worked out as usize, as the images this is for can have more
+    // bytes than a u32 can count
+    let row = cut_width as usize * 4;
+    let mut pixels = Vec::with_capacity(row * cut_height as usize);
+    for y in top..top + cut_height {
+        let start = (y as usize * width as usize + left as usize) * 4;
+        pixels.extend_from_slice(&data[start..start + row]);
+    }
+    return pixels;
+}
+
+// halve shrinks an image to half its size, rounding up, averaging each
+// square of four pixels (fewer at an odd edge) into one
+fn halve(width: u32, height: u32, data: &[u8]) -> (u32, u32, Vec<u8>) {
+    let (half_width, half_height) = ((width + 1) / 2, (height + 1) / 2);
+    let mut pixels = Vec::with_capacity(half_width as usize * half_height as usize * 4);
+    for y in 0..half_height {
+        for x in 0..half_width {
+            let xs = [x * 2, cmp::min(x * 2 + 1, width - 1)];
+            let ys = [y * 2, cmp::min(y * 2 + 1, height - 1)];
+            for channel in 0..4usize {
+                let mut sum = 0u32;
+                for &from_y in ys.iter() {
+                    for &from_x in xs.iter() {
+                        let at = (from_y as usize * width as usize + from_x as usize) * 4 + channel;
+                        sum += u32::from(data[at]);
+                    }
+                }
+                pixels.push(((sum + 2) / 4) as u8);
+            }
+        }
+    }
+    return (half_width, half_height, pixels);
+}
@@ -0,0 +1,74 @@
+use affine::Affine2D;
+
+// Transform2D is how a shape is moved, turned and stretched on top of its
+// position and size. Points are scaled and rotated about the pivot, which is
+// relative to the shape's center, and then moved by the translation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    pub translation: (f32, f32),
+    // rotation is counter-clockwise, in radians
+    pub rotation: f32,
+    pub pivot: (f32, f32),
+    pub scale: (f32, f32),
+}
+
+impl Transform2D {
+    pub fn identity() -> Transform2D {
+        return Transform2D {
+            translation: (0.0, 0.0),
+            rotation: 0.0,
+            pivot: (0.0, 0.0),
+            scale: (1.0, 1.0),
+        };
+    }
+
+    pub fn translated(mut self, x: f32, y: f32) -> Transform2D {
+        self.translation = (x, y);
+        return self;
+    }
+
+    pub fn rotated(mut self, radians: f32) -> Transform2D {
+        self.rotation = radians;
+        return self;
+    }
+
+    pub fn scaled(mut self, x: f32, y: f32) -> Transform2D {
+        self.scale = (x, y);
+        return self;
+    }
+
+    // about sets the pivot the shape is rotated and scaled about
+    pub fn about(mut self, x: f32, y: f32) -> Transform2D {
+        self.pivot = (x, y);
+        return self;
+    }
+
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (px, py) = self.pivot;
+        let (sx, sy) = ((x - px) * self.scale.0, (y - py) * self.scale.1);
+        let (sin, cos) = self.rotation.sin_cos();
+        return (px + sx * cos - sy * sin + self.translation.0,
+                py + sx * sin + sy * cos + self.translation.1);
+    }
+
+    pub fn to_affine(&self) -> Affine2D {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (px, py) = self.pivot;
+        let (xx, xy) = (cos * self.scale.0, -sin * self.scale.1);
+        let (yx, yy) = (sin * self.scale.0, cos * self.scale.1);
+        return Affine2D {
+            xx: xx,
+            xy: xy,
+            yx: yx,
+            yy: yy,
+            x_offset: self.translation.0 + px - (xx * px + xy * py),
+            y_offset: self.translation.1 + py - (yx * px + yy * py),
+        };
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Transform2D {
+        return Transform2D::identity();
+    }
+}
@@ -5,6 +5,8 @@ use gl::types::*;
 use std::mem;
 use std::vec;
 
+use fill;
+
 pub struct VertexBuffers {
     vao: GLuint,
     vbo: GLuint,
@@ -16,9 +18,20 @@ pub trait VertexSpecable {
     fn get_vertex_specification(&self) -> VertexSpecification;
 }
 
+// borrowed shapes can be drawn too, so that a list of some of the shapes
+// can be drawn without giving up the rest
+impl<'a, V: VertexSpecable + ?Sized> VertexSpecable for &'a V {
+    fn get_vertex_specification(&self) -> VertexSpecification {
+        return (**self).get_vertex_specification();
+    }
+}
+
 pub struct VertexSpecification {
     pub vertices: Vec<Box<Vertex>>,
     pub elements: Vec<ElementTriangle>,
+    // the gradient the shader fills the triangles with, if they aren't just
+    // the colors of their vertices
+    pub fill: Option<fill::Fill>,
 }
 
 pub trait Vertex {
@@ -31,11 +44,22 @@ pub struct ColorVertex {
     pub red: GLfloat,
     pub green: GLfloat,
     pub blue: GLfloat,
+    pub alpha: GLfloat,
+    // where the vertex is in the box a gradient fill is laid out over
+    pub fill_x: GLfloat,
+    pub fill_y: GLfloat,
 }
 
 impl Vertex for ColorVertex {
     fn get_vec(&self) -> Vec<GLfloat> {
-        return vec![self.x, self.y, self.red, self.green, self.blue];
+        return vec![self.x,
+                    self.y,
+                    self.red,
+                    self.green,
+                    self.blue,
+                    self.alpha,
+                    self.fill_x,
+                    self.fill_y];
     }
 }
 
@@ -103,10 +127,9 @@ impl VertexBuffers {
         return v;
     }
 
-    pub fn gen_vertex_buffers<V: VertexSpecable + ?Sized>(&self, rects: &Vec<Box<V>>) -> GLsizei {
-        let vertex_spec = full_vertex_spec(rects);
-        let vertex_structs = vertex_spec.vertices;
-        let element_triangles = vertex_spec.elements;
+    pub fn gen_vertex_buffers(&self, vertex_spec: &VertexSpecification) -> GLsizei {
+        let vertex_structs = &vertex_spec.vertices;
+        let element_triangles = &vertex_spec.elements;
 
         let mut vertices: vec::Vec<GLfloat> = vec::Vec::new();
         for vertex in vertex_structs.iter() {
@@ -118,6 +141,10 @@ impl VertexBuffers {
         }
 
         let elem_count = elements.len() as GLsizei;
+        if elem_count == 0 {
+            // nothing to draw, and no first element to copy from
+            return 0;
+        }
 
         unsafe {
             // copy the vertex data to the Vertex Buffer Object
@@ -147,12 +174,14 @@ impl VertexBuffers {
     }
 }
 
-fn full_vertex_spec<V: VertexSpecable + ?Sized>(rects: &Vec<Box<V>>) -> VertexSpecification {
+// join puts shapes together to be drawn at once, which they can be as long
+// as they have the same fill, as that of the first is kept
+pub fn join(specs: Vec<VertexSpecification>) -> VertexSpecification {
+    let fill = specs.first().and_then(|spec| spec.fill.clone());
     let mut vertices = vec::Vec::new();
     let mut elements = vec::Vec::new();
     let mut vertex_count_offset = 0;
-    for rect in rects.iter() {
-        let mut vert_spec = rect.get_vertex_specification();
+    for mut vert_spec in specs.into_iter() {
 
         let vertex_count = vert_spec.vertices.len() as i32;
 
@@ -168,5 +197,6 @@ fn full_vertex_spec<V: VertexSpecable + ?Sized>(rects: &Vec<Box<V>>) -> VertexSp
     return VertexSpecification {
         vertices: vertices,
         elements: elements,
+        fill: fill,
     };
 }
@@ -3,6 +3,7 @@ use glfw;
 
 use glfw::Context;
 
+use std::cmp;
 use std::sync::mpsc;
 
 use gerror;
@@ -15,6 +16,13 @@ pub struct Window {
 
 pub enum Action {
     Resized(u32, u32),
+    KeyPressed(glfw::Key),
+    // the cursor's position, in screen coordinates from the top-left corner
+    CursorMoved(f32, f32),
+    // how far the wheel turned, positive away from the user
+    Scrolled(f32),
+    MousePressed,
+    MouseReleased,
     Closed,
 }
 
@@ -51,6 +59,24 @@ impl Window {
         gl::load_with(|symbol| self.window.get_proc_address(symbol) as *const _);
     }
 
+    pub fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    // framebuffer_size is the size of the window in pixels, which can be
+    // more than its size in screen coordinates on high density displays
+    pub fn framebuffer_size(&self) -> (u32, u32) {
+        let (width, height) = self.window.get_framebuffer_size();
+        return (cmp::max(width, 0) as u32, cmp::max(height, 0) as u32);
+    }
+
+    // size is the size of the window in screen coordinates, which cursor
+    // positions and Resized are measured in
+    pub fn size(&self) -> (u32, u32) {
+        let (width, height) = self.window.get_size();
+        return (cmp::max(width, 0) as u32, cmp::max(height, 0) as u32);
+    }
+
     pub fn swap_buffers(&mut self) {
         self.window.swap_buffers();
     }
@@ -58,16 +84,26 @@ impl Window {
     pub fn handle_events(&mut self) -> Option<Action> {
         self.inner_glfw.poll_events();
         for (_, event) in glfw::flush_messages(&self.event_rec) {
-            println!("an event");
             match event {
                 glfw::WindowEvent::Close => return Some(Action::Closed),
                 glfw::WindowEvent::Key(glfw::Key::Q, _, glfw::Action::Release, _) => {
                     return Some(Action::Closed)
                 }
-                glfw::WindowEvent::Size(w, h) => {
-                    println!("I'm resizing");
-                    return Some(Action::Resized(w as u32, h as u32));
+                glfw::WindowEvent::Key(key, _, glfw::Action::Press, _) |
+                glfw::WindowEvent::Key(key, _, glfw::Action::Repeat, _) => {
+                    return Some(Action::KeyPressed(key))
+                }
+                glfw::WindowEvent::CursorPos(x, y) => {
+                    return Some(Action::CursorMoved(x as f32, y as f32))
+                }
+                glfw::WindowEvent::Scroll(_, y) => return Some(Action::Scrolled(y as f32)),
+                glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, glfw::Action::Press, _) => {
+                    return Some(Action::MousePressed)
+                }
+                glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, glfw::Action::Release, _) => {
+                    return Some(Action::MouseReleased)
                 }
+                glfw::WindowEvent::Size(w, h) => return Some(Action::Resized(w as u32, h as u32)),
                 _ => (),
             }
         }
@@ -1,5 +1,10 @@
 use num::rational::Ratio;
 
+use std::cmp;
+
+use request;
+
+#[derive(Clone, Copy)]
 pub struct SimpleColor {
     red: u8,
     green: u8,
@@ -20,6 +25,78 @@ pub fn yellow_to_blue(value: u8) -> [u8; 4] {
                        value);
 }
 
+// palette_color is the color for a value from 0, for points that took the
+// longest to escape, to 255, for those that escaped straight away
+pub fn palette_color(palette: request::Palette, value: u8) -> [u8; 4] {
+    return match palette {
+        request::Palette::YellowToBlue => yellow_to_blue(value),
+        request::Palette::Greyscale => greyscale(value),
+        request::Palette::Fire => fire(value),
+    };
+}
+
+pub fn greyscale(value: u8) -> [u8; 4] {
+    return pixel_color(SimpleColor {
+                           red: 255,
+                           green: 255,
+                           blue: 255,
+                       },
+                       SimpleColor {
+                           red: 0,
+                           green: 0,
+                           blue: 0,
+                       },
+                       value);
+}
+
+// fire runs from white through yellow and red to black
+pub fn fire(value: u8) -> [u8; 4] {
+    let stops = [SimpleColor {
+                     red: 255,
+                     green: 255,
+                     blue: 255,
+                 },
+                 SimpleColor {
+                     red: 255,
+                     green: 220,
+                     blue: 0,
+                 },
+                 SimpleColor {
+                     red: 200,
+                     green: 0,
+                     blue: 0,
+                 },
+                 SimpleColor {
+                     red: 0,
+                     green: 0,
+                     blue: 0,
+                 }];
+    // each of the three steps gets a third of the values
+    let scaled = u32::from(value) * 3;
+    let step = cmp::min(scaled / 255, 2) as usize;
+    return pixel_color(stops[step], stops[step + 1], (scaled - step as u32 * 255) as u8);
+}
+
+// to_linear turns an srgb channel into the amount of light it stands for,
+// which is what has to be averaged for colors to mix as they would really
+pub fn to_linear(channel: u8) -> f64 {
+    let value = f64::from(channel) / 255.0;
+    if value <= 0.04045 {
+        return value / 12.92;
+    }
+    return ((value + 0.055) / 1.055).powf(2.4);
+}
+
+// from_linear turns an amount of light back into an srgb channel
+pub fn from_linear(linear: f64) -> u8 {
+    let value = if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    return (value.max(0.0).min(1.0) * 255.0).round() as u8;
+}
+
 pub fn pixel_color(start: SimpleColor, end: SimpleColor, value: u8) -> [u8; 4] {
     return [lin_interp(start.red, end.red, value),
             lin_interp(start.green, end.green, value),
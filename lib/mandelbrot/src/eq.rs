@@ -1,17 +1,40 @@
 use num::complex::Complex64;
 
+use request;
+
+// mandelbrot_divergence is escape_time for the mandelbrot set as it was
+// first given, failing with how many of the iterations were left when the
+// point escaped
 pub fn mandelbrot_divergence(x: f64, y: f64, iterations: u32) -> Result<(), u32> {
-    return mandelbrot_value(Complex64 { re: 0.0, im: 0.0 },
-                            Complex64::new(x, y),
-                            iterations);
+    return match escape_time(request::Fractal::Mandelbrot, x, y, iterations) {
+        Some((iteration, _)) => Err(iterations - iteration),
+        None => Ok(()),
+    };
 }
 
-fn mandelbrot_value(prev: Complex64, point: Complex64, iteration: u32) -> Result<(), u32> {
-    if iteration <= 0 {
-        return Ok(());
-    } else if prev.norm() > 2.0 {
-        return Err(iteration);
-    } else {
-        return mandelbrot_value((prev * prev) + point, point, iteration - 1);
+// escape_time iterates the fractal's formula for the point x + yi until it
+// leaves the circle of radius 2, giving how many iterations that took and
+// how far out it got, or None if it stays inside for all the iterations
+pub fn escape_time(fractal: request::Fractal,
+                   x: f64,
+                   y: f64,
+                   iterations: u32)
+                   -> Option<(u32, f64)> {
+    let point = Complex64::new(x, y);
+    let mut z = Complex64::new(0.0, 0.0);
+    for iteration in 0..iterations {
+        let norm = z.norm();
+        if norm > 2.0 {
+            return Some((iteration, norm));
+        }
+        z = match fractal {
+            request::Fractal::Mandelbrot => z * z,
+            request::Fractal::BurningShip => {
+                let folded = Complex64::new(z.re.abs(), z.im.abs());
+                folded * folded
+            }
+            request::Fractal::Tricorn => z.conj() * z.conj(),
+        } + point;
     }
+    return None;
 }
@@ -1,48 +1,173 @@
-use csv;
+use diagnostics;
 
 use std::error;
-use std::fmt;
+use std::fs;
+use std::io::Write;
 
-#[derive(Debug)]
-struct MultiFrameError {}
+use frame;
+use request;
 
-impl fmt::Display for MultiFrameError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,
-               "error that signifies multiple frames were present in file parsing")
+// FrameRow is a row of a frame file. Only the frame and its iterations have
+// to be given; the other columns may be left out for the defaults
+#[derive(RustcDecodable)]
+struct FrameRow {
+    screen_width: u32,
+    screen_height: u32,
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+    iterations: u32,
+    y_axis: Option<frame::YAxis>,
+    fractal: Option<request::Fractal>,
+    palette: Option<request::Palette>,
+    coloring: Option<request::Coloring>,
+    supersampling: Option<u32>,
+    sample_pattern: Option<request::Pattern>,
+    adaptive: Option<bool>,
+    output: Option<String>,
+}
+
+// check_frame finds the mistakes in a frame that parsed but can't be drawn
+fn check_frame(frame: &FrameRow) -> Vec<diagnostics::Diagnostic> {
+    let mut problems = Vec::new();
+    let counts = [("screen_width", frame.screen_width),
+                  ("screen_height", frame.screen_height),
+                  ("iterations", frame.iterations)];
+    for &(column, count) in counts.iter() {
+        if count == 0 {
+            problems.push(diagnostics::Diagnostic::new("must be greater than zero".to_string())
+                .in_column(column)
+                .with_value(&count.to_string()));
+        }
+    }
+    if let Some(samples) = frame.supersampling {
+        if samples == 0 || samples > request::MAX_SAMPLES {
+            let message = format!("must be from 1 to {}", request::MAX_SAMPLES);
+            problems.push(diagnostics::Diagnostic::new(message)
+                .in_column("supersampling")
+                .with_value(&samples.to_string()));
+        }
+    }
+    let ranges = [("x_max", frame.x_min, frame.x_max), ("y_max", frame.y_min, frame.y_max)];
+    for &(column, min, max) in ranges.iter() {
+        if !(max > min) {
+            let message = format!("must be greater than the minimum of {}", min);
+            problems.push(diagnostics::Diagnostic::new(message)
+                .in_column(column)
+                .with_value(&max.to_string()));
+        }
     }
+    return problems;
 }
 
-impl error::Error for MultiFrameError {
-    fn description(&self) -> &str {
-        return "must supply only one frame in file";
+fn to_request(row: &FrameRow) -> request::RenderRequest {
+    let frame = frame::Frame::new(row.screen_width,
+                                  row.screen_height,
+                                  row.x_min,
+                                  row.x_max,
+                                  row.y_min,
+                                  row.y_max)
+        .with_y_axis(row.y_axis.unwrap_or(frame::YAxis::Up));
+    let mut request = request::RenderRequest::new(frame).with_iterations(row.iterations);
+    if let Some(fractal) = row.fractal {
+        request = request.with_fractal(fractal);
+    }
+    if let Some(palette) = row.palette {
+        request = request.with_palette(palette);
+    }
+    if let Some(coloring) = row.coloring {
+        request = request.with_coloring(coloring);
+    }
+    let supersampling = request::Supersampling {
+        samples: row.supersampling.unwrap_or(1),
+        pattern: row.sample_pattern.unwrap_or(request::Pattern::Grid),
+        adaptive: row.adaptive.unwrap_or(false),
+    };
+    request = request.with_supersampling(supersampling);
+    if let Some(ref output) = row.output {
+        request = request.with_output(output);
     }
+    return request;
+}
 
-    fn cause(&self) -> Option<&error::Error> {
-        return None;
+pub fn parse_frame(filename: &str) -> Result<request::RenderRequest, Box<error::Error>> {
+    let rows = try!(diagnostics::read_csv::<FrameRow>(filename, diagnostics::no_accepted_values));
+    if rows.len() != 1 {
+        let message = format!("must supply exactly one frame, but found {} rows", rows.len());
+        return Err(Box::new(diagnostics::Diagnostic::new(message).in_file(filename)));
     }
+    let row = &rows[0];
+    let problems = check_frame(&row.value)
+        .into_iter()
+        .map(|problem| problem.in_file(filename).at_row(row.number))
+        .collect();
+    try!(diagnostics::Diagnostics::check(problems));
+    return Ok(to_request(&row.value));
 }
 
-#[derive(RustcDecodable, Copy, Clone)]
-pub struct Frame {
-    pub screen_width: u32,
-    pub screen_height: u32,
-    pub x_min: f64,
-    pub x_max: f64,
-    pub y_min: f64,
-    pub y_max: f64,
-    pub iterations: u32,
+// write_frame saves a request as a frame file, which parse_frame reads back
+// as the same request, apart from its output
+pub fn write_frame(request: &request::RenderRequest,
+                   filename: &str)
+                   -> Result<(), Box<error::Error>> {
+    let frame = &request.frame;
+    let supersampling = &request.supersampling;
+    let contents = format!("screen_width,screen_height,x_min,x_max,y_min,y_max,iterations,\
+                            y_axis,fractal,palette,coloring,supersampling,sample_pattern,\
+                            adaptive\n\
+                            {},{},{},{},{},{},{},{:?},{:?},{:?},{:?},{},{:?},{}\n",
+                           frame.screen_width,
+                           frame.screen_height,
+                           frame.x_min,
+                           frame.x_max,
+                           frame.y_min,
+                           frame.y_max,
+                           request.iterations,
+                           frame.y_axis,
+                           request.fractal,
+                           request.palette,
+                           request.coloring,
+                           supersampling.samples,
+                           supersampling.pattern,
+                           supersampling.adaptive);
+    let mut file = try!(fs::File::create(filename));
+    try!(file.write_all(contents.as_bytes()));
+    return Ok(());
 }
 
-pub fn parse_frame(filename: &str) -> Result<Frame, Box<error::Error>> {
-    let mut reader = try!(csv::Reader::from_file(filename));
-    let mut frames: Vec<Frame> = Vec::new();
-    for record in reader.decode() {
-        let shape_source: Frame = try!(record);
-        frames.push(shape_source);
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use frame;
+    use request;
+
+    use super::parse_frame;
+    use super::write_frame;
+
+    #[test]
+    fn write_frame_reads_back_as_the_same_request() {
+        let frame = frame::Frame::new(640, 480, -0.7512, -0.7478, 0.1018, 0.10435)
+            .with_y_axis(frame::YAxis::Down);
+        let supersampling = request::Supersampling {
+            samples: 3,
+            pattern: request::Pattern::Jittered,
+            adaptive: true,
+        };
+        let request = request::RenderRequest::new(frame)
+            .with_iterations(750)
+            .with_fractal(request::Fractal::BurningShip)
+            .with_palette(request::Palette::Fire)
+            .with_coloring(request::Coloring::Smooth)
+            .with_supersampling(supersampling);
+
+        let filename = env::temp_dir().join("mandelbrot-write-frame-test.csv");
+        let filename = filename.to_str().unwrap();
+        write_frame(&request, filename).unwrap();
+        let read = parse_frame(filename);
+        fs::remove_file(filename).unwrap();
+        assert_eq!(read.unwrap(), request);
     }
-    return match frames.len() {
-        1 => Ok(frames[0]),
-        _ => Err(Box::new(MultiFrameError {})),
-    };
 }
@@ -0,0 +1,141 @@
+// UNZOOMED_HEIGHT is how much of the imaginary axis a frame at zoom 1
+// shows, which is enough for the whole set with a little room around it
+pub const UNZOOMED_HEIGHT: f64 = 2.8;
+
+// YAxis is which way up the imaginary axis is drawn
+#[derive(RustcDecodable, RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub enum YAxis {
+    // y_max at the top of the image, as graphs are drawn
+    Up,
+    // y_min at the top, as the rows of the image are numbered
+    Down,
+}
+
+// Frame is the part of the complex plane to draw and the size of the image
+// to draw it in. Each pixel is sampled at its center, so the edges of the
+// image are the edges of the ranges
+#[derive(RustcDecodable, RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub y_axis: YAxis,
+}
+
+impl Frame {
+    // new draws exactly the ranges given, stretching them to the screen if
+    // they aren't the same shape
+    pub fn new(screen_width: u32,
+               screen_height: u32,
+               x_min: f64,
+               x_max: f64,
+               y_min: f64,
+               y_max: f64)
+               -> Frame {
+        return Frame {
+            screen_width: screen_width,
+            screen_height: screen_height,
+            x_min: x_min,
+            x_max: x_max,
+            y_min: y_min,
+            y_max: y_max,
+            y_axis: YAxis::Up,
+        };
+    }
+
+    // fit draws the ranges without stretching them, widening whichever is
+    // too narrow for the screen's shape about its middle
+    pub fn fit(screen_width: u32,
+               screen_height: u32,
+               x_min: f64,
+               x_max: f64,
+               y_min: f64,
+               y_max: f64)
+               -> Frame {
+        let aspect = f64::from(screen_width) / f64::from(screen_height);
+        let height = (y_max - y_min).max((x_max - x_min) / aspect);
+        let zoom = UNZOOMED_HEIGHT / height;
+        return Frame::centered(screen_width,
+                               screen_height,
+                               (x_min + x_max) / 2.0,
+                               (y_min + y_max) / 2.0,
+                               zoom);
+    }
+
+    // centered draws the point at the center of the screen, zoomed in by
+    // the factor from UNZOOMED_HEIGHT, with the width to match the screen
+    pub fn centered(screen_width: u32,
+                    screen_height: u32,
+                    center_x: f64,
+                    center_y: f64,
+                    zoom: f64)
+                    -> Frame {
+        let aspect = f64::from(screen_width) / f64::from(screen_height);
+        let half_height = UNZOOMED_HEIGHT / zoom / 2.0;
+        let half_width = half_height * aspect;
+        return Frame::new(screen_width,
+                          screen_height,
+                          center_x - half_width,
+                          center_x + half_width,
+                          center_y - half_height,
+                          center_y + half_height);
+    }
+
+    pub fn with_y_axis(mut self, y_axis: YAxis) -> Frame {
+        self.y_axis = y_axis;
+        return self;
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        return ((self.x_min + self.x_max) / 2.0, (self.y_min + self.y_max) / 2.0);
+    }
+
+    pub fn zoom(&self) -> f64 {
+        return UNZOOMED_HEIGHT / (self.y_max - self.y_min);
+    }
+
+    pub fn get_coord_for_pixel(&self, screen_x: u32, screen_y: u32) -> (f64, f64) {
+        return self.get_coord_for_point(f64::from(screen_x) + 0.5, f64::from(screen_y) + 0.5);
+    }
+
+    // get_coord_for_point is the point in the plane at a point on the screen,
+    // measured in pixels from the top-left corner of the image, so that the
+    // middle of the first pixel is at 0.5, 0.5
+    pub fn get_coord_for_point(&self, screen_x: f64, screen_y: f64) -> (f64, f64) {
+        let x_percent = screen_x / f64::from(self.screen_width);
+        let y_percent = screen_y / f64::from(self.screen_height);
+
+        let x = (self.x_max - self.x_min) * x_percent + self.x_min;
+        let y = match self.y_axis {
+            YAxis::Up => self.y_max - (self.y_max - self.y_min) * y_percent,
+            YAxis::Down => (self.y_max - self.y_min) * y_percent + self.y_min,
+        };
+        return (x, y);
+    }
+
+    // get_point_for_coord is where a point in the plane is on the screen,
+    // the other way from get_coord_for_point
+    pub fn get_point_for_coord(&self, x: f64, y: f64) -> (f64, f64) {
+        let x_percent = (x - self.x_min) / (self.x_max - self.x_min);
+        let y_percent = match self.y_axis {
+            YAxis::Up => (self.y_max - y) / (self.y_max - self.y_min),
+            YAxis::Down => (y - self.y_min) / (self.y_max - self.y_min),
+        };
+        return (x_percent * f64::from(self.screen_width),
+                y_percent * f64::from(self.screen_height));
+    }
+
+    // get_pixel_for_coord is the pixel a point in the plane falls in, if it
+    // is on the screen
+    pub fn get_pixel_for_coord(&self, x: f64, y: f64) -> Option<(u32, u32)> {
+        let (screen_x, screen_y) = self.get_point_for_coord(x, y);
+        if !(screen_x >= 0.0 && screen_y >= 0.0 && screen_x < f64::from(self.screen_width) &&
+             screen_y < f64::from(self.screen_height)) {
+            return None;
+        }
+        return Some((screen_x as u32, screen_y as u32));
+    }
+}
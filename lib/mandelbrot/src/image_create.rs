@@ -1,84 +1,136 @@
 use image;
 
+use std::cmp;
 use std::error;
 use std::fs;
 use std::path;
 
 use color_calc;
 use eq;
+use request;
 
-pub fn get_pixel_values(frame: &Frame, raw_x: u32, raw_y: u32, iterations: u32) -> [u8; 4] {
-    let (x, y) = frame.get_coord_for_pixel(raw_x, raw_y);
-    let divergence = eq::mandelbrot_divergence(x, y, iterations);
-    return gen_pixel(divergence, iterations);
-}
+// ADAPTIVE_THRESHOLD is how far apart (in any channel) a pixel's color can
+// be from its neighbours' before adaptive supersampling refines it
+const ADAPTIVE_THRESHOLD: u8 = 8;
 
-pub fn gen_png(frame: Frame, iterations: u32) -> image::RgbaImage {
-    let mut imagebuf: image::RgbaImage = image::ImageBuffer::new(frame.screen_width,
-                                                                 frame.screen_height);
-    for (raw_x, raw_y, pixel) in imagebuf.enumerate_pixels_mut() {
-        *pixel = image::Rgba(get_pixel_values(&frame, raw_x, raw_y, iterations));
+// get_pixel_values is the color of a pixel, averaged over all the samples
+// the request's supersampling takes of it
+pub fn get_pixel_values(request: &request::RenderRequest, raw_x: u32, raw_y: u32) -> [u8; 4] {
+    let supersampling = request.supersampling;
+    let samples = cmp::max(1, cmp::min(supersampling.samples, request::MAX_SAMPLES));
+    if samples == 1 {
+        return get_sample(request, f64::from(raw_x) + 0.5, f64::from(raw_y) + 0.5);
     }
-    return imagebuf;
+    let mut totals = [0.0f64; 3];
+    for sample_y in 0..samples {
+        for sample_x in 0..samples {
+            let (offset_x, offset_y) = match supersampling.pattern {
+                request::Pattern::Grid => (0.5, 0.5),
+                request::Pattern::Jittered => jitter(raw_x, raw_y, sample_y * samples + sample_x),
+            };
+            let screen_x = f64::from(raw_x) + (f64::from(sample_x) + offset_x) / f64::from(samples);
+            let screen_y = f64::from(raw_y) + (f64::from(sample_y) + offset_y) / f64::from(samples);
+            let color = get_sample(request, screen_x, screen_y);
+            for channel in 0..3 {
+                totals[channel] += color_calc::to_linear(color[channel]);
+            }
+        }
+    }
+    let count = f64::from(samples * samples);
+    return [color_calc::from_linear(totals[0] / count),
+            color_calc::from_linear(totals[1] / count),
+            color_calc::from_linear(totals[2] / count),
+            255];
 }
 
-pub fn write_png(prefix: &str, frame: Frame, iterations: u32) -> Result<(), Box<error::Error>> {
-    let ref mut fout = try!(fs::File::create(path::Path::new(&format!("{}_{}x{}_mandelbrot.png",
-                                                                      prefix,
-                                                                      frame.screen_width,
-                                                                      frame.screen_height))));
-    let imagebuf = gen_png(frame, iterations);
-    try!(image::ImageRgba8(imagebuf).save(fout, image::PNG));
-    return Ok(());
+// jitter is a random point in a sample's cell, between 0 and 1 across and
+// down. It is worked out from which sample of which pixel it is, so that
+// rendering the same request always gives the same image
+fn jitter(raw_x: u32, raw_y: u32, sample: u32) -> (f64, f64) {
+    // splitmix64, which scatters neighbouring numbers well
+    let mut hash = (u64::from(raw_x) << 40 ^ u64::from(raw_y) << 16 ^ u64::from(sample))
+        .wrapping_add(0x9e3779b97f4a7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash = hash ^ (hash >> 31);
+    let scale = f64::from(1u32 << 24);
+    return ((hash >> 40) as f64 / scale, ((hash >> 16) & 0xffffff) as f64 / scale);
 }
 
-fn gen_pixel(m_divergence: Result<(), u32>, iterations: u32) -> [u8; 4] {
-    match m_divergence {
-        Ok(()) => {
-            [0, 0, 0, 255]
-        }
-        Err(divergence) => {
-            let div_u8 = (((divergence * 255) / iterations)) as u8;
-            color_calc::yellow_to_blue(div_u8)
+fn get_sample(request: &request::RenderRequest, screen_x: f64, screen_y: f64) -> [u8; 4] {
+    let (x, y) = request.frame.get_coord_for_point(screen_x, screen_y);
+    let escape = eq::escape_time(request.fractal, x, y, request.iterations);
+    return gen_pixel(escape, request);
+}
+
+pub fn gen_png(request: &request::RenderRequest) -> image::RgbaImage {
+    let mut imagebuf: image::RgbaImage = image::ImageBuffer::new(request.frame.screen_width,
+                                                                 request.frame.screen_height);
+    if !request.supersampling.adaptive || request.supersampling.samples <= 1 {
+        for (raw_x, raw_y, pixel) in imagebuf.enumerate_pixels_mut() {
+            *pixel = image::Rgba(get_pixel_values(request, raw_x, raw_y));
         }
+        return imagebuf;
     }
-}
 
-pub struct Frame {
-    screen_width: u32,
-    screen_height: u32,
-    min_x: f64,
-    min_y: f64,
-    plot_width: f64,
-    plot_height: f64,
+    // a first pass takes a single sample of each pixel, and only the pixels
+    // that stand out from it, along edges, are supersampled
+    let rough = gen_png(&request.clone().with_supersampling(request::Supersampling::none()));
+    for (raw_x, raw_y, pixel) in imagebuf.enumerate_pixels_mut() {
+        *pixel = match differs_from_neighbours(&rough, raw_x, raw_y) {
+            true => image::Rgba(get_pixel_values(request, raw_x, raw_y)),
+            false => *rough.get_pixel(raw_x, raw_y),
+        };
+    }
+    return imagebuf;
 }
 
-impl Frame {
-    pub fn new(screen_width: u32,
-               screen_height: u32,
-               min_x: f64,
-               max_x: f64,
-               min_y: f64,
-               max_y: f64)
-               -> Frame {
-        let plot_width = max_x - min_x;
-        let plot_height = max_y - min_y;
-        return Frame {
-            screen_width: screen_width,
-            screen_height: screen_height,
-            min_x: min_x,
-            min_y: min_y,
-            plot_width: plot_width,
-            plot_height: plot_height,
-        };
+fn differs_from_neighbours(imagebuf: &image::RgbaImage, raw_x: u32, raw_y: u32) -> bool {
+    let here = imagebuf.get_pixel(raw_x, raw_y);
+    for y in raw_y.saturating_sub(1)..cmp::min(raw_y + 2, imagebuf.height()) {
+        for x in raw_x.saturating_sub(1)..cmp::min(raw_x + 2, imagebuf.width()) {
+            let there = imagebuf.get_pixel(x, y);
+            for channel in 0..3 {
+                let (a, b) = (here.data[channel], there.data[channel]);
+                if cmp::max(a, b) - cmp::min(a, b) > ADAPTIVE_THRESHOLD {
+                    return true;
+                }
+            }
+        }
     }
+    return false;
+}
 
-    fn get_coord_for_pixel(&self, screen_x: u32, screen_y: u32) -> (f64, f64) {
-        let x_percent = f64::from(screen_x) / f64::from(self.screen_width);
-        let y_percent = f64::from(screen_y) / f64::from(self.screen_height);
+// write_png renders the request and saves it to its output_path, which it
+// gives back
+pub fn write_png(request: &request::RenderRequest) -> Result<String, Box<error::Error>> {
+    let filename = request.output_path();
+    let ref mut fout = try!(fs::File::create(path::Path::new(&filename)));
+    let imagebuf = gen_png(request);
+    try!(image::ImageRgba8(imagebuf).save(fout, image::PNG));
+    return Ok(filename);
+}
 
-        let x = self.plot_width * x_percent + self.min_x;
-        let y = self.plot_height * y_percent + self.min_y;
-        return (x, y);
+fn gen_pixel(escape: Option<(u32, f64)>, request: &request::RenderRequest) -> [u8; 4] {
+    match escape {
+        None => {
+            [0, 0, 0, 255]
+        }
+        Some((iteration, norm)) => {
+            let iterations = request.iterations;
+            let value = match request.coloring {
+                request::Coloring::Bands => {
+                    (u64::from(iterations - iteration) * 255 / u64::from(iterations)) as u8
+                }
+                request::Coloring::Smooth => {
+                    // the iterations it took, plus how far through the next one
+                    // it got, judging by how far past the edge it went
+                    let smooth = f64::from(iteration) + 1.0 - norm.ln().ln() / 2f64.ln();
+                    let shade = (1.0 - smooth / f64::from(iterations)) * 255.0;
+                    shade.max(0.0).min(255.0).round() as u8
+                }
+            };
+            color_calc::palette_color(request.palette, value)
+        }
     }
 }
@@ -1,4 +1,4 @@
-extern crate csv;
+extern crate diagnostics;
 extern crate rustc_serialize;
 
 extern crate image;
@@ -7,11 +7,24 @@ extern crate num;
 mod color_calc;
 mod eq;
 mod fileformat;
+mod frame;
 mod image_create;
+mod request;
 
+pub use eq::escape_time;
 pub use eq::mandelbrot_divergence;
 pub use fileformat::parse_frame;
+pub use fileformat::write_frame;
+pub use frame::Frame;
+pub use frame::UNZOOMED_HEIGHT;
+pub use frame::YAxis;
 pub use image_create::gen_png;
 pub use image_create::write_png;
-pub use image_create::Frame;
 pub use image_create::get_pixel_values;
+pub use request::Coloring;
+pub use request::Fractal;
+pub use request::MAX_SAMPLES;
+pub use request::Palette;
+pub use request::Pattern;
+pub use request::RenderRequest;
+pub use request::Supersampling;
@@ -0,0 +1,161 @@
+use frame;
+
+// Fractal is the formula iterated for each point c, starting from z = 0
+#[derive(RustcDecodable, RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub enum Fractal {
+    // z*z + c
+    Mandelbrot,
+    // (|re z| + i|im z|)^2 + c
+    BurningShip,
+    // conj(z)^2 + c
+    Tricorn,
+}
+
+// Palette is the colors escaping points run through, from those that
+// escape slowest to those that escape straight away
+#[derive(RustcDecodable, RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub enum Palette {
+    YellowToBlue,
+    Greyscale,
+    Fire,
+}
+
+// Coloring is how the number of iterations a point took to escape picks its
+// color
+#[derive(RustcDecodable, RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub enum Coloring {
+    // a step in the palette for each iteration, which shows as bands
+    Bands,
+    // blended between iterations by how far past the edge the point got
+    Smooth,
+}
+
+// Pattern is where in a pixel its samples are taken, each in its own cell of
+// an even grid across the pixel
+#[derive(RustcDecodable, RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    // at the middle of each cell
+    Grid,
+    // somewhere random in each cell, which turns the moire patterns that
+    // fine, regular detail leaves into less noticeable noise
+    Jittered,
+}
+
+// MAX_SAMPLES is the most samples that can be taken across (and down) a
+// pixel
+pub const MAX_SAMPLES: u32 = 8;
+
+// Supersampling is how many samples are taken of each pixel, to smooth the
+// jagged edges of the set. They are averaged as linear light, so that edges
+// blend as they would if the colors were really mixed
+#[derive(RustcDecodable, RustcEncodable, Clone, Copy, Debug, PartialEq)]
+pub struct Supersampling {
+    // across and down, from 1 (a single sample) to MAX_SAMPLES
+    pub samples: u32,
+    pub pattern: Pattern,
+    // only pixels whose color differs from one of their neighbours' are
+    // supersampled, which leaves the flat areas inside and far outside the
+    // set at the cost of one sample
+    pub adaptive: bool,
+}
+
+impl Supersampling {
+    pub fn none() -> Supersampling {
+        return Supersampling::grid(1);
+    }
+
+    pub fn grid(samples: u32) -> Supersampling {
+        return Supersampling {
+            samples: samples,
+            pattern: Pattern::Grid,
+            adaptive: false,
+        };
+    }
+
+    pub fn jittered(samples: u32) -> Supersampling {
+        return Supersampling {
+            samples: samples,
+            pattern: Pattern::Jittered,
+            adaptive: false,
+        };
+    }
+
+    pub fn with_adaptive(mut self, adaptive: bool) -> Supersampling {
+        self.adaptive = adaptive;
+        return self;
+    }
+}
+
+// RenderRequest is everything needed to draw an image of a fractal. Its
+// options start out as the original yellow to blue mandelbrot and are
+// changed with the with_ methods:
+//
+//     RenderRequest::new(frame).with_iterations(500).with_palette(Palette::Fire)
+#[derive(RustcDecodable, RustcEncodable, Clone, Debug, PartialEq)]
+pub struct RenderRequest {
+    pub frame: frame::Frame,
+    pub iterations: u32,
+    pub fractal: Fractal,
+    pub palette: Palette,
+    pub coloring: Coloring,
+    pub supersampling: Supersampling,
+    // where write_png saves the image, if not where output_path says
+    pub output: Option<String>,
+}
+
+impl RenderRequest {
+    pub fn new(frame: frame::Frame) -> RenderRequest {
+        return RenderRequest {
+            frame: frame,
+            iterations: 100,
+            fractal: Fractal::Mandelbrot,
+            palette: Palette::YellowToBlue,
+            coloring: Coloring::Bands,
+            supersampling: Supersampling::none(),
+            output: None,
+        };
+    }
+
+    pub fn with_iterations(mut self, iterations: u32) -> RenderRequest {
+        self.iterations = iterations;
+        return self;
+    }
+
+    pub fn with_fractal(mut self, fractal: Fractal) -> RenderRequest {
+        self.fractal = fractal;
+        return self;
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> RenderRequest {
+        self.palette = palette;
+        return self;
+    }
+
+    pub fn with_coloring(mut self, coloring: Coloring) -> RenderRequest {
+        self.coloring = coloring;
+        return self;
+    }
+
+    pub fn with_supersampling(mut self, supersampling: Supersampling) -> RenderRequest {
+        self.supersampling = supersampling;
+        return self;
+    }
+
+    pub fn with_output(mut self, output: &str) -> RenderRequest {
+        self.output = Some(output.to_string());
+        return self;
+    }
+
+    // output_path is where write_png saves the image: the output if there is
+    // one, or mandelbrot_<width>x<height>.png in the working directory
+    pub fn output_path(&self) -> String {
+        return match self.output {
+            Some(ref output) => output.clone(),
+            None => {
+                format!("mandelbrot_{}x{}.png",
+                        self.frame.screen_width,
+                        self.frame.screen_height)
+            }
+        };
+    }
+}
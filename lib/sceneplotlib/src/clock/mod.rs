@@ -0,0 +1,100 @@
+use std::time;
+
+// Clock tracks how many seconds of animation have played, so that shapes
+// move at the same speed no matter how quickly frames are drawn
+pub struct Clock {
+    step: Step,
+    elapsed: f32,
+    speed: f32,
+    paused: bool,
+}
+
+enum Step {
+    // advance by the wall-clock time since the last tick
+    RealTime(Option<time::Instant>),
+    // advance by the same amount every tick, for deterministic capture
+    Fixed(f32),
+}
+
+impl Clock {
+    pub fn real_time() -> Clock {
+        return Clock::new(Step::RealTime(None));
+    }
+
+    pub fn fixed_step(seconds_per_tick: f32) -> Clock {
+        return Clock::new(Step::Fixed(seconds_per_tick));
+    }
+
+    fn new(step: Step) -> Clock {
+        return Clock {
+            step: step,
+            elapsed: 0.0,
+            speed: 1.0,
+            paused: false,
+        };
+    }
+
+    // tick advances the clock by one frame and returns the elapsed seconds
+    pub fn tick(&mut self) -> f32 {
+        let delta = match self.step {
+            Step::RealTime(ref mut last) => {
+                let now = time::Instant::now();
+                let delta = match *last {
+                    Some(prev) => duration_seconds(now.duration_since(prev)),
+                    None => 0.0,
+                };
+                *last = Some(now);
+                delta
+            }
+            Step::Fixed(seconds_per_tick) => seconds_per_tick,
+        };
+        if !self.paused {
+            self.elapsed = (self.elapsed + delta * self.speed).max(0.0);
+        }
+        return self.elapsed;
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        return self.elapsed;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        return self.paused;
+    }
+
+    pub fn speed(&self) -> f32 {
+        return self.speed;
+    }
+
+    // set_speed scales how fast the animation plays, negative speeds run it backwards
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    // seek jumps to an absolute point in the animation, clamped to the start
+    pub fn seek(&mut self, seconds: f32) {
+        self.elapsed = seconds.max(0.0);
+    }
+
+    // skip jumps forwards (or backwards for negative values) relative to now
+    pub fn skip(&mut self, seconds: f32) {
+        let target = self.elapsed + seconds;
+        self.seek(target);
+    }
+}
+
+fn duration_seconds(d: time::Duration) -> f32 {
+    return d.as_secs() as f32 + (d.subsec_nanos() as f32 / 1_000_000_000.0);
+}
@@ -0,0 +1,87 @@
+use super::ParseError;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Token {
+    Number(f32),
+    Name(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    Comma,
+    LeftParen,
+    RightParen,
+    End,
+}
+
+// Located is a token along with the (zero based) character it starts at
+pub struct Located {
+    pub token: Token,
+    pub position: usize,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Located>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = if c.is_digit(10) || c == '.' {
+            while i < chars.len() && (chars[i].is_digit(10) || chars[i] == '.') {
+                i += 1;
+            }
+            // allow an exponent such as 1e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_digit(10) {
+                    i = j;
+                    while i < chars.len() && chars[i].is_digit(10) {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().cloned().collect();
+            let value = try!(text.parse()
+                .map_err(|_| ParseError::new(start, format!("invalid number '{}'", text))));
+            Token::Number(value)
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Name(chars[start..i].iter().cloned().collect())
+        } else {
+            i += 1;
+            match c {
+                '+' => Token::Plus,
+                '-' => Token::Minus,
+                '*' => Token::Star,
+                '/' => Token::Slash,
+                '%' => Token::Percent,
+                '^' => Token::Caret,
+                ',' => Token::Comma,
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                _ => return Err(ParseError::new(start, format!("unexpected character '{}'", c))),
+            }
+        };
+        tokens.push(Located {
+            token: token,
+            position: start,
+        });
+    }
+    tokens.push(Located {
+        token: Token::End,
+        position: chars.len(),
+    });
+    return Ok(tokens);
+}
@@ -0,0 +1,188 @@
+// Small maths expressions in t (the animation time in seconds), such as
+// `0.3*sin(2*pi*t/4) + 0.1*t`, used to describe motion that the fixed
+// MathFunc shapes can't
+
+mod lexer;
+mod parser;
+
+use std::error;
+use std::fmt;
+
+pub use self::parser::parse;
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Number(f32),
+    Time,
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sqrt,
+    Abs,
+    Exp,
+    Ln,
+    Log10,
+    Floor,
+    Ceil,
+    Round,
+    Fract,
+    Sign,
+    Min,
+    Max,
+    Pow,
+    Clamp,
+}
+
+impl Func {
+    pub fn from_name(name: &str) -> Option<Func> {
+        match name {
+            "sin" => Some(Func::Sin),
+            "cos" => Some(Func::Cos),
+            "tan" => Some(Func::Tan),
+            "asin" => Some(Func::Asin),
+            "acos" => Some(Func::Acos),
+            "atan" => Some(Func::Atan),
+            "atan2" => Some(Func::Atan2),
+            "sqrt" => Some(Func::Sqrt),
+            "abs" => Some(Func::Abs),
+            "exp" => Some(Func::Exp),
+            "ln" => Some(Func::Ln),
+            "log" => Some(Func::Log10),
+            "floor" => Some(Func::Floor),
+            "ceil" => Some(Func::Ceil),
+            "round" => Some(Func::Round),
+            "fract" => Some(Func::Fract),
+            "sign" => Some(Func::Sign),
+            "min" => Some(Func::Min),
+            "max" => Some(Func::Max),
+            "pow" => Some(Func::Pow),
+            "clamp" => Some(Func::Clamp),
+            _ => None,
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        match self {
+            &Func::Atan2 | &Func::Min | &Func::Max | &Func::Pow => 2,
+            &Func::Clamp => 3,
+            _ => 1,
+        }
+    }
+
+    fn apply(&self, args: &[f32]) -> f32 {
+        match self {
+            &Func::Sin => args[0].sin(),
+            &Func::Cos => args[0].cos(),
+            &Func::Tan => args[0].tan(),
+            &Func::Asin => args[0].asin(),
+            &Func::Acos => args[0].acos(),
+            &Func::Atan => args[0].atan(),
+            &Func::Atan2 => args[0].atan2(args[1]),
+            &Func::Sqrt => args[0].sqrt(),
+            &Func::Abs => args[0].abs(),
+            &Func::Exp => args[0].exp(),
+            &Func::Ln => args[0].ln(),
+            &Func::Log10 => args[0].log10(),
+            &Func::Floor => args[0].floor(),
+            &Func::Ceil => args[0].ceil(),
+            &Func::Round => args[0].round(),
+            &Func::Fract => args[0] - args[0].floor(),
+            &Func::Sign => {
+                if args[0] == 0.0 {
+                    0.0
+                } else {
+                    args[0].signum()
+                }
+            }
+            &Func::Min => args[0].min(args[1]),
+            &Func::Max => args[0].max(args[1]),
+            &Func::Pow => args[0].powf(args[1]),
+            // clamp(x, low, high)
+            &Func::Clamp => args[0].max(args[1]).min(args[2]),
+        }
+    }
+}
+
+impl Expr {
+    pub fn eval(&self, t: f32) -> f32 {
+        match self {
+            &Expr::Number(value) => value,
+            &Expr::Time => t,
+            &Expr::Negate(ref inner) => -inner.eval(t),
+            &Expr::Binary(op, ref left, ref right) => {
+                let l = left.eval(t);
+                let r = right.eval(t);
+                match op {
+                    BinaryOp::Add => l + r,
+                    BinaryOp::Subtract => l - r,
+                    BinaryOp::Multiply => l * r,
+                    BinaryOp::Divide => l / r,
+                    BinaryOp::Remainder => l % r,
+                    BinaryOp::Power => l.powf(r),
+                }
+            }
+            &Expr::Call(func, ref args) => {
+                let values: Vec<f32> = args.iter().map(|arg| arg.eval(t)).collect();
+                func.apply(&values)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    // zero based character position the error was found at
+    pub position: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(position: usize, message: String) -> ParseError {
+        return ParseError {
+            position: position,
+            message: message,
+        };
+    }
+
+    // column is the one based column for reporting to people
+    pub fn column(&self) -> usize {
+        return self.position + 1;
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column(), self.message)
+    }
+}
+
+impl error::Error for ParseError {
+    fn description(&self) -> &str {
+        return "could not parse expression";
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        return None;
+    }
+}
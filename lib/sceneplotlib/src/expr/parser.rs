@@ -0,0 +1,251 @@
+use std::f32;
+
+use super::lexer;
+use super::lexer::Token;
+use super::BinaryOp;
+use super::Expr;
+use super::Func;
+use super::ParseError;
+
+// The grammar, from loosest to tightest binding:
+//
+//   sum     = product (('+' | '-') product)*
+//   product = unary (('*' | '/' | '%') unary)*
+//   unary   = ('-' | '+') unary | power
+//   power   = atom ('^' unary)?
+//   atom    = number | name | name '(' sum (',' sum)* ')' | '(' sum ')'
+//
+// so -2^2 is -(2^2) and 2^3^2 is 2^(3^2), as in written maths
+pub fn parse(source: &str) -> Result<Expr, ParseError> {
+    let tokens = try!(lexer::tokenize(source));
+    let mut parser = Parser {
+        tokens: tokens,
+        index: 0,
+    };
+    let expr = try!(parser.sum());
+    return match parser.peek() {
+        &Token::End => Ok(expr),
+        _ => Err(parser.unexpected()),
+    };
+}
+
+struct Parser {
+    tokens: Vec<lexer::Located>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        return &self.tokens[self.index].token;
+    }
+
+    fn position(&self) -> usize {
+        return self.tokens[self.index].position;
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.index].token.clone();
+        if token != Token::End {
+            self.index += 1;
+        }
+        return token;
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), ParseError> {
+        if *self.peek() == expected {
+            self.advance();
+            return Ok(());
+        }
+        return Err(ParseError::new(self.position(),
+                                   format!("expected {} but found {}",
+                                           description,
+                                           describe(self.peek()))));
+    }
+
+    fn unexpected(&self) -> ParseError {
+        return ParseError::new(self.position(),
+                               format!("unexpected {}", describe(self.peek())));
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut left = try!(self.product());
+        loop {
+            let op = match *self.peek() {
+                Token::Plus => BinaryOp::Add,
+                Token::Minus => BinaryOp::Subtract,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = try!(self.product());
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut left = try!(self.unary());
+        loop {
+            let op = match *self.peek() {
+                Token::Star => BinaryOp::Multiply,
+                Token::Slash => BinaryOp::Divide,
+                Token::Percent => BinaryOp::Remainder,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = try!(self.unary());
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        match *self.peek() {
+            Token::Minus => {
+                self.advance();
+                let inner = try!(self.unary());
+                return Ok(Expr::Negate(Box::new(inner)));
+            }
+            Token::Plus => {
+                self.advance();
+                return self.unary();
+            }
+            _ => return self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = try!(self.atom());
+        if *self.peek() == Token::Caret {
+            self.advance();
+            let exponent = try!(self.unary());
+            return Ok(Expr::Binary(BinaryOp::Power, Box::new(base), Box::new(exponent)));
+        }
+        return Ok(base);
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        match self.peek().clone() {
+            Token::Number(value) => {
+                self.advance();
+                Ok(Expr::Number(value))
+            }
+            Token::LeftParen => {
+                self.advance();
+                let inner = try!(self.sum());
+                try!(self.expect(Token::RightParen, "')'"));
+                Ok(inner)
+            }
+            Token::Name(name) => {
+                self.advance();
+                if *self.peek() == Token::LeftParen {
+                    self.advance();
+                    self.call(name, position)
+                } else {
+                    name_value(&name).ok_or(ParseError::new(position,
+                                                            format!("unknown name '{}'", name)))
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn call(&mut self, name: String, position: usize) -> Result<Expr, ParseError> {
+        let func = try!(Func::from_name(&name)
+            .ok_or(ParseError::new(position, format!("unknown function '{}'", name))));
+        let mut args = Vec::new();
+        if *self.peek() != Token::RightParen {
+            args.push(try!(self.sum()));
+            while *self.peek() == Token::Comma {
+                self.advance();
+                args.push(try!(self.sum()));
+            }
+        }
+        try!(self.expect(Token::RightParen, "',' or ')'"));
+        if args.len() != func.arity() {
+            return Err(ParseError::new(position,
+                                       format!("{} takes {} argument(s) but was given {}",
+                                               name,
+                                               func.arity(),
+                                               args.len())));
+        }
+        return Ok(Expr::Call(func, args));
+    }
+}
+
+fn name_value(name: &str) -> Option<Expr> {
+    match name {
+        "t" => Some(Expr::Time),
+        "pi" => Some(Expr::Number(f32::consts::PI)),
+        "tau" => Some(Expr::Number(2.0 * f32::consts::PI)),
+        "e" => Some(Expr::Number(f32::consts::E)),
+        _ => None,
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        &Token::Number(value) => format!("number {}", value),
+        &Token::Name(ref name) => format!("'{}'", name),
+        &Token::Plus => "'+'".to_string(),
+        &Token::Minus => "'-'".to_string(),
+        &Token::Star => "'*'".to_string(),
+        &Token::Slash => "'/'".to_string(),
+        &Token::Percent => "'%'".to_string(),
+        &Token::Caret => "'^'".to_string(),
+        &Token::Comma => "','".to_string(),
+        &Token::LeftParen => "'('".to_string(),
+        &Token::RightParen => "')'".to_string(),
+        &Token::End => "end of expression".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    fn eval(source: &str, t: f32) -> f32 {
+        return parse(source).unwrap().eval(t);
+    }
+
+    fn error_column(source: &str) -> usize {
+        return parse(source).err().unwrap().column();
+    }
+
+    #[test]
+    fn products_bind_tighter_than_sums() {
+        assert_eq!(eval("1 + 2 * 3", 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0), 9.0);
+        assert_eq!(eval("10 - 4 - 3", 0.0), 3.0);
+        assert_eq!(eval("7 % 4 * 2", 0.0), 6.0);
+        assert_eq!(eval("2 * t + 1", 3.0), 7.0);
+    }
+
+    #[test]
+    fn unary_minus_binds_looser_than_powers() {
+        assert_eq!(eval("-2^2", 0.0), -4.0);
+        assert_eq!(eval("2 * -3", 0.0), -6.0);
+        assert_eq!(eval("--1", 0.0), 1.0);
+        assert_eq!(eval("2^-1", 0.0), 0.5);
+    }
+
+    #[test]
+    fn powers_group_from_the_right() {
+        assert_eq!(eval("2^3^2", 0.0), 512.0);
+        assert_eq!(eval("(2^3)^2", 0.0), 64.0);
+    }
+
+    #[test]
+    fn functions_take_comma_separated_arguments() {
+        assert_eq!(eval("max(t, 1)", 0.5), 1.0);
+        assert_eq!(eval("clamp(t, 0, 1)", 2.0), 1.0);
+        assert_eq!(eval("pow(2, 3)", 0.0), 8.0);
+    }
+
+    #[test]
+    fn errors_give_the_column_they_were_found_at() {
+        assert_eq!(error_column("1 + * 2"), 5);
+        assert_eq!(error_column("sin(t"), 6);
+        assert_eq!(error_column("2 * foo(t)"), 5);
+        assert_eq!(error_column("max(t)"), 1);
+        assert_eq!(error_column("1 + 2 $"), 7);
+    }
+}
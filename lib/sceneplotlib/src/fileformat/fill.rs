@@ -0,0 +1,199 @@
+use rustc_serialize::Decodable;
+use rustc_serialize::Decoder;
+use rustc_serialize::Encodable;
+use rustc_serialize::Encoder;
+
+use std::cmp;
+use std::fmt;
+
+// MAX_STOPS is the most stops a gradient can have, which is all the shader
+// that draws them has room for
+const MAX_STOPS: usize = 8;
+
+// red, green, blue and alpha
+pub type Rgba = (u8, u8, u8, u8);
+
+// Gradient is a fill that changes color across a shape, laid out over the
+// box the shape's width and height make, from (0, 0) at the bottom-left to
+// (1, 1) at the top-right. It is written as its kind and where it goes,
+// then its colors, separated by semicolons. Colors are `red green blue`
+// with an optional alpha, and stops start with their position:
+//
+//   linear 0 0 0 1; 0 255 200 150; 1 30 80 160    from the start x y to the
+//                                                 end x y, with 0 at the
+//                                                 start and 1 at the end
+//   radial 0.5 0.5 0.5; 0 255 255 200; 1 255 160 0 0
+//                                                 out from the center x y,
+//                                                 with 1 at the radius
+//   corners; 255 0 0; 0 255 0; 0 0 255; 255 255 0 top-left, top-right,
+//                                                 bottom-right, bottom-left
+#[derive(Clone)]
+pub enum Gradient {
+    Linear {
+        start: (f32, f32),
+        end: (f32, f32),
+        stops: Vec<(f32, Rgba)>,
+    },
+    Radial {
+        center: (f32, f32),
+        radius: f32,
+        stops: Vec<(f32, Rgba)>,
+    },
+    Corners([Rgba; 4]),
+}
+
+impl Gradient {
+    pub fn parse(s: &str) -> Result<Gradient, String> {
+        let parts: Vec<Vec<&str>> = s.split(';').map(|p| p.split_whitespace().collect()).collect();
+        let head = &parts[0];
+        let rest = &parts[1..];
+        let kind = head.get(0).cloned().unwrap_or("");
+        let values = try!(numbers(&head[cmp::min(head.len(), 1)..]));
+        match kind {
+            "linear" => {
+                if values.len() != 4 {
+                    return Err("linear needs a start and end: linear x1 y1 x2 y2".to_string());
+                }
+                if values[0] == values[2] && values[1] == values[3] {
+                    return Err("a linear gradient's start and end must differ".to_string());
+                }
+                return Ok(Gradient::Linear {
+                    start: (values[0], values[1]),
+                    end: (values[2], values[3]),
+                    stops: try!(stops(rest)),
+                });
+            }
+            "radial" => {
+                if values.len() != 3 {
+                    return Err("radial needs a center and radius: radial x y radius".to_string());
+                }
+                if !(values[2] > 0.0) {
+                    return Err("a radial gradient's radius must be greater than zero"
+                        .to_string());
+                }
+                return Ok(Gradient::Radial {
+                    center: (values[0], values[1]),
+                    radius: values[2],
+                    stops: try!(stops(rest)),
+                });
+            }
+            "corners" => {
+                if values.len() != 0 || rest.len() != 4 {
+                    return Err("corners needs four colors: corners; top-left; top-right; \
+                                bottom-right; bottom-left"
+                        .to_string());
+                }
+                let mut corners = [(0, 0, 0, 255); 4];
+                for (i, part) in rest.iter().enumerate() {
+                    corners[i] = try!(color(part));
+                }
+                return Ok(Gradient::Corners(corners));
+            }
+            _ => {
+                return Err(format!("unknown gradient '{}' (expected linear, radial or corners)",
+                                   kind))
+            }
+        }
+    }
+}
+
+fn numbers(values: &[&str]) -> Result<Vec<f32>, String> {
+    let mut parsed = Vec::new();
+    for value in values.iter() {
+        parsed.push(try!(value.parse::<f32>().map_err(|_| format!("invalid number '{}'", value))));
+    }
+    return Ok(parsed);
+}
+
+fn color(values: &[&str]) -> Result<Rgba, String> {
+    let mut channels = Vec::new();
+    for value in values.iter() {
+        channels.push(try!(value.parse::<u8>()
+            .map_err(|_| format!("invalid color value '{}' (expected 0 to 255)", value))));
+    }
+    return match channels.len() {
+        3 => Ok((channels[0], channels[1], channels[2], 255)),
+        4 => Ok((channels[0], channels[1], channels[2], channels[3])),
+        _ => Err(format!("expected 'red green blue' or 'red green blue alpha' but found '{}'",
+                         values.join(" "))),
+    };
+}
+
+fn stops(parts: &[Vec<&str>]) -> Result<Vec<(f32, Rgba)>, String> {
+    let mut stops: Vec<(f32, Rgba)> = Vec::new();
+    for part in parts.iter() {
+        if part.len() == 0 {
+            return Err("expected a stop 'position red green blue' but found nothing".to_string());
+        }
+        let position = try!(part[0]
+            .parse::<f32>()
+            .map_err(|_| format!("invalid stop position '{}'", part[0])));
+        if stops.last().map(|&(last, _)| position < last).unwrap_or(false) {
+            return Err(format!("stop positions must not go down, but {} comes after {}",
+                               position,
+                               stops[stops.len() - 1].0));
+        }
+        stops.push((position, try!(color(&part[1..]))));
+    }
+    if stops.len() < 2 {
+        return Err("a gradient needs at least two stops".to_string());
+    }
+    if stops.len() > MAX_STOPS {
+        return Err(format!("a gradient can have at most {} stops but there are {}",
+                           MAX_STOPS,
+                           stops.len()));
+    }
+    return Ok(stops);
+}
+
+fn write_color(f: &mut fmt::Formatter, (red, green, blue, alpha): Rgba) -> fmt::Result {
+    try!(write!(f, "{} {} {}", red, green, blue));
+    if alpha != 255 {
+        try!(write!(f, " {}", alpha));
+    }
+    return Ok(());
+}
+
+fn write_stops(f: &mut fmt::Formatter, stops: &[(f32, Rgba)]) -> fmt::Result {
+    for &(position, color) in stops.iter() {
+        try!(write!(f, "; {} ", position));
+        try!(write_color(f, color));
+    }
+    return Ok(());
+}
+
+impl fmt::Display for Gradient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Gradient::Linear { start, end, ref stops } => {
+                try!(write!(f, "linear {} {} {} {}", start.0, start.1, end.0, end.1));
+                write_stops(f, stops)
+            }
+            &Gradient::Radial { center, radius, ref stops } => {
+                try!(write!(f, "radial {} {} {}", center.0, center.1, radius));
+                write_stops(f, stops)
+            }
+            &Gradient::Corners(ref corners) => {
+                try!(write!(f, "corners"));
+                for corner in corners.iter() {
+                    try!(write!(f, "; "));
+                    try!(write_color(f, *corner));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Decodable for Gradient {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Gradient, D::Error> {
+        let s = try!(d.read_str());
+        return Gradient::parse(&s).map_err(|e| d.error(&e));
+    }
+}
+
+impl Encodable for Gradient {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(&self.to_string());
+    }
+}
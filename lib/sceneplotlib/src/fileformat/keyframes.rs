@@ -0,0 +1,93 @@
+use diagnostics;
+
+use std::cmp;
+use std::error;
+
+use keyframe;
+
+#[derive(RustcDecodable)]
+struct KeyframeRow {
+    shape: usize,
+    property: keyframe::Property,
+    time: f32,
+    value: f32,
+    ease: keyframe::Ease,
+    repeat: keyframe::Repeat,
+}
+
+// keyframes_filename is where the keyframes for a scene file live: next to
+// it, with `.keys.csv` in place of `.csv` (so `mountain.csv` has its
+// keyframes in `mountain.keys.csv`)
+pub fn keyframes_filename(scene_filename: &str) -> String {
+    let stem = scene_filename.trim_right_matches(".csv");
+    return format!("{}.keys.csv", stem);
+}
+
+// accepted_values are the names the columns that take a fixed set of names
+// will accept, for error messages
+pub fn accepted_values(column: &str) -> Vec<&'static str> {
+    match column {
+        "property" => keyframe::Property::names(),
+        "ease" => keyframe::Ease::names(),
+        "repeat" => keyframe::Repeat::names(),
+        _ => Vec::new(),
+    }
+}
+
+// parse_keyframes reads a keyframe file, where each row is one keyframe of
+// the track for a shape (the row index of the shape in the scene file,
+// starting from 0, so below shape_count) and property, and gathers them up
+// into tracks
+pub fn parse_keyframes(filename: &str,
+                       shape_count: usize)
+                       -> Result<Vec<keyframe::Track>, Box<error::Error>> {
+    let rows = try!(diagnostics::read_csv::<KeyframeRow>(filename, accepted_values));
+    let mut tracks: Vec<keyframe::Track> = Vec::new();
+    for row in rows.into_iter() {
+        let key = row.value;
+        if key.shape >= shape_count {
+            let message = format!("there are only {} shapes", shape_count);
+            return Err(Box::new(diagnostics::Diagnostic::new(message)
+                .in_file(filename)
+                .at_row(row.number)
+                .in_column("shape")
+                .with_value(&key.shape.to_string())));
+        }
+        let keyframe = keyframe::Keyframe {
+            time: key.time,
+            value: key.value,
+            ease: key.ease,
+        };
+
+        match tracks.iter_mut()
+            .find(|track| track.shape == key.shape && track.property == key.property) {
+            Some(track) => {
+                if track.repeat != key.repeat {
+                    let message = format!("every keyframe of a track must use the same \
+                                           repeat mode, and this track is already {}",
+                                          track.repeat.name());
+                    return Err(Box::new(diagnostics::Diagnostic::new(message)
+                        .in_file(filename)
+                        .at_row(row.number)
+                        .in_column("repeat")
+                        .with_value(key.repeat.name())));
+                }
+                track.keyframes.push(keyframe);
+                continue;
+            }
+            None => (),
+        }
+        tracks.push(keyframe::Track {
+            shape: key.shape,
+            property: key.property,
+            repeat: key.repeat,
+            keyframes: vec![keyframe],
+        });
+    }
+
+    for track in tracks.iter_mut() {
+        track.keyframes
+            .sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(cmp::Ordering::Equal));
+    }
+    return Ok(tracks);
+}
@@ -1,5 +1,24 @@
+mod fill;
+mod keyframes;
+mod scene;
 mod shapesource;
 
+pub use self::fill::Gradient;
+pub use self::fill::Rgba;
+pub use self::keyframes::keyframes_filename;
+pub use self::keyframes::parse_keyframes;
+pub use self::scene::SCENE_VERSION;
+pub use self::scene::Scene;
+pub use self::scene::Settings;
+pub use self::scene::load_scene;
+pub use self::scene::parse_scene_file;
+pub use self::scene::scene_files;
+pub use self::scene::to_scene_file;
+pub use self::shapesource::Cap;
+pub use self::shapesource::Join;
+pub use self::shapesource::PathCommand;
+pub use self::shapesource::PathData;
+pub use self::shapesource::Points;
 pub use self::shapesource::ShapeSource;
 pub use self::shapesource::ShapeType;
 pub use self::shapesource::parse_shape_source;
@@ -0,0 +1,533 @@
+// The structured scene format, written in TOML. Where the legacy csv is one
+// flat row per shape, a scene file can set up the window, give defaults that
+// every shape picks up, carry comments, and keep keyframes with the shapes:
+//
+//   version = 1                      # required, the only version so far is 1
+//
+//   [settings]                       # optional, as is every field in it
+//   title = "Mountain"               # window title
+//   width = 600                      # window size in pixels
+//   height = 600
+//   background = [0, 191, 255]       # red, green, blue
+//
+//   [defaults]                       # optional, any field a [[shapes]] has
+//   shape = "tri"
+//   color = [0, 120, 0]
+//
+//   [[shapes]]                       # one per shape, drawn in order
+//   shape = "rect"                   # required (here or in defaults)
+//   x = 0.0                          # default 0.0
+//   y = -0.5                         # default 0.0
+//   width = 2.0                      # required (here or in defaults)
+//   height = 1.0                     # required (here or in defaults)
+//   color = [120, 241, 0]            # required (here or in defaults)
+//   x_func = "sin:0.4:0.25"          # the motion fields all mean the same
+//   y_func = "0.1*sin(2*pi*t)"       # as the csv columns of the same name,
+//   x_scale = 2.3                    # and default to standing still:
+//   y_scale = 0.0                    # lin, a scale of 0.0, a cycle size of
+//   x_cycle_size = 26.0              # 1.0 and an offset of 0.0
+//   y_cycle_size = 1.0
+//   x_offset = 0.0
+//   y_offset = 0.0
+//   segments = 32                    # the shape specific fields are the
+//   radius = 0.1                     # same as the optional csv columns
+//   points = "-0.5 0.0; 0.5 0.0"     # of the same name
+//   thickness = 0.02
+//   join = "round"
+//   cap = "round"
+//   path = "M -1 0 L 0 1 1 0 Z"
+//   rotation_func = "sin"            # the rotation and grow fields are
+//   rotation_scale = 15.0            # also the same as the csv columns,
+//   rotation_cycle_size = 4.0        # and are left out to keep a shape
+//   rotation_offset = 0.0            # upright and its own size
+//   grow_func = "sin"
+//   grow_x = 0.1
+//   grow_y = 0.1
+//   grow_cycle_size = 2.0
+//   grow_offset = 0.0
+//   pivot_x = 0.0
+//   pivot_y = -0.1
+//   name = "tree"                    # groups are named, and the shapes in
+//   parent = "tree"                  # them give the name as their parent
+//   visible = true                   # default true
+//   fill = "linear 0 0 0 1; 0 255 200 150; 1 30 80 160"
+//
+//   [[keyframes]]                    # optional, one per track
+//   shape = 1                        # index into the shapes above
+//   property = "x"
+//   repeat = "pingpong"              # default once
+//   keys = [{ time = 0.0, value = -0.6, ease = "cubic-inout" },
+//           { time = 2.0, value = 0.6 }]   # ease defaults to lin
+
+use diagnostics;
+use toml;
+
+use rustc_serialize::Decodable;
+
+use std::cmp;
+use std::error;
+use std::fs;
+use std::io::Read;
+use std::path;
+
+use keyframe;
+use types;
+
+use super::fill;
+use super::keyframes;
+use super::shapesource;
+
+pub const SCENE_VERSION: u32 = 1;
+
+// Scene is a fully loaded scene, whichever format it was read from
+pub struct Scene {
+    pub settings: Settings,
+    pub shapes: Vec<shapesource::ShapeSource>,
+    pub tracks: Vec<keyframe::Track>,
+}
+
+pub struct Settings {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub background: (u8, u8, u8),
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        return Settings {
+            title: "Parallax Client Demo".to_string(),
+            width: 600,
+            height: 600,
+            background: (230, 25, 51),
+        };
+    }
+}
+
+#[derive(RustcDecodable, RustcEncodable)]
+struct SceneFile {
+    version: u32,
+    settings: Option<SettingsFile>,
+    defaults: Option<ShapeFile>,
+    shapes: Vec<ShapeFile>,
+    keyframes: Option<Vec<TrackFile>>,
+}
+
+#[derive(RustcDecodable, RustcEncodable)]
+struct SettingsFile {
+    title: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    background: Option<Vec<u8>>,
+}
+
+#[derive(RustcDecodable, RustcEncodable, Clone, Default)]
+struct ShapeFile {
+    shape: Option<shapesource::ShapeType>,
+    x: Option<f64>,
+    y: Option<f64>,
+    width: Option<f64>,
+    height: Option<f64>,
+    color: Option<Vec<u8>>,
+    x_func: Option<types::MotionPath>,
+    y_func: Option<types::MotionPath>,
+    x_scale: Option<f64>,
+    y_scale: Option<f64>,
+    x_cycle_size: Option<f64>,
+    y_cycle_size: Option<f64>,
+    x_offset: Option<f64>,
+    y_offset: Option<f64>,
+    segments: Option<u32>,
+    radius: Option<f64>,
+    points: Option<shapesource::Points>,
+    thickness: Option<f64>,
+    join: Option<shapesource::Join>,
+    cap: Option<shapesource::Cap>,
+    path: Option<shapesource::PathData>,
+    rotation_func: Option<types::MotionPath>,
+    rotation_scale: Option<f64>,
+    rotation_cycle_size: Option<f64>,
+    rotation_offset: Option<f64>,
+    grow_func: Option<types::MotionPath>,
+    grow_x: Option<f64>,
+    grow_y: Option<f64>,
+    grow_cycle_size: Option<f64>,
+    grow_offset: Option<f64>,
+    pivot_x: Option<f64>,
+    pivot_y: Option<f64>,
+    name: Option<String>,
+    parent: Option<String>,
+    visible: Option<bool>,
+    fill: Option<fill::Gradient>,
+}
+
+#[derive(RustcDecodable, RustcEncodable)]
+struct TrackFile {
+    shape: usize,
+    property: keyframe::Property,
+    repeat: Option<keyframe::Repeat>,
+    keys: Vec<KeyFile>,
+}
+
+#[derive(RustcDecodable, RustcEncodable)]
+struct KeyFile {
+    time: f64,
+    value: f64,
+    ease: Option<keyframe::Ease>,
+}
+
+// load_scene reads a scene in either format, going by the file extension:
+// `.toml` files are structured scene files and anything else is read as the
+// legacy csv, along with its keyframe file if there is one
+pub fn load_scene(filename: &str) -> Result<Scene, Box<error::Error>> {
+    if filename.ends_with(".toml") {
+        return parse_scene_file(filename);
+    }
+
+    let shapes = try!(shapesource::parse_shape_source(filename));
+    let keyframes_filename = keyframes::keyframes_filename(filename);
+    let tracks = if path::Path::new(&keyframes_filename).exists() {
+        try!(keyframes::parse_keyframes(&keyframes_filename, shapes.len()))
+    } else {
+        Vec::new()
+    };
+    return Ok(Scene {
+        settings: Settings::default(),
+        shapes: shapes,
+        tracks: tracks,
+    });
+}
+
+// scene_files are the files a scene is read from, which load_scene would
+// pick up changes to
+pub fn scene_files(filename: &str) -> Vec<String> {
+    if filename.ends_with(".toml") {
+        return vec![filename.to_string()];
+    }
+    return vec![filename.to_string(), keyframes::keyframes_filename(filename)];
+}
+
+pub fn parse_scene_file(filename: &str) -> Result<Scene, Box<error::Error>> {
+    let in_file = |message: String| diagnostics::Diagnostic::new(message).in_file(filename);
+    let mut contents = String::new();
+    try!(fs::File::open(filename)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| in_file(e.to_string())));
+
+    let mut parser = toml::Parser::new(&contents);
+    let table = try!(parser.parse().ok_or_else(|| {
+        let messages: Vec<String> = parser.errors
+            .iter()
+            .map(|e| {
+                let (line, column) = parser.to_linecol(e.lo);
+                format!("line {}, column {}: {}", line + 1, column + 1, e.desc)
+            })
+            .collect();
+        in_file(messages.join("; "))
+    }));
+
+    let mut decoder = toml::Decoder::new(toml::Value::Table(table));
+    let file = try!(SceneFile::decode(&mut decoder).map_err(|e| {
+        let field = e.field.clone();
+        // the field is given separately, so leave it out of the message
+        let message = toml::DecodeError {
+                field: None,
+                kind: e.kind,
+            }
+            .to_string();
+        match field {
+            Some(field) => in_file(message).in_column(&field).accepting(accepted_values(&field)),
+            None => in_file(message),
+        }
+    }));
+    if file.version != SCENE_VERSION {
+        return Err(Box::new(in_file(format!("unsupported version, expected {}", SCENE_VERSION))
+            .in_column("version")
+            .with_value(&file.version.to_string())));
+    }
+
+    let settings = match file.settings {
+        Some(s) => {
+            let default = Settings::default();
+            Settings {
+                title: s.title.unwrap_or(default.title),
+                width: s.width.unwrap_or(default.width),
+                height: s.height.unwrap_or(default.height),
+                background: match s.background {
+                    Some(rgb) => {
+                        try!(color_triple(&rgb, "settings.background")
+                            .map_err(|d| d.in_file(filename)))
+                    }
+                    None => default.background,
+                },
+            }
+        }
+        None => Settings::default(),
+    };
+
+    let defaults = file.defaults.unwrap_or(ShapeFile::default());
+    let mut shapes = Vec::new();
+    let mut problems = Vec::new();
+    for (index, shape) in file.shapes.iter().enumerate() {
+        let shape = try!(resolve_shape(index, shape, &defaults).map_err(|d| d.in_file(filename)));
+        for problem in shapesource::check_shape(&shape).into_iter() {
+            let column = format!("shapes[{}].{}",
+                                 index,
+                                 problem.column.clone().unwrap_or(String::new()));
+            problems.push(problem.in_file(filename).in_column(&column));
+        }
+        shapes.push(shape);
+    }
+    for (index, problem) in shapesource::check_groups(&shapes).into_iter() {
+        let column = format!("shapes[{}].{}",
+                             index,
+                             problem.column.clone().unwrap_or(String::new()));
+        problems.push(problem.in_file(filename).in_column(&column));
+    }
+    try!(diagnostics::Diagnostics::check(problems));
+
+    let mut tracks = Vec::new();
+    for (index, track) in file.keyframes.unwrap_or(Vec::new()).into_iter().enumerate() {
+        tracks.push(try!(resolve_track(index, track, shapes.len())
+            .map_err(|d| d.in_file(filename))));
+    }
+
+    return Ok(Scene {
+        settings: settings,
+        shapes: shapes,
+        tracks: tracks,
+    });
+}
+
+// resolve_shape fills in a shape's missing fields from the defaults table,
+// and then from the built in defaults for those that can have one
+fn resolve_shape(index: usize,
+                 shape: &ShapeFile,
+                 defaults: &ShapeFile)
+                 -> Result<shapesource::ShapeSource, diagnostics::Diagnostic> {
+    let standing_still = types::MotionPath::Motion(types::Motion::new(types::MathFunc::Linear));
+    let missing = |field: &str| {
+        diagnostics::Diagnostic::new("missing, and there is no default for it".to_string())
+            .in_column(&format!("shapes[{}].{}", index, field))
+    };
+    let kind = try!(shape.shape.or(defaults.shape).ok_or(missing("shape")));
+    // groups aren't drawn, so they can go without a size or color
+    let needed = |field: &str| {
+        match kind {
+            shapesource::ShapeType::Group => Ok(()),
+            _ => Err(missing(field)),
+        }
+    };
+    let color = match shape.color.clone().or(defaults.color.clone()) {
+        Some(color) => color,
+        None => {
+            try!(needed("color"));
+            vec![0, 0, 0]
+        }
+    };
+    let (red, green, blue) = try!(color_triple(&color, &format!("shapes[{}].color", index)));
+    let width = match shape.width.or(defaults.width) {
+        Some(width) => width,
+        None => {
+            try!(needed("width"));
+            0.0
+        }
+    };
+    let height = match shape.height.or(defaults.height) {
+        Some(height) => height,
+        None => {
+            try!(needed("height"));
+            0.0
+        }
+    };
+
+    return Ok(shapesource::ShapeSource {
+        shape: kind,
+        x: shape.x.or(defaults.x).unwrap_or(0.0) as f32,
+        y: shape.y.or(defaults.y).unwrap_or(0.0) as f32,
+        width: width as f32,
+        height: height as f32,
+        x_func: shape.x_func.clone().or(defaults.x_func.clone()).unwrap_or(standing_still.clone()),
+        y_func: shape.y_func.clone().or(defaults.y_func.clone()).unwrap_or(standing_still),
+        x_scale: shape.x_scale.or(defaults.x_scale).unwrap_or(0.0) as f32,
+        y_scale: shape.y_scale.or(defaults.y_scale).unwrap_or(0.0) as f32,
+        x_cycle_size: shape.x_cycle_size.or(defaults.x_cycle_size).unwrap_or(1.0) as f32,
+        y_cycle_size: shape.y_cycle_size.or(defaults.y_cycle_size).unwrap_or(1.0) as f32,
+        x_offset: shape.x_offset.or(defaults.x_offset).unwrap_or(0.0) as f32,
+        y_offset: shape.y_offset.or(defaults.y_offset).unwrap_or(0.0) as f32,
+        red: red,
+        green: green,
+        blue: blue,
+        segments: shape.segments.or(defaults.segments),
+        radius: shape.radius.or(defaults.radius).map(|r| r as f32),
+        points: shape.points.clone().or(defaults.points.clone()),
+        thickness: shape.thickness.or(defaults.thickness).map(|t| t as f32),
+        join: shape.join.or(defaults.join),
+        cap: shape.cap.or(defaults.cap),
+        path: shape.path.clone().or(defaults.path.clone()),
+        rotation_func: shape.rotation_func.clone().or(defaults.rotation_func.clone()),
+        rotation_scale: shape.rotation_scale.or(defaults.rotation_scale).map(|v| v as f32),
+        rotation_cycle_size: shape.rotation_cycle_size
+            .or(defaults.rotation_cycle_size)
+            .map(|v| v as f32),
+        rotation_offset: shape.rotation_offset.or(defaults.rotation_offset).map(|v| v as f32),
+        grow_func: shape.grow_func.clone().or(defaults.grow_func.clone()),
+        grow_x: shape.grow_x.or(defaults.grow_x).map(|v| v as f32),
+        grow_y: shape.grow_y.or(defaults.grow_y).map(|v| v as f32),
+        grow_cycle_size: shape.grow_cycle_size.or(defaults.grow_cycle_size).map(|v| v as f32),
+        grow_offset: shape.grow_offset.or(defaults.grow_offset).map(|v| v as f32),
+        pivot_x: shape.pivot_x.or(defaults.pivot_x).map(|v| v as f32),
+        pivot_y: shape.pivot_y.or(defaults.pivot_y).map(|v| v as f32),
+        // names have to be unique, so they aren't taken from the defaults
+        name: shape.name.clone(),
+        parent: shape.parent.clone().or(defaults.parent.clone()),
+        visible: shape.visible.or(defaults.visible),
+        fill: shape.fill.clone().or(defaults.fill.clone()),
+    });
+}
+
+fn resolve_track(index: usize,
+                 track: TrackFile,
+                 shape_count: usize)
+                 -> Result<keyframe::Track, diagnostics::Diagnostic> {
+    if track.shape >= shape_count {
+        return Err(diagnostics::Diagnostic::new(format!("there are only {} shapes", shape_count))
+            .in_column(&format!("keyframes[{}].shape", index))
+            .with_value(&track.shape.to_string()));
+    }
+    if track.keys.is_empty() {
+        return Err(diagnostics::Diagnostic::new("a track needs at least one key".to_string())
+            .in_column(&format!("keyframes[{}].keys", index)));
+    }
+    let mut keyframes: Vec<keyframe::Keyframe> = track.keys
+        .iter()
+        .map(|key| {
+            keyframe::Keyframe {
+                time: key.time as f32,
+                value: key.value as f32,
+                ease: key.ease.unwrap_or(keyframe::Ease::Linear),
+            }
+        })
+        .collect();
+    keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(cmp::Ordering::Equal));
+    return Ok(keyframe::Track {
+        shape: track.shape,
+        property: track.property,
+        repeat: track.repeat.unwrap_or(keyframe::Repeat::Once),
+        keyframes: keyframes,
+    });
+}
+
+fn color_triple(rgb: &Vec<u8>, field: &str) -> Result<(u8, u8, u8), diagnostics::Diagnostic> {
+    return match rgb.len() {
+        3 => Ok((rgb[0], rgb[1], rgb[2])),
+        _ => {
+            let values: Vec<String> = rgb.iter().map(|v| v.to_string()).collect();
+            Err(diagnostics::Diagnostic::new("must be [red, green, blue]".to_string())
+                .in_column(field)
+                .with_value(&format!("[{}]", values.join(", "))))
+        }
+    };
+}
+
+// accepted_values are the names a field that takes a fixed set of names will
+// accept, going by its path such as shapes.shape or keyframes.ease
+fn accepted_values(field: &str) -> Vec<&'static str> {
+    let name = field.rsplit('.').next().unwrap_or(field);
+    if field.starts_with("keyframes") {
+        return keyframes::accepted_values(name);
+    }
+    return shapesource::accepted_values(name);
+}
+
+// to_scene_file writes a scene out in the structured format, with every
+// field of every shape written out in full; this is how legacy csv scenes
+// are converted
+pub fn to_scene_file(scene: &Scene) -> String {
+    let (red, green, blue) = scene.settings.background;
+    let file = SceneFile {
+        version: SCENE_VERSION,
+        settings: Some(SettingsFile {
+            title: Some(scene.settings.title.clone()),
+            width: Some(scene.settings.width),
+            height: Some(scene.settings.height),
+            background: Some(vec![red, green, blue]),
+        }),
+        defaults: None,
+        shapes: scene.shapes
+            .iter()
+            .map(|s| {
+                ShapeFile {
+                    shape: Some(s.shape),
+                    x: Some(widen(s.x)),
+                    y: Some(widen(s.y)),
+                    width: Some(widen(s.width)),
+                    height: Some(widen(s.height)),
+                    color: Some(vec![s.red, s.green, s.blue]),
+                    x_func: Some(s.x_func.clone()),
+                    y_func: Some(s.y_func.clone()),
+                    x_scale: Some(widen(s.x_scale)),
+                    y_scale: Some(widen(s.y_scale)),
+                    x_cycle_size: Some(widen(s.x_cycle_size)),
+                    y_cycle_size: Some(widen(s.y_cycle_size)),
+                    x_offset: Some(widen(s.x_offset)),
+                    y_offset: Some(widen(s.y_offset)),
+                    segments: s.segments,
+                    radius: s.radius.map(widen),
+                    points: s.points.clone(),
+                    thickness: s.thickness.map(widen),
+                    join: s.join,
+                    cap: s.cap,
+                    path: s.path.clone(),
+                    rotation_func: s.rotation_func.clone(),
+                    rotation_scale: s.rotation_scale.map(widen),
+                    rotation_cycle_size: s.rotation_cycle_size.map(widen),
+                    rotation_offset: s.rotation_offset.map(widen),
+                    grow_func: s.grow_func.clone(),
+                    grow_x: s.grow_x.map(widen),
+                    grow_y: s.grow_y.map(widen),
+                    grow_cycle_size: s.grow_cycle_size.map(widen),
+                    grow_offset: s.grow_offset.map(widen),
+                    pivot_x: s.pivot_x.map(widen),
+                    pivot_y: s.pivot_y.map(widen),
+                    name: s.name.clone(),
+                    parent: s.parent.clone(),
+                    visible: s.visible,
+                    fill: s.fill.clone(),
+                }
+            })
+            .collect(),
+        keyframes: match scene.tracks.len() {
+            0 => None,
+            _ => {
+                Some(scene.tracks
+                    .iter()
+                    .map(|track| {
+                        TrackFile {
+                            shape: track.shape,
+                            property: track.property,
+                            repeat: Some(track.repeat),
+                            keys: track.keyframes
+                                .iter()
+                                .map(|key| {
+                                    KeyFile {
+                                        time: widen(key.time),
+                                        value: widen(key.value),
+                                        ease: Some(key.ease),
+                                    }
+                                })
+                                .collect(),
+                        }
+                    })
+                    .collect())
+            }
+        },
+    };
+    return toml::encode_str(&file);
+}
+
+// widen goes through the shortest decimal form of the value, as widening an
+// f32 directly would write noise like 0.6000000238418579 for 0.6
+fn widen(value: f32) -> f64 {
+    return value.to_string().parse().unwrap_or(value as f64);
+}
@@ -1,51 +1,657 @@
-use csv;
+use diagnostics;
 use rustc_serialize::Decodable;
 use rustc_serialize::Decoder;
+use rustc_serialize::Encodable;
+use rustc_serialize::Encoder;
+
+use std::error;
+use std::f32;
+use std::fmt;
 
 use types;
 
+use super::fill;
+
+#[derive(Clone, Copy)]
 pub enum ShapeType {
     Rect,
     Triangle,
+    // ellipses and circles are drawn with `segments` sides (default 32), and
+    // circles use the width for both their width and height
+    Ellipse,
+    Circle,
+    // polygons are regular with `segments` sides (default 6)
+    Polygon,
+    // convex shapes and lines are drawn through their `points`
+    Convex,
+    Line,
+    // rounded rects have corners of the given `radius`
+    RoundedRect,
+    // paths are filled outlines that can be concave, curved and have holes
+    Path,
+    // groups aren't drawn, but move, turn and hide the shapes that name
+    // them as their `parent`, which are placed relative to the group
+    Group,
+}
+
+impl ShapeType {
+    pub fn from_name(name: &str) -> Option<ShapeType> {
+        match name {
+            "rect" => Some(ShapeType::Rect),
+            "tri" => Some(ShapeType::Triangle),
+            "ellipse" => Some(ShapeType::Ellipse),
+            "circle" => Some(ShapeType::Circle),
+            "polygon" => Some(ShapeType::Polygon),
+            "convex" => Some(ShapeType::Convex),
+            "line" => Some(ShapeType::Line),
+            "roundrect" => Some(ShapeType::RoundedRect),
+            "path" => Some(ShapeType::Path),
+            "group" => Some(ShapeType::Group),
+            _ => None,
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        return vec!["rect", "tri", "ellipse", "circle", "polygon", "convex", "line", "roundrect",
+                    "path", "group"];
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &ShapeType::Rect => "rect",
+            &ShapeType::Triangle => "tri",
+            &ShapeType::Ellipse => "ellipse",
+            &ShapeType::Circle => "circle",
+            &ShapeType::Polygon => "polygon",
+            &ShapeType::Convex => "convex",
+            &ShapeType::Line => "line",
+            &ShapeType::RoundedRect => "roundrect",
+            &ShapeType::Path => "path",
+            &ShapeType::Group => "group",
+        }
+    }
 }
 
 impl Decodable for ShapeType {
     fn decode<D: Decoder>(d: &mut D) -> Result<ShapeType, D::Error> {
         let s = try!(d.read_str());
-        match s.as_str() {
-            "rect" => Ok(ShapeType::Rect),
-            "tri" => Ok(ShapeType::Triangle),
-            _ => Err(d.error("invalid math function")),
+        return ShapeType::from_name(&s).ok_or(d.error("unknown shape type"));
+    }
+}
+
+impl Encodable for ShapeType {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(self.name());
+    }
+}
+
+// Points are the outline of a convex shape or the path of a line, written
+// as `x y` pairs separated by semicolons: `-0.5 -0.5; 0.5 -0.5; 0.0 0.5`
+#[derive(Clone)]
+pub struct Points(pub Vec<(f32, f32)>);
+
+impl Points {
+    pub fn parse(s: &str) -> Result<Points, String> {
+        let mut points = Vec::new();
+        for pair in s.split(';') {
+            let values: Vec<&str> = pair.split_whitespace().collect();
+            if values.len() != 2 {
+                return Err(format!("expected an 'x y' pair but found '{}'", pair.trim()));
+            }
+            let x = try!(values[0].parse().map_err(|_| format!("invalid x '{}'", values[0])));
+            let y = try!(values[1].parse().map_err(|_| format!("invalid y '{}'", values[1])));
+            points.push((x, y));
+        }
+        return Ok(Points(points));
+    }
+
+    // is_convex is true when every corner turns the same way and the turns
+    // add up to a single time around, as a star that crosses itself also
+    // only ever turns the one way
+    pub fn is_convex(&self) -> bool {
+        let points = &self.0;
+        let count = points.len();
+        let mut turn = 0.0;
+        let mut total = 0.0f32;
+        for i in 0..count {
+            let (ax, ay) = points[i];
+            let (bx, by) = points[(i + 1) % count];
+            let (cx, cy) = points[(i + 2) % count];
+            let cross = (bx - ax) * (cy - by) - (by - ay) * (cx - bx);
+            if cross * turn < 0.0 {
+                return false;
+            }
+            if cross != 0.0 {
+                turn = cross;
+            }
+            let dot = (bx - ax) * (cx - bx) + (by - ay) * (cy - by);
+            total += cross.atan2(dot);
+        }
+        return (total.abs() - 2.0 * f32::consts::PI).abs() < 1e-3;
+    }
+}
+
+impl fmt::Display for Points {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pairs: Vec<String> = self.0.iter().map(|&(x, y)| format!("{} {}", x, y)).collect();
+        write!(f, "{}", pairs.join("; "))
+    }
+}
+
+impl Decodable for Points {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Points, D::Error> {
+        let s = try!(d.read_str());
+        return Points::parse(&s).map_err(|e| d.error(&e));
+    }
+}
+
+impl Encodable for Points {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(&self.to_string());
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum PathCommand {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo(f32, f32, f32, f32),
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    Close,
+}
+
+impl PathCommand {
+    fn letter(&self) -> &'static str {
+        match self {
+            &PathCommand::MoveTo(..) => "M",
+            &PathCommand::LineTo(..) => "L",
+            &PathCommand::QuadTo(..) => "Q",
+            &PathCommand::CubicTo(..) => "C",
+            &PathCommand::Close => "Z",
+        }
+    }
+
+    fn values(&self) -> Vec<f32> {
+        match self {
+            &PathCommand::MoveTo(x, y) |
+            &PathCommand::LineTo(x, y) => vec![x, y],
+            &PathCommand::QuadTo(cx, cy, x, y) => vec![cx, cy, x, y],
+            &PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) => vec![c1x, c1y, c2x, c2y, x, y],
+            &PathCommand::Close => Vec::new(),
+        }
+    }
+}
+
+// PathData is the outline of a path shape in the style of an SVG path: `M`
+// moves to a point, `L` draws a line to one, `Q` a quadratic curve through
+// one control point, `C` a cubic curve through two, and `Z` closes the
+// outline. Letters are followed by their x y values, which can repeat for
+// more of the same (more than one point after an `M` are lines):
+// `M -1 0 L -0.5 0.6 0 0.2 Q 0.5 0.8 1 0 Z`. Every `M` after the first
+// starts a hole
+#[derive(Clone)]
+pub struct PathData(pub Vec<PathCommand>);
+
+impl PathData {
+    pub fn parse(s: &str) -> Result<PathData, String> {
+        let mut commands = Vec::new();
+        let tokens: Vec<&str> = s.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| t.len() > 0)
+            .collect();
+        let mut letter = "";
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i].chars().all(|c| c.is_alphabetic()) {
+                letter = tokens[i];
+                i += 1;
+                if letter == "Z" || letter == "z" {
+                    commands.push(PathCommand::Close);
+                    continue;
+                }
+            } else if letter == "" {
+                return Err(format!("expected M to start the path but found '{}'", tokens[i]));
+            }
+            let count = match letter {
+                "M" | "L" => 2,
+                "Q" => 4,
+                "C" => 6,
+                _ => return Err(format!("unknown path command '{}' (expected M, L, Q, C or Z)",
+                                        letter)),
+            };
+            if i + count > tokens.len() {
+                return Err(format!("{} needs {} values", letter, count));
+            }
+            let mut v = Vec::new();
+            for token in tokens[i..i + count].iter() {
+                v.push(try!(token.parse::<f32>()
+                    .map_err(|_| format!("invalid value '{}' after {}", token, letter))));
+            }
+            i += count;
+            commands.push(match letter {
+                "M" => PathCommand::MoveTo(v[0], v[1]),
+                "L" => PathCommand::LineTo(v[0], v[1]),
+                "Q" => PathCommand::QuadTo(v[0], v[1], v[2], v[3]),
+                _ => PathCommand::CubicTo(v[0], v[1], v[2], v[3], v[4], v[5]),
+            });
+            if letter == "M" {
+                letter = "L";
+            }
+        }
+        if commands.len() == 0 {
+            return Err("the path is empty".to_string());
+        }
+        return Ok(PathData(commands));
+    }
+
+    // outline_points is how many points the first outline has, curves
+    // counting their end point only
+    pub fn outline_points(&self) -> usize {
+        return self.0
+            .iter()
+            .enumerate()
+            .take_while(|&(i, c)| match c {
+                &PathCommand::MoveTo(..) => i == 0,
+                &PathCommand::Close => false,
+                _ => true,
+            })
+            .count();
+    }
+}
+
+impl fmt::Display for PathData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let commands: Vec<String> = self.0
+            .iter()
+            .map(|c| {
+                let mut parts = vec![c.letter().to_string()];
+                parts.extend(c.values().iter().map(|v| v.to_string()));
+                parts.join(" ")
+            })
+            .collect();
+        write!(f, "{}", commands.join(" "))
+    }
+}
+
+impl Decodable for PathData {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PathData, D::Error> {
+        let s = try!(d.read_str());
+        return PathData::parse(&s).map_err(|e| d.error(&e));
+    }
+}
+
+impl Encodable for PathData {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(&self.to_string());
+    }
+}
+
+// Join is how a line fills in the outside of its bends
+#[derive(Clone, Copy)]
+pub enum Join {
+    Miter,
+    Bevel,
+    Round,
+}
+
+impl Join {
+    pub fn from_name(name: &str) -> Option<Join> {
+        match name {
+            "miter" => Some(Join::Miter),
+            "bevel" => Some(Join::Bevel),
+            "round" => Some(Join::Round),
+            _ => None,
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        return vec!["miter", "bevel", "round"];
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Join::Miter => "miter",
+            &Join::Bevel => "bevel",
+            &Join::Round => "round",
+        }
+    }
+}
+
+impl Decodable for Join {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Join, D::Error> {
+        let s = try!(d.read_str());
+        return Join::from_name(&s).ok_or(d.error("unknown line join"));
+    }
+}
+
+impl Encodable for Join {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(self.name());
+    }
+}
+
+// Cap is how a line finishes its two ends
+#[derive(Clone, Copy)]
+pub enum Cap {
+    Butt,
+    Square,
+    Round,
+}
+
+impl Cap {
+    pub fn from_name(name: &str) -> Option<Cap> {
+        match name {
+            "butt" => Some(Cap::Butt),
+            "square" => Some(Cap::Square),
+            "round" => Some(Cap::Round),
+            _ => None,
         }
     }
+
+    pub fn names() -> Vec<&'static str> {
+        return vec!["butt", "square", "round"];
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Cap::Butt => "butt",
+            &Cap::Square => "square",
+            &Cap::Round => "round",
+        }
+    }
+}
+
+impl Decodable for Cap {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Cap, D::Error> {
+        let s = try!(d.read_str());
+        return Cap::from_name(&s).ok_or(d.error("unknown line cap"));
+    }
+}
+
+impl Encodable for Cap {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(self.name());
+    }
 }
 
-#[derive(RustcDecodable)]
 pub struct ShapeSource {
     pub shape: ShapeType,
     pub x: f32,
     pub y: f32,
     pub width: f32,
     pub height: f32,
-    pub x_func: types::MathFunc,
-    pub y_func: types::MathFunc,
+    pub x_func: types::MotionPath,
+    pub y_func: types::MotionPath,
     pub x_scale: f32,
     pub y_scale: f32,
-    pub x_cycle_size: u16,
-    pub y_cycle_size: u16,
-    pub x_offset: u16,
-    pub y_offset: u16,
+    // cycle sizes and offsets are measured in seconds
+    pub x_cycle_size: f32,
+    pub y_cycle_size: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
     pub red: u8,
     pub green: u8,
     pub blue: u8,
+    // the rest only mean something for some shapes, and their columns can
+    // be left out of files that don't use them
+    pub segments: Option<u32>,
+    pub radius: Option<f32>,
+    // points are multiplied by the width and height
+    pub points: Option<Points>,
+    pub thickness: Option<f32>,
+    pub join: Option<Join>,
+    pub cap: Option<Cap>,
+    // paths are multiplied by the width and height, like points
+    pub path: Option<PathData>,
+    // rotation and growth are animated the same way as x and y, with the
+    // scale of a rotation in degrees, counter-clockwise. Growth multiplies
+    // the width by 1 + grow_x times the motion, and likewise the height
+    pub rotation_func: Option<types::MotionPath>,
+    pub rotation_scale: Option<f32>,
+    pub rotation_cycle_size: Option<f32>,
+    pub rotation_offset: Option<f32>,
+    pub grow_func: Option<types::MotionPath>,
+    pub grow_x: Option<f32>,
+    pub grow_y: Option<f32>,
+    pub grow_cycle_size: Option<f32>,
+    pub grow_offset: Option<f32>,
+    // the pivot is what the shape turns and grows about, relative to its
+    // center (which is the default)
+    pub pivot_x: Option<f32>,
+    pub pivot_y: Option<f32>,
+    // a group is named so that the rows after it can give it as their
+    // parent, and hiding a group hides everything in it
+    pub name: Option<String>,
+    pub parent: Option<String>,
+    pub visible: Option<bool>,
+    // a fill gradient is used in place of the red, green and blue
+    pub fill: Option<fill::Gradient>,
 }
 
-pub fn parse_shape_source(filename: &str) -> Result<Vec<ShapeSource>, csv::Error> {
-    let mut reader = try!(csv::Reader::from_file(filename));
-    let mut shape_sources: Vec<ShapeSource> = Vec::new();
-    for record in reader.decode() {
-        let shape_source: ShapeSource = try!(record);
-        shape_sources.push(shape_source);
+// shapes are decoded by hand so that group rows, which aren't drawn, can
+// leave their size and color empty
+impl Decodable for ShapeSource {
+    fn decode<D: Decoder>(d: &mut D) -> Result<ShapeSource, D::Error> {
+        return d.read_struct("ShapeSource", 38, |d| {
+            let shape: ShapeType = try!(field(d, "shape", 0));
+            let is_group = match shape {
+                ShapeType::Group => true,
+                _ => false,
+            };
+            return Ok(ShapeSource {
+                shape: shape,
+                x: try!(field(d, "x", 1)),
+                y: try!(field(d, "y", 2)),
+                width: try!(group_optional(d, is_group, "width", 3)),
+                height: try!(group_optional(d, is_group, "height", 4)),
+                x_func: try!(field(d, "x_func", 5)),
+                y_func: try!(field(d, "y_func", 6)),
+                x_scale: try!(field(d, "x_scale", 7)),
+                y_scale: try!(field(d, "y_scale", 8)),
+                x_cycle_size: try!(field(d, "x_cycle_size", 9)),
+                y_cycle_size: try!(field(d, "y_cycle_size", 10)),
+                x_offset: try!(field(d, "x_offset", 11)),
+                y_offset: try!(field(d, "y_offset", 12)),
+                red: try!(group_optional(d, is_group, "red", 13)),
+                green: try!(group_optional(d, is_group, "green", 14)),
+                blue: try!(group_optional(d, is_group, "blue", 15)),
+                segments: try!(field(d, "segments", 16)),
+                radius: try!(field(d, "radius", 17)),
+                points: try!(field(d, "points", 18)),
+                thickness: try!(field(d, "thickness", 19)),
+                join: try!(field(d, "join", 20)),
+                cap: try!(field(d, "cap", 21)),
+                path: try!(field(d, "path", 22)),
+                rotation_func: try!(field(d, "rotation_func", 23)),
+                rotation_scale: try!(field(d, "rotation_scale", 24)),
+                rotation_cycle_size: try!(field(d, "rotation_cycle_size", 25)),
+                rotation_offset: try!(field(d, "rotation_offset", 26)),
+                grow_func: try!(field(d, "grow_func", 27)),
+                grow_x: try!(field(d, "grow_x", 28)),
+                grow_y: try!(field(d, "grow_y", 29)),
+                grow_cycle_size: try!(field(d, "grow_cycle_size", 30)),
+                grow_offset: try!(field(d, "grow_offset", 31)),
+                pivot_x: try!(field(d, "pivot_x", 32)),
+                pivot_y: try!(field(d, "pivot_y", 33)),
+                name: try!(field(d, "name", 34)),
+                parent: try!(field(d, "parent", 35)),
+                visible: try!(field(d, "visible", 36)),
+                fill: try!(field(d, "fill", 37)),
+            });
+        });
+    }
+}
+
+fn field<D: Decoder, T: Decodable>(d: &mut D, name: &str, index: usize) -> Result<T, D::Error> {
+    return d.read_struct_field(name, index, Decodable::decode);
+}
+
+// group_optional reads a field that every shape needs but a group, which
+// gets the default in its place
+fn group_optional<D, T>(d: &mut D, is_group: bool, name: &str, index: usize) -> Result<T, D::Error>
+    where D: Decoder,
+          T: Decodable + Default
+{
+    if is_group {
+        let value: Option<T> = try!(field(d, name, index));
+        return Ok(value.unwrap_or(T::default()));
+    }
+    return field(d, name, index);
+}
+
+// accepted_values are the names the columns that take a fixed set of names
+// will accept, for error messages
+pub fn accepted_values(column: &str) -> Vec<&'static str> {
+    match column {
+        "shape" => ShapeType::names(),
+        "x_func" | "y_func" | "rotation_func" | "grow_func" => types::MathFunc::names(),
+        "join" => Join::names(),
+        "cap" => Cap::names(),
+        _ => Vec::new(),
+    }
+}
+
+// check_shape finds the mistakes in a shape that parsed but can't be drawn
+// sensibly; the diagnostics it gives name the column and value but are left
+// for the caller to say which file and row they are in
+pub fn check_shape(shape: &ShapeSource) -> Vec<diagnostics::Diagnostic> {
+    let mut problems = Vec::new();
+    let problem = |column: &str, message: &str| {
+        diagnostics::Diagnostic::new(message.to_string()).in_column(column)
+    };
+    let sizes = [("width", shape.width), ("height", shape.height)];
+    for &(column, size) in sizes.iter() {
+        if size < 0.0 {
+            problems.push(problem(column, "size must not be negative")
+                .with_value(&size.to_string()));
+        }
+    }
+    match (shape.shape, &shape.points) {
+        (ShapeType::Convex, &None) |
+        (ShapeType::Line, &None) => {
+            problems.push(problem("points", &format!("a {} needs points", shape.shape.name())))
+        }
+        (ShapeType::Convex, &Some(ref points)) => {
+            if points.0.len() < 3 || !points.is_convex() {
+                problems.push(problem("points", "points must make a convex outline")
+                    .with_value(&points.to_string()));
+            }
+        }
+        (ShapeType::Line, &Some(ref points)) => {
+            if points.0.len() < 2 {
+                problems.push(problem("points", "a line needs at least two points")
+                    .with_value(&points.to_string()));
+            }
+        }
+        _ => (),
+    }
+    match (shape.shape, &shape.path) {
+        (ShapeType::Path, &None) => problems.push(problem("path", "a path shape needs a path")),
+        (ShapeType::Path, &Some(ref path)) => {
+            if let Some(&PathCommand::MoveTo(..)) = path.0.first() {
+                if path.outline_points() < 3 {
+                    problems.push(problem("path", "the outline needs at least three points")
+                        .with_value(&path.to_string()));
+                }
+            } else {
+                problems.push(problem("path", "a path must start with M")
+                    .with_value(&path.to_string()));
+            }
+        }
+        _ => (),
+    }
+    if let Some(segments) = shape.segments {
+        if segments < 3 {
+            problems.push(problem("segments", "must be at least 3")
+                .with_value(&segments.to_string()));
+        }
+    }
+    if let Some(radius) = shape.radius {
+        if radius < 0.0 {
+            problems.push(problem("radius", "must not be negative")
+                .with_value(&radius.to_string()));
+        }
+    }
+    if let Some(thickness) = shape.thickness {
+        if !(thickness > 0.0) {
+            problems.push(problem("thickness", "must be greater than zero")
+                .with_value(&thickness.to_string()));
+        }
+    }
+    // named motions divide time by the cycle size, so a cycle of zero would
+    // put NaN into the position; expressions don't use it
+    let standing_still = types::MotionPath::Motion(types::Motion::new(types::MathFunc::Linear));
+    let cycles = [("x_cycle_size", &shape.x_func, shape.x_cycle_size),
+                  ("y_cycle_size", &shape.y_func, shape.y_cycle_size),
+                  ("rotation_cycle_size",
+                   shape.rotation_func.as_ref().unwrap_or(&standing_still),
+                   shape.rotation_cycle_size.unwrap_or(1.0)),
+                  ("grow_cycle_size",
+                   shape.grow_func.as_ref().unwrap_or(&standing_still),
+                   shape.grow_cycle_size.unwrap_or(1.0))];
+    for &(column, func, cycle_size) in cycles.iter() {
+        if let &types::MotionPath::Motion(_) = func {
+            if !(cycle_size > 0.0) {
+                problems.push(problem(column, "cycle size must be greater than zero")
+                    .with_value(&cycle_size.to_string()));
+            }
+        }
+    }
+    return problems;
+}
+
+// check_groups finds parents that don't name a group before the shape, and
+// groups without a name or with one that is already taken, giving the index
+// of the shape each problem is in
+pub fn check_groups(shapes: &[ShapeSource]) -> Vec<(usize, diagnostics::Diagnostic)> {
+    let mut problems = Vec::new();
+    let mut groups: Vec<&str> = Vec::new();
+    for (i, shape) in shapes.iter().enumerate() {
+        if let Some(ref parent) = shape.parent {
+            if !groups.contains(&parent.as_str()) {
+                problems.push((i,
+                               diagnostics::Diagnostic::new("no group of that name comes \
+                                                             before this shape"
+                                       .to_string())
+                                   .in_column("parent")
+                                   .with_value(parent)));
+            }
+        }
+        match (shape.shape, &shape.name) {
+            (ShapeType::Group, &None) => {
+                problems.push((i,
+                               diagnostics::Diagnostic::new("a group needs a name".to_string())
+                                   .in_column("name")))
+            }
+            (ShapeType::Group, &Some(ref name)) => {
+                if groups.contains(&name.as_str()) {
+                    problems.push((i,
+                                   diagnostics::Diagnostic::new("there is already a group \
+                                                                 with this name"
+                                           .to_string())
+                                       .in_column("name")
+                                       .with_value(name)));
+                }
+                groups.push(name);
+            }
+            _ => (),
+        }
+    }
+    return problems;
+}
+
+pub fn parse_shape_source(filename: &str) -> Result<Vec<ShapeSource>, Box<error::Error>> {
+    let rows = try!(diagnostics::read_csv::<ShapeSource>(filename, accepted_values));
+    let mut problems = Vec::new();
+    for row in rows.iter() {
+        for problem in check_shape(&row.value).into_iter() {
+            problems.push(problem.in_file(filename).at_row(row.number));
+        }
+    }
+    let shapes: Vec<ShapeSource> = rows.into_iter().map(|row| row.value).collect();
+    for (i, problem) in check_groups(&shapes).into_iter() {
+        // the header is row 1
+        problems.push(problem.in_file(filename).at_row(i + 2));
     }
-    return Ok(shape_sources);
+    try!(diagnostics::Diagnostics::check(problems));
+    return Ok(shapes);
 }
@@ -0,0 +1,259 @@
+use rustc_serialize::Decodable;
+use rustc_serialize::Decoder;
+use rustc_serialize::Encodable;
+use rustc_serialize::Encoder;
+
+use types;
+
+// Property is which part of a shape a keyframe track animates
+#[derive(Clone, Copy, PartialEq)]
+pub enum Property {
+    X,
+    Y,
+    Width,
+    Height,
+    // rotation is counter-clockwise, in degrees
+    Rotation,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Property {
+    pub fn from_name(name: &str) -> Option<Property> {
+        match name {
+            "x" => Some(Property::X),
+            "y" => Some(Property::Y),
+            "width" => Some(Property::Width),
+            "height" => Some(Property::Height),
+            "rotation" => Some(Property::Rotation),
+            "red" => Some(Property::Red),
+            "green" => Some(Property::Green),
+            "blue" => Some(Property::Blue),
+            "alpha" => Some(Property::Alpha),
+            _ => None,
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        return vec!["x", "y", "width", "height", "rotation", "red", "green", "blue", "alpha"];
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Property::X => "x",
+            &Property::Y => "y",
+            &Property::Width => "width",
+            &Property::Height => "height",
+            &Property::Rotation => "rotation",
+            &Property::Red => "red",
+            &Property::Green => "green",
+            &Property::Blue => "blue",
+            &Property::Alpha => "alpha",
+        }
+    }
+}
+
+// Repeat is what a track does once time passes its last keyframe
+#[derive(Clone, Copy, PartialEq)]
+pub enum Repeat {
+    // hold the last value
+    Once,
+    // jump back to the first keyframe and play again
+    Loop,
+    // play backwards to the first keyframe, then forwards again
+    PingPong,
+}
+
+impl Repeat {
+    pub fn from_name(name: &str) -> Option<Repeat> {
+        match name {
+            "once" => Some(Repeat::Once),
+            "loop" => Some(Repeat::Loop),
+            "pingpong" => Some(Repeat::PingPong),
+            _ => None,
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        return vec!["once", "loop", "pingpong"];
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Repeat::Once => "once",
+            &Repeat::Loop => "loop",
+            &Repeat::PingPong => "pingpong",
+        }
+    }
+}
+
+// Ease is how a track moves from one keyframe's value to the next
+#[derive(Clone, Copy)]
+pub enum Ease {
+    // hold the value until the next keyframe
+    Step,
+    Linear,
+    Curve(types::Easing),
+}
+
+impl Ease {
+    pub fn from_name(name: &str) -> Option<Ease> {
+        match name {
+            "step" => Some(Ease::Step),
+            "lin" => Some(Ease::Linear),
+            _ => types::Easing::from_name(name).map(Ease::Curve),
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        let mut names = vec!["step", "lin"];
+        names.extend(types::Easing::names());
+        return names;
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Ease::Step => "step",
+            &Ease::Linear => "lin",
+            &Ease::Curve(easing) => easing.name(),
+        }
+    }
+
+    fn apply(&self, p: f32) -> f32 {
+        match self {
+            &Ease::Step => if p < 1.0 { 0.0 } else { 1.0 },
+            &Ease::Linear => p,
+            &Ease::Curve(easing) => easing.apply(p),
+        }
+    }
+}
+
+pub struct Keyframe {
+    pub time: f32,
+    pub value: f32,
+    // ease is used for the segment from this keyframe to the next one
+    pub ease: Ease,
+}
+
+pub struct Track {
+    // shape is the index of the shape (in file order) the track animates
+    pub shape: usize,
+    pub property: Property,
+    pub repeat: Repeat,
+    // keyframes are kept sorted by time
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    pub fn value_at(&self, time: f32) -> f32 {
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        let duration = last.time - first.time;
+        if duration <= 0.0 {
+            return first.value;
+        }
+
+        let since_first = time - first.time;
+        let local = match self.repeat {
+            Repeat::Once => since_first.max(0.0).min(duration),
+            Repeat::Loop => wrap(since_first, duration),
+            Repeat::PingPong => {
+                let there_and_back = wrap(since_first, 2.0 * duration);
+                if there_and_back > duration {
+                    2.0 * duration - there_and_back
+                } else {
+                    there_and_back
+                }
+            }
+        };
+        let t = first.time + local;
+
+        for pair in self.keyframes.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            if t < to.time {
+                let p = (t - from.time) / (to.time - from.time);
+                return from.value + (to.value - from.value) * from.ease.apply(p);
+            }
+        }
+        return last.value;
+    }
+}
+
+fn wrap(value: f32, size: f32) -> f32 {
+    return ((value % size) + size) % size;
+}
+
+// Pose is what the keyframe tracks say about a shape at a point in time,
+// with None for properties that no track animates
+#[derive(Default)]
+pub struct Pose {
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub rotation: Option<f32>,
+    pub red: Option<f32>,
+    pub green: Option<f32>,
+    pub blue: Option<f32>,
+    pub alpha: Option<f32>,
+}
+
+pub fn pose_at(tracks: &[Track], shape: usize, time: f32) -> Pose {
+    let mut pose = Pose::default();
+    for track in tracks.iter().filter(|track| track.shape == shape) {
+        let value = Some(track.value_at(time));
+        match track.property {
+            Property::X => pose.x = value,
+            Property::Y => pose.y = value,
+            Property::Width => pose.width = value,
+            Property::Height => pose.height = value,
+            Property::Rotation => pose.rotation = value,
+            Property::Red => pose.red = value,
+            Property::Green => pose.green = value,
+            Property::Blue => pose.blue = value,
+            Property::Alpha => pose.alpha = value,
+        }
+    }
+    return pose;
+}
+
+impl Decodable for Property {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Property, D::Error> {
+        let s = try!(d.read_str());
+        return Property::from_name(&s).ok_or(d.error("invalid keyframe property"));
+    }
+}
+
+impl Decodable for Repeat {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Repeat, D::Error> {
+        let s = try!(d.read_str());
+        return Repeat::from_name(&s).ok_or(d.error("invalid keyframe repeat mode"));
+    }
+}
+
+impl Decodable for Ease {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Ease, D::Error> {
+        let s = try!(d.read_str());
+        return Ease::from_name(&s).ok_or(d.error("invalid keyframe ease"));
+    }
+}
+
+impl Encodable for Property {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(self.name());
+    }
+}
+
+impl Encodable for Repeat {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(self.name());
+    }
+}
+
+impl Encodable for Ease {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(self.name());
+    }
+}
@@ -1,5 +1,10 @@
-extern crate csv;
+extern crate diagnostics;
 extern crate rustc_serialize;
+extern crate toml;
 
+pub mod clock;
+pub mod expr;
 pub mod fileformat;
+pub mod keyframe;
 pub mod types;
+pub mod watch;
@@ -0,0 +1,148 @@
+use std::f32;
+
+// Easing curves map progress through a cycle (0.0 to 1.0) onto how far
+// along the motion is; they start at 0.0 and finish at 1.0 but the elastic
+// curves overshoot on the way
+#[derive(Clone, Copy)]
+pub enum Easing {
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    pub fn from_name(name: &str) -> Option<Easing> {
+        match name {
+            "quad-in" => Some(Easing::QuadIn),
+            "quad-out" => Some(Easing::QuadOut),
+            "quad-inout" => Some(Easing::QuadInOut),
+            "cubic-in" => Some(Easing::CubicIn),
+            "cubic-out" => Some(Easing::CubicOut),
+            "cubic-inout" => Some(Easing::CubicInOut),
+            "elastic-in" => Some(Easing::ElasticIn),
+            "elastic-out" => Some(Easing::ElasticOut),
+            "elastic-inout" => Some(Easing::ElasticInOut),
+            "bounce-in" => Some(Easing::BounceIn),
+            "bounce-out" => Some(Easing::BounceOut),
+            "bounce-inout" => Some(Easing::BounceInOut),
+            _ => None,
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        return vec!["quad-in",
+                    "quad-out",
+                    "quad-inout",
+                    "cubic-in",
+                    "cubic-out",
+                    "cubic-inout",
+                    "elastic-in",
+                    "elastic-out",
+                    "elastic-inout",
+                    "bounce-in",
+                    "bounce-out",
+                    "bounce-inout"];
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Easing::QuadIn => "quad-in",
+            &Easing::QuadOut => "quad-out",
+            &Easing::QuadInOut => "quad-inout",
+            &Easing::CubicIn => "cubic-in",
+            &Easing::CubicOut => "cubic-out",
+            &Easing::CubicInOut => "cubic-inout",
+            &Easing::ElasticIn => "elastic-in",
+            &Easing::ElasticOut => "elastic-out",
+            &Easing::ElasticInOut => "elastic-inout",
+            &Easing::BounceIn => "bounce-in",
+            &Easing::BounceOut => "bounce-out",
+            &Easing::BounceInOut => "bounce-inout",
+        }
+    }
+
+    pub fn apply(&self, p: f32) -> f32 {
+        match self {
+            &Easing::QuadIn => p.powi(2),
+            &Easing::QuadOut => 1.0 - (1.0 - p).powi(2),
+            &Easing::QuadInOut => {
+                if p < 0.5 {
+                    2.0 * p.powi(2)
+                } else {
+                    1.0 - (-2.0 * p + 2.0).powi(2) / 2.0
+                }
+            }
+            &Easing::CubicIn => p.powi(3),
+            &Easing::CubicOut => 1.0 - (1.0 - p).powi(3),
+            &Easing::CubicInOut => {
+                if p < 0.5 {
+                    4.0 * p.powi(3)
+                } else {
+                    1.0 - (-2.0 * p + 2.0).powi(3) / 2.0
+                }
+            }
+            &Easing::ElasticIn => elastic_in(p),
+            &Easing::ElasticOut => elastic_out(p),
+            &Easing::ElasticInOut => {
+                if p < 0.5 {
+                    elastic_in(2.0 * p) / 2.0
+                } else {
+                    (1.0 + elastic_out(2.0 * p - 1.0)) / 2.0
+                }
+            }
+            &Easing::BounceIn => 1.0 - bounce_out(1.0 - p),
+            &Easing::BounceOut => bounce_out(p),
+            &Easing::BounceInOut => {
+                if p < 0.5 {
+                    (1.0 - bounce_out(1.0 - 2.0 * p)) / 2.0
+                } else {
+                    (1.0 + bounce_out(2.0 * p - 1.0)) / 2.0
+                }
+            }
+        }
+    }
+}
+
+// three oscillations per cycle, decaying by 2^10 over the cycle
+const ELASTIC_PERIOD: f32 = (2.0 * f32::consts::PI) / 3.0;
+
+fn elastic_in(p: f32) -> f32 {
+    if p <= 0.0 || p >= 1.0 {
+        return p.max(0.0).min(1.0);
+    }
+    return -(2.0f32.powf(10.0 * p - 10.0)) * ((10.0 * p - 10.75) * ELASTIC_PERIOD).sin();
+}
+
+fn elastic_out(p: f32) -> f32 {
+    if p <= 0.0 || p >= 1.0 {
+        return p.max(0.0).min(1.0);
+    }
+    return 2.0f32.powf(-10.0 * p) * ((10.0 * p - 0.75) * ELASTIC_PERIOD).sin() + 1.0;
+}
+
+// bounce_out drops to the floor and bounces three times with shrinking height
+fn bounce_out(p: f32) -> f32 {
+    let n = 7.5625;
+    let d = 2.75;
+    if p < 1.0 / d {
+        return n * p.powi(2);
+    } else if p < 2.0 / d {
+        let q = p - 1.5 / d;
+        return n * q.powi(2) + 0.75;
+    } else if p < 2.5 / d {
+        let q = p - 2.25 / d;
+        return n * q.powi(2) + 0.9375;
+    } else {
+        let q = p - 2.625 / d;
+        return n * q.powi(2) + 0.984375;
+    }
+}
@@ -1,52 +1,285 @@
 use rustc_serialize::Decodable;
 use rustc_serialize::Decoder;
+use rustc_serialize::Encodable;
+use rustc_serialize::Encoder;
 
 use std::f32;
 use std::fmt;
 use std::error;
 
+use expr;
+
+mod easing;
+
+pub use self::easing::Easing;
+
 #[derive(Clone, Copy)]
 pub enum MathFunc {
     Linear,
     Parabolic,
     Sin,
+    Cos,
+    Triangle,
+    Square,
+    Sawtooth,
+    Ease(Easing),
+    ExpDecay,
+    Noise,
+    Constant,
 }
 
 impl Decodable for MathFunc {
     fn decode<D: Decoder>(d: &mut D) -> Result<MathFunc, D::Error> {
         let s = try!(d.read_str());
-        match s.as_str() {
-            "lin" => Ok(MathFunc::Linear),
-            "par" => Ok(MathFunc::Parabolic),
-            "sin" => Ok(MathFunc::Sin),
-            _ => Err(d.error("invalid math function")),
+        match MathFunc::from_name(s.as_str()) {
+            Some(func) => Ok(func),
+            None => Err(d.error("unknown math function")),
         }
     }
 }
 
 impl MathFunc {
+    pub fn from_name(name: &str) -> Option<MathFunc> {
+        match name {
+            "lin" => Some(MathFunc::Linear),
+            "par" => Some(MathFunc::Parabolic),
+            "sin" => Some(MathFunc::Sin),
+            "cos" => Some(MathFunc::Cos),
+            "tri" => Some(MathFunc::Triangle),
+            "square" => Some(MathFunc::Square),
+            "saw" => Some(MathFunc::Sawtooth),
+            "decay" => Some(MathFunc::ExpDecay),
+            "noise" => Some(MathFunc::Noise),
+            "const" => Some(MathFunc::Constant),
+            _ => Easing::from_name(name).map(MathFunc::Ease),
+        }
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        let mut names = vec!["lin", "par", "sin", "cos", "tri", "square", "saw", "decay", "noise",
+                             "const"];
+        names.extend(Easing::names());
+        return names;
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &MathFunc::Linear => "lin",
+            &MathFunc::Parabolic => "par",
+            &MathFunc::Sin => "sin",
+            &MathFunc::Cos => "cos",
+            &MathFunc::Triangle => "tri",
+            &MathFunc::Square => "square",
+            &MathFunc::Sawtooth => "saw",
+            &MathFunc::Ease(easing) => easing.name(),
+            &MathFunc::ExpDecay => "decay",
+            &MathFunc::Noise => "noise",
+            &MathFunc::Constant => "const",
+        }
+    }
+
     pub fn operate(&self, x: f32) -> f32 {
         match self {
             &MathFunc::Linear => x,
             &MathFunc::Parabolic => x.powi(2),
             &MathFunc::Sin => x.sin(),
+            &MathFunc::Cos => x.cos(),
+            _ => self.wave(x - x.floor()),
+        }
+    }
+
+    // wave gives the shape of the function over a single cycle, where p runs
+    // from 0.0 at the start of the cycle up to 1.0 at the end
+    pub fn wave(&self, p: f32) -> f32 {
+        match self {
+            // runs from -0.5 to 0.5 over the cycle
+            &MathFunc::Linear => p - 0.5,
+            // y = 4p(1 - p), rising from 0.0 to 1.0 at the half way point and back
+            &MathFunc::Parabolic => 4.0 * p * (1.0 - p),
+            &MathFunc::Sin => (p * f32::consts::PI * 2.0).sin(),
+            &MathFunc::Cos => (p * f32::consts::PI * 2.0).cos(),
+            // follows the same 0, 1, 0, -1, 0 path as sin but in straight lines
+            &MathFunc::Triangle => {
+                if p < 0.25 {
+                    4.0 * p
+                } else if p < 0.75 {
+                    2.0 - 4.0 * p
+                } else {
+                    4.0 * p - 4.0
+                }
+            }
+            &MathFunc::Square => if p < 0.5 { 1.0 } else { -1.0 },
+            &MathFunc::Sawtooth => 2.0 * p - 1.0,
+            &MathFunc::Ease(easing) => easing.apply(p),
+            // falls from 1.0 to under 1% of that by the end of the cycle
+            &MathFunc::ExpDecay => (-5.0 * p).exp(),
+            &MathFunc::Noise => periodic_noise(p),
+            &MathFunc::Constant => 1.0,
+        }
+    }
+
+    // default_amplitude is the scaling files get when they don't give one,
+    // which keeps scenes written before amplitudes existed moving the same way
+    pub fn default_amplitude(&self) -> f32 {
+        match self {
+            &MathFunc::Sin => 0.4,
+            _ => 1.0,
         }
     }
 
     pub fn plot_with_max(&self, x: f32, max: f32) -> f32 {
+        return self.default_amplitude() * self.wave(x / max);
+    }
+}
+
+// Motion is a math function along with how far it swings (amplitude) and how
+// far through its cycle it starts (phase, as a fraction of the cycle).
+// In files it is written as `name`, `name:amplitude` or `name:amplitude:phase`
+#[derive(Clone, Copy)]
+pub struct Motion {
+    pub func: MathFunc,
+    pub amplitude: f32,
+    pub phase: f32,
+}
+
+impl Motion {
+    pub fn new(func: MathFunc) -> Motion {
+        return Motion {
+            func: func,
+            amplitude: func.default_amplitude(),
+            phase: 0.0,
+        };
+    }
+
+    pub fn plot_with_max(&self, x: f32, max: f32) -> f32 {
+        let shifted = x / max + self.phase;
+        return self.amplitude * self.func.wave(shifted - shifted.floor());
+    }
+}
+
+impl Motion {
+    // parse reads the `name[:amplitude[:phase]]` form used in files
+    pub fn parse(s: &str) -> Result<Motion, String> {
+        let mut parts = s.split(':');
+        let name = parts.next().unwrap_or("");
+        let func = try!(MathFunc::from_name(name)
+            .ok_or(format!("invalid math function '{}'", name)));
+        let mut motion = Motion::new(func);
+        if let Some(amplitude) = parts.next() {
+            motion.amplitude = try!(amplitude.parse()
+                .map_err(|_| format!("invalid amplitude '{}'", amplitude)));
+        }
+        if let Some(phase) = parts.next() {
+            motion.phase = try!(phase.parse().map_err(|_| format!("invalid phase '{}'", phase)));
+        }
+        if parts.next().is_some() {
+            return Err(format!("too many motion parameters in '{}'", s));
+        }
+        return Ok(motion);
+    }
+}
+
+impl fmt::Display for Motion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.phase != 0.0 {
+            write!(f, "{}:{}:{}", self.func.name(), self.amplitude, self.phase)
+        } else if self.amplitude != self.func.default_amplitude() {
+            write!(f, "{}:{}", self.func.name(), self.amplitude)
+        } else {
+            write!(f, "{}", self.func.name())
+        }
+    }
+}
+
+impl Decodable for Motion {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Motion, D::Error> {
+        let s = try!(d.read_str());
+        return Motion::parse(&s).map_err(|e| d.error(&e));
+    }
+}
+
+// MotionPath is what the x_func and y_func columns describe: either a named
+// Motion or an expression in t, such as `0.3*sin(2*pi*t/4) + 0.1*t`, which
+// is kept along with the text it was parsed from
+#[derive(Clone)]
+pub enum MotionPath {
+    Motion(Motion),
+    Expression(String, expr::Expr),
+}
+
+impl MotionPath {
+    pub fn parse(s: &str) -> Result<MotionPath, String> {
+        let name = s.split(':').next().unwrap_or("");
+        if MathFunc::from_name(name).is_some() {
+            return Motion::parse(s).map(MotionPath::Motion);
+        }
+        return expr::parse(s)
+            .map(|e| MotionPath::Expression(s.to_string(), e))
+            .map_err(|e| format!("not a math function or a valid expression (at {})", e));
+    }
+
+    // value_at is the (unscaled) offset at the given time in seconds. Named
+    // motions repeat every cycle_size seconds, while expressions see the
+    // time directly so they are free to never repeat
+    pub fn value_at(&self, time: f32, cycle_size: f32) -> f32 {
         match self {
-            &MathFunc::Linear => (x - max / 2.0) / max,
-            // y = -(x - n/2)^2 + (n/2)^2
-            &MathFunc::Parabolic => {
-                let half_max = max / 2.0;
-                let non_minimized = -((x - half_max).powi(2)) + half_max.powi(2);
-                non_minimized / half_max.powi(2)
+            &MotionPath::Motion(ref motion) => {
+                // keep the tick positive even when an offset or seek puts time before zero
+                let tick = ((time % cycle_size) + cycle_size) % cycle_size;
+                motion.plot_with_max(tick, cycle_size)
             }
-            &MathFunc::Sin => ((x / max) * f32::consts::PI * 2.0).sin() / 2.5,
+            &MotionPath::Expression(_, ref expression) => expression.eval(time),
+        }
+    }
+}
+
+impl fmt::Display for MotionPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &MotionPath::Motion(ref motion) => write!(f, "{}", motion),
+            &MotionPath::Expression(ref source, _) => write!(f, "{}", source),
         }
     }
 }
 
+impl Encodable for MotionPath {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        return s.emit_str(&self.to_string());
+    }
+}
+
+impl Decodable for MotionPath {
+    fn decode<D: Decoder>(d: &mut D) -> Result<MotionPath, D::Error> {
+        let s = try!(d.read_str());
+        return MotionPath::parse(&s).map_err(|e| d.error(&e));
+    }
+}
+
+// periodic_noise is one dimensional gradient (Perlin style) noise over a
+// lattice that wraps around each cycle, so the motion loops seamlessly
+fn periodic_noise(p: f32) -> f32 {
+    let cells = 8;
+    let x = p * (cells as f32);
+    let cell = x.floor();
+    let f = x - cell;
+    let left = cell as u32 % cells;
+    let right = (left + 1) % cells;
+
+    let from_left = lattice_gradient(left) * f;
+    let from_right = lattice_gradient(right) * (f - 1.0);
+    let fade = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+
+    // gradient noise stays within half a unit, so double it to fill -1.0 to 1.0
+    return 2.0 * (from_left + fade * (from_right - from_left));
+}
+
+fn lattice_gradient(point: u32) -> f32 {
+    let mut h = point.wrapping_mul(0x27d4eb2d) ^ 0x9e3779b9;
+    h = (h ^ (h >> 15)).wrapping_mul(0x85ebca6b);
+    h = h ^ (h >> 13);
+    return ((h & 0xffff) as f32 / 32767.5) - 1.0;
+}
+
 #[derive(Debug)]
 pub struct OutOfBoundsError {}
 
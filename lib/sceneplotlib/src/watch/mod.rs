@@ -0,0 +1,59 @@
+use std::fs;
+use std::time;
+
+// FileWatcher notices when any of a set of files changes by polling their
+// modification times, checking at most once per interval so it can be asked
+// every frame. A file that is missing counts as a state of its own, so one
+// being created or deleted is a change too
+pub struct FileWatcher {
+    files: Vec<WatchedFile>,
+    interval: time::Duration,
+    last_check: time::Instant,
+}
+
+struct WatchedFile {
+    path: String,
+    modified: Option<time::SystemTime>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<String>, interval: time::Duration) -> FileWatcher {
+        return FileWatcher {
+            files: paths.into_iter()
+                .map(|path| {
+                    let modified = modified_time(&path);
+                    WatchedFile {
+                        path: path,
+                        modified: modified,
+                    }
+                })
+                .collect(),
+            interval: interval,
+            last_check: time::Instant::now(),
+        };
+    }
+
+    // changed is true when any of the files has changed since the last time
+    // it returned true (or since the watcher was made)
+    pub fn changed(&mut self) -> bool {
+        let now = time::Instant::now();
+        if now.duration_since(self.last_check) < self.interval {
+            return false;
+        }
+        self.last_check = now;
+
+        let mut changed = false;
+        for file in self.files.iter_mut() {
+            let modified = modified_time(&file.path);
+            if modified != file.modified {
+                file.modified = modified;
+                changed = true;
+            }
+        }
+        return changed;
+    }
+}
+
+fn modified_time(path: &str) -> Option<time::SystemTime> {
+    return fs::metadata(path).and_then(|m| m.modified()).ok();
+}
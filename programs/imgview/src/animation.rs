@@ -0,0 +1,309 @@
+use std::cmp;
+use std::time;
+
+// Disposal is what happens to a frame's area before the next frame is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Disposal {
+    // leave it as it is
+    Keep,
+    // clear it to transparent
+    Background,
+    // put back what was there before the frame was drawn
+    Previous,
+}
+
+// Blend is how a frame's pixels are drawn over what is already there
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Blend {
+    // replace them, transparency and all
+    Source,
+    // lay them on top, letting through what shows behind
+    Over,
+}
+
+// Patch is one frame as it is stored in the file: a rectangle of rgba
+// pixels to draw at an offset on the canvas
+pub struct Patch {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+    pub delay_ms: u32,
+    pub disposal: Disposal,
+    pub blend: Blend,
+}
+
+// delay_ms is how long to show a frame for. Like browsers, very short delays
+// are stretched, as they are almost always meant to be the default
+pub fn delay_ms(delay_ms: u32) -> u32 {
+    if delay_ms <= 10 {
+        return 100;
+    }
+    return delay_ms;
+}
+
+// Animation is the frames of an animated image, kept as the patches the file
+// stores them as and drawn one at a time onto a single canvas, so that a
+// long animation takes little more memory than one frame
+pub struct Animation {
+    width: u32,
+    height: u32,
+    patches: Vec<Patch>,
+    canvas: Vec<u8>,
+    // what was under the frame showing, while its disposal needs it back
+    previous: Option<Vec<u8>>,
+    frame: usize,
+}
+
+impl Animation {
+    // new starts on the first frame. There must be at least one patch
+    pub fn new(width: u32, height: u32, patches: Vec<Patch>) -> Animation {
+        let mut animation = Animation {
+            width: width,
+            height: height,
+            patches: patches,
+            canvas: Vec::new(),
+            previous: None,
+            frame: 0,
+        };
+        animation.restart();
+        return animation;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.patches.len();
+    }
+
+    pub fn delay_ms(&self, frame: usize) -> u32 {
+        return self.patches.get(frame).map(|patch| patch.delay_ms).unwrap_or(0);
+    }
+
+    // data is the whole of the frame showing
+    pub fn data(&self) -> &[u8] {
+        return &self.canvas;
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        return self.canvas;
+    }
+
+    // seek draws the frame, carrying on from the one showing, or starting
+    // again from the first for an earlier one, as each frame is drawn on top
+    // of those before it
+    pub fn seek(&mut self, frame: usize) {
+        if frame >= self.patches.len() || frame == self.frame {
+            return;
+        }
+        if frame < self.frame {
+            self.restart();
+        }
+        while self.frame < frame {
+            self.dispose();
+            self.frame += 1;
+            self.draw_frame();
+        }
+    }
+
+    fn restart(&mut self) {
+        self.canvas = vec![0; self.width as usize * self.height as usize * 4];
+        self.frame = 0;
+        self.draw_frame();
+    }
+
+    // draw_frame draws the current frame's patch, keeping what was under it
+    // if it is to be put back afterwards
+    fn draw_frame(&mut self) {
+        let patch = &self.patches[self.frame];
+        self.previous = match patch.disposal {
+            Disposal::Previous => Some(self.canvas.clone()),
+            _ => None,
+        };
+        draw(&mut self.canvas, self.width, self.height, patch);
+    }
+
+    // dispose gets rid of the current frame as it says, ready for the next
+    fn dispose(&mut self) {
+        let patch = &self.patches[self.frame];
+        match (patch.disposal, self.previous.take()) {
+            (Disposal::Background, _) => clear(&mut self.canvas, self.width, self.height, patch),
+            (Disposal::Previous, Some(previous)) => self.canvas = previous,
+            _ => (),
+        }
+    }
+}
+
+// visible is how many of the patch's columns and rows land on the canvas,
+// none at all if a malformed file puts its offset outside
+fn visible(width: u32, height: u32, patch: &Patch) -> (usize, usize) {
+    let columns = cmp::min(patch.width, width.saturating_sub(patch.left));
+    let rows = cmp::min(patch.height, height.saturating_sub(patch.top));
+    return (columns as usize, rows as usize);
+}
+
+// offset is where a pixel of the patch is on the canvas
+fn offset(width: u32, patch: &Patch, x: usize, y: usize) -> usize {
+    return ((patch.top as usize + y) * width as usize + patch.left as usize + x) * 4;
+}
+
+fn draw(canvas: &mut [u8], width: u32, height: u32, patch: &Patch) {
+    let (columns, rows) = visible(width, height, patch);
+    for y in 0..rows {
+        for x in 0..columns {
+            let from = (y * patch.width as usize + x) * 4;
+            let to = offset(width, patch, x, y);
+            if from + 4 > patch.data.len() {
+                return;
+            }
+            let source = &patch.data[from..from + 4];
+            let pixel = match patch.blend {
+                Blend::Source => [source[0], source[1], source[2], source[3]],
+                Blend::Over => over(source, &canvas[to..to + 4]),
+            };
+            canvas[to..to + 4].copy_from_slice(&pixel);
+        }
+    }
+}
+
+// over lays one rgba pixel on top of another
+fn over(top: &[u8], bottom: &[u8]) -> [u8; 4] {
+    let top_alpha = f32::from(top[3]) / 255.0;
+    let bottom_alpha = f32::from(bottom[3]) / 255.0 * (1.0 - top_alpha);
+    let alpha = top_alpha + bottom_alpha;
+    if alpha <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |i: usize| {
+        let sum = f32::from(top[i]) * top_alpha + f32::from(bottom[i]) * bottom_alpha;
+        (sum / alpha).round() as u8
+    };
+    return [channel(0), channel(1), channel(2), (alpha * 255.0).round() as u8];
+}
+
+fn clear(canvas: &mut [u8], width: u32, height: u32, patch: &Patch) {
+    let (columns, rows) = visible(width, height, patch);
+    for y in 0..rows {
+        for x in 0..columns {
+            let at = offset(width, patch, x, y);
+            canvas[at..at + 4].copy_from_slice(&[0, 0, 0, 0]);
+        }
+    }
+}
+
+// Player keeps track of which frame of an animation is showing
+pub struct Player {
+    frame: usize,
+    playing: bool,
+    frame_started: time::Instant,
+    // how many times to play through, where 0 is forever
+    plays: u32,
+    played: u32,
+}
+
+impl Player {
+    pub fn new(plays: u32) -> Player {
+        return Player {
+            frame: 0,
+            playing: true,
+            frame_started: time::Instant::now(),
+            plays: plays,
+            played: 0,
+        };
+    }
+
+    pub fn frame(&self) -> usize {
+        return self.frame;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        return self.playing;
+    }
+
+    // toggle_pause stops or carries on playing, starting again from the
+    // beginning of the current frame, and from the first play if they had
+    // all finished
+    pub fn toggle_pause(&mut self) {
+        self.playing = !self.playing;
+        self.frame_started = time::Instant::now();
+        if self.plays != 0 && self.played >= self.plays {
+            self.played = 0;
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    // step pauses and moves one frame forward or back
+    pub fn step(&mut self, count: usize, forward: bool) {
+        self.playing = false;
+        if count == 0 {
+            return;
+        }
+        if forward {
+            self.frame = (self.frame + 1) % count;
+        } else {
+            self.frame = (self.frame + count - 1) % count;
+        }
+    }
+
+    // advance moves on to the next of the animation's frames once the
+    // current one has been up for its delay, and says whether it did
+    pub fn advance(&mut self, animation: &Animation) -> bool {
+        let count = animation.len();
+        if !self.playing || count < 2 {
+            return false;
+        }
+        let delay = time::Duration::from_millis(u64::from(animation.delay_ms(self.frame)));
+        if self.frame_started.elapsed() < delay {
+            return false;
+        }
+        if self.frame + 1 == count {
+            self.played += 1;
+            if self.plays != 0 && self.played >= self.plays {
+                self.playing = false;
+                return false;
+            }
+        }
+        self.frame = (self.frame + 1) % count;
+        self.frame_started = time::Instant::now();
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::u32;
+
+    use super::Animation;
+    use super::Blend;
+    use super::Disposal;
+    use super::Patch;
+
+    fn patch(left: u32, top: u32) -> Patch {
+        return Patch {
+            left: left,
+            top: top,
+            width: 2,
+            height: 1,
+            data: vec![255; 8],
+            delay_ms: 100,
+            disposal: Disposal::Background,
+            blend: Blend::Source,
+        };
+    }
+
+    #[test]
+    fn patches_are_cut_off_at_the_canvas_edge() {
+        let animation = Animation::new(2, 2, vec![patch(1, 1)]);
+        assert_eq!(animation.data(), &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn patches_offset_past_the_canvas_are_left_out() {
+        let mut animation = Animation::new(2, 2, vec![patch(u32::MAX, 0), patch(0, u32::MAX)]);
+        animation.seek(1);
+        animation.seek(0);
+        assert_eq!(animation.data(), &[0; 16]);
+    }
+}
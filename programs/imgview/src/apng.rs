@@ -0,0 +1,198 @@
+use animation;
+
+const SIGNATURE: &'static [u8] = b"\x89PNG\r\n\x1a\n";
+
+struct Chunk<'a> {
+    kind: &'a [u8],
+    data: &'a [u8],
+}
+
+struct FrameControl {
+    width: u32,
+    height: u32,
+    left: u32,
+    top: u32,
+    delay_ms: u32,
+    disposal: animation::Disposal,
+    blend: animation::Blend,
+}
+
+// is_animated is whether a png has an animation control chunk before its
+// image data, which makes it an apng
+pub fn is_animated(contents: &[u8]) -> bool {
+    for chunk in chunks(contents) {
+        match chunk.kind {
+            b"acTL" => return true,
+            b"IDAT" => return false,
+            _ => (),
+        }
+    }
+    return false;
+}
+
+// split turns an apng into a png for each of its frames, along with where
+// and how to draw them, the size of the whole animation and how many times
+// to play it (0 for forever). The frames are rebuilt from the file's own
+// chunks: its header, with the frame's size, the chunks before the image
+// data, such as the palette, and the frame's data
+pub fn split(contents: &[u8]) -> Result<(Vec<(Vec<u8>, FramePlacement)>, (u32, u32), u32), String> {
+    let all = chunks(contents);
+    let header = try!(all.iter()
+        .find(|c| c.kind == b"IHDR" && c.data.len() == 13)
+        .ok_or("the header chunk is missing".to_string()));
+    let plays = match all.iter().find(|c| c.kind == b"acTL" && c.data.len() == 8) {
+        Some(control) => read_u32(&control.data[4..]),
+        None => return Err("the animation control chunk is missing".to_string()),
+    };
+
+    let mut shared: Vec<&Chunk> = Vec::new();
+    let mut frames: Vec<(FrameControl, Vec<u8>)> = Vec::new();
+    let mut seen_data = false;
+    for chunk in all.iter() {
+        match chunk.kind {
+            b"IHDR" | b"acTL" | b"IEND" => (),
+            b"fcTL" => frames.push((try!(frame_control(chunk.data)), Vec::new())),
+            // the default image is only a frame if a frame control comes
+            // before it
+            b"IDAT" => {
+                seen_data = true;
+                if let Some(&mut (_, ref mut data)) = frames.last_mut() {
+                    data.extend_from_slice(chunk.data);
+                }
+            }
+            b"fdAT" if chunk.data.len() >= 4 => {
+                if let Some(&mut (_, ref mut data)) = frames.last_mut() {
+                    data.extend_from_slice(&chunk.data[4..]);
+                }
+            }
+            _ if !seen_data => shared.push(chunk),
+            _ => (),
+        }
+    }
+    if frames.is_empty() {
+        return Err("there are no frames".to_string());
+    }
+
+    let mut pngs = Vec::with_capacity(frames.len());
+    for (index, (control, data)) in frames.into_iter().enumerate() {
+        let mut png = SIGNATURE.to_vec();
+        let mut frame_header = header.data.to_vec();
+        frame_header[0..4].copy_from_slice(&be_bytes(control.width));
+        frame_header[4..8].copy_from_slice(&be_bytes(control.height));
+        write_chunk(&mut png, b"IHDR", &frame_header);
+        for chunk in shared.iter() {
+            write_chunk(&mut png, chunk.kind, chunk.data);
+        }
+        write_chunk(&mut png, b"IDAT", &data);
+        write_chunk(&mut png, b"IEND", &[]);
+        // disposing of the first frame to what came before it means
+        // clearing it, as there is nothing before it
+        let disposal = match (index, control.disposal) {
+            (0, animation::Disposal::Previous) => animation::Disposal::Background,
+            (_, disposal) => disposal,
+        };
+        pngs.push((png,
+                   FramePlacement {
+                       left: control.left,
+                       top: control.top,
+                       delay_ms: control.delay_ms,
+                       disposal: disposal,
+                       blend: control.blend,
+                   }));
+    }
+    let size = (read_u32(&header.data[0..]), read_u32(&header.data[4..]));
+    return Ok((pngs, size, plays));
+}
+
+// FramePlacement is where and how to draw a frame of an apng
+pub struct FramePlacement {
+    pub left: u32,
+    pub top: u32,
+    pub delay_ms: u32,
+    pub disposal: animation::Disposal,
+    pub blend: animation::Blend,
+}
+
+fn frame_control(data: &[u8]) -> Result<FrameControl, String> {
+    if data.len() < 26 {
+        return Err("a frame control chunk is too short".to_string());
+    }
+    let numerator = u32::from(read_u16(&data[20..]));
+    let denominator = match read_u16(&data[22..]) {
+        0 => 100,
+        d => u32::from(d),
+    };
+    return Ok(FrameControl {
+        width: read_u32(&data[4..]),
+        height: read_u32(&data[8..]),
+        left: read_u32(&data[12..]),
+        top: read_u32(&data[16..]),
+        delay_ms: animation::delay_ms(numerator * 1000 / denominator),
+        disposal: match data[24] {
+            1 => animation::Disposal::Background,
+            2 => animation::Disposal::Previous,
+            _ => animation::Disposal::Keep,
+        },
+        blend: match data[25] {
+            1 => animation::Blend::Over,
+            _ => animation::Blend::Source,
+        },
+    });
+}
+
+fn chunks(contents: &[u8]) -> Vec<Chunk> {
+    let mut found = Vec::new();
+    if !contents.starts_with(SIGNATURE) {
+        return found;
+    }
+    let mut at = SIGNATURE.len();
+    while at + 12 <= contents.len() {
+        let length = read_u32(&contents[at..]) as usize;
+        if at + 12 + length > contents.len() {
+            break;
+        }
+        found.push(Chunk {
+            kind: &contents[at + 4..at + 8],
+            data: &contents[at + 8..at + 8 + length],
+        });
+        at += 12 + length;
+    }
+    return found;
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    png.extend_from_slice(&be_bytes(data.len() as u32));
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&be_bytes(crc));
+}
+
+// crc32 is the checksum each png chunk ends with, over its type and data
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb88320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    return !crc;
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    return u32::from(bytes[0]) << 24 | u32::from(bytes[1]) << 16 | u32::from(bytes[2]) << 8 |
+           u32::from(bytes[3]);
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    return u16::from(bytes[0]) << 8 | u16::from(bytes[1]);
+}
+
+fn be_bytes(value: u32) -> [u8; 4] {
+    return [(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8];
+}
@@ -0,0 +1,64 @@
+use graphics;
+
+use std::cmp;
+use std::error;
+
+use decode;
+
+pub struct Entry {
+    pub index: usize,
+    pub image: decode::Image,
+    pub texture: graphics::TiledTexture,
+}
+
+// TextureCache keeps the most recently shown images on the graphics card,
+// up to a fixed number, freeing the least recently used to make room
+pub struct TextureCache {
+    capacity: usize,
+    // from least to most recently used
+    entries: Vec<Entry>,
+}
+
+impl TextureCache {
+    pub fn new(capacity: usize) -> TextureCache {
+        return TextureCache {
+            capacity: capacity,
+            entries: Vec::new(),
+        };
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        return self.entries.iter().any(|e| e.index == index);
+    }
+
+    // get marks the image as the most recently used
+    pub fn get(&mut self, index: usize) -> Option<&mut Entry> {
+        let position = match self.entries.iter().position(|e| e.index == index) {
+            Some(position) => position,
+            None => return None,
+        };
+        let entry = self.entries.remove(position);
+        self.entries.push(entry);
+        return self.entries.last_mut();
+    }
+
+    // insert loads the image's texture, dropping the least recently used
+    // texture if the cache is full
+    pub fn insert(&mut self,
+                  app: &mut graphics::App,
+                  index: usize,
+                  image: decode::Image)
+                  -> Result<(), Box<error::Error>> {
+        self.entries.retain(|e| e.index != index);
+        while self.entries.len() >= cmp::max(self.capacity, 1) {
+            self.entries.remove(0);
+        }
+        let texture = try!(app.load_tiled_texture(image.width, image.height, image.data()));
+        self.entries.push(Entry {
+            index: index,
+            image: image,
+            texture: texture,
+        });
+        return Ok(());
+    }
+}
@@ -0,0 +1,403 @@
+use gif;
+use image;
+
+use gif::SetParameter;
+use image::ImageDecoder;
+
+use std::cmp;
+use std::fmt;
+use std::fs::File;
+use std::io::Cursor;
+use std::io::Read;
+use std::path::Path;
+
+use animation;
+use apng;
+use pnm;
+use Error;
+
+// Format is a kind of image file that imgview can open
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Png,
+    Jpeg,
+    Gif,
+    Bmp,
+    Tga,
+    WebP,
+    Pnm,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Format::Png => "PNG",
+            Format::Jpeg => "JPEG",
+            Format::Gif => "GIF",
+            Format::Bmp => "BMP",
+            Format::Tga => "TGA",
+            Format::WebP => "WebP",
+            Format::Pnm => "PNM",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Image is a decoded image, as 8 bit red, green, blue and alpha for each
+// pixel, from the top row down. Animated images have their frames in place
+// of the pixels, and how many times to play them through (0 for forever)
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    data: Vec<u8>,
+    animation: Option<animation::Animation>,
+    pub plays: u32,
+}
+
+impl Image {
+    fn still(width: u32, height: u32, data: Vec<u8>) -> Image {
+        return Image {
+            width: width,
+            height: height,
+            data: data,
+            animation: None,
+            plays: 0,
+        };
+    }
+
+    fn animated(width: u32, height: u32, animation: animation::Animation, plays: u32) -> Image {
+        return Image {
+            width: width,
+            height: height,
+            data: Vec::new(),
+            animation: Some(animation),
+            plays: plays,
+        };
+    }
+
+    // data is the pixels of the image, or of the frame showing if it is
+    // animated
+    pub fn data(&self) -> &[u8] {
+        return match self.animation {
+            Some(ref animation) => animation.data(),
+            None => &self.data,
+        };
+    }
+
+    pub fn animation(&self) -> Option<&animation::Animation> {
+        return self.animation.as_ref();
+    }
+
+    // frame_count is how many frames an animated image has, or 0 for a still
+    pub fn frame_count(&self) -> usize {
+        return self.animation.as_ref().map(|animation| animation.len()).unwrap_or(0);
+    }
+
+    // seek shows the frame of an animated image
+    pub fn seek(&mut self, frame: usize) {
+        if let Some(ref mut animation) = self.animation {
+            animation.seek(frame);
+        }
+    }
+}
+
+// detect_format works out the format from the first bytes of the file.
+// TGA files don't start with anything recognisable, so they go by their
+// extension instead
+pub fn detect_format(filename: &str, contents: &[u8]) -> Option<Format> {
+    if contents.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(Format::Png);
+    }
+    if contents.starts_with(&[0xff, 0xd8, 0xff]) {
+        return Some(Format::Jpeg);
+    }
+    if contents.starts_with(b"GIF87a") || contents.starts_with(b"GIF89a") {
+        return Some(Format::Gif);
+    }
+    if contents.starts_with(b"BM") {
+        return Some(Format::Bmp);
+    }
+    if contents.len() >= 12 && &contents[0..4] == b"RIFF" && &contents[8..12] == b"WEBP" {
+        return Some(Format::WebP);
+    }
+    if contents.len() >= 2 && contents[0] == b'P' && contents[1] >= b'1' && contents[1] <= b'6' {
+        return Some(Format::Pnm);
+    }
+    let extension = Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    return match extension.as_ref().map(|e| e.as_str()) {
+        Some("tga") => Some(Format::Tga),
+        _ => None,
+    };
+}
+
+// load reads and decodes the file, whatever its format, color type and bit
+// depth, into 8 bit rgba
+pub fn load(filename: &str) -> Result<Image, Error> {
+    let mut contents = Vec::new();
+    try!(File::open(filename)
+        .and_then(|mut f| f.read_to_end(&mut contents))
+        .map_err(|e| Error::ReadError(filename.to_string(), e.to_string())));
+
+    let format = try!(detect_format(filename, &contents)
+        .ok_or(Error::UnknownFormatError(filename.to_string())));
+
+    return decode(format, contents).map_err(|reason| {
+        Error::DecodeError {
+            filename: filename.to_string(),
+            format: format,
+            reason: reason,
+        }
+    });
+}
+
+fn decode(format: Format, contents: Vec<u8>) -> Result<Image, String> {
+    // the webp decoder only gives back the brightness of lossy images, which
+    // would show up in grey rather than in their colors
+    if format == Format::WebP && contents.len() >= 16 && &contents[12..16] == b"VP8 " {
+        return Err("lossy WebP can't be decoded in color, only its brightness".to_string());
+    }
+    let (width, height, color, pixels) = try!(match format {
+        Format::Gif => return decode_gif(contents),
+        Format::Png if apng::is_animated(&contents) => return decode_apng(&contents),
+        Format::Png => read_with(image::png::PNGDecoder::new(Cursor::new(contents))),
+        Format::Jpeg => read_with(image::jpeg::JPEGDecoder::new(Cursor::new(contents))),
+        Format::Bmp => read_with(image::bmp::BMPDecoder::new(Cursor::new(contents))),
+        Format::Tga => read_with(image::tga::TGADecoder::new(Cursor::new(contents))),
+        Format::WebP => read_with(image::webp::WebpDecoder::new(Cursor::new(contents))),
+        Format::Pnm => pnm::decode(&contents),
+    });
+    let data = try!(to_rgba8(color, width, height, pixels));
+    return Ok(Image::still(width, height, data));
+}
+
+// decode_gif reads every frame of a gif, which is a still image if it only
+// has the one
+fn decode_gif(contents: Vec<u8>) -> Result<Image, String> {
+    let plays = gif_plays(&contents);
+    let mut decoder = gif::Decoder::new(Cursor::new(contents));
+    decoder.set(gif::ColorOutput::RGBA);
+    let mut reader = try!(decoder.read_info().map_err(|e| e.to_string()));
+    let (width, height) = (u32::from(reader.width()), u32::from(reader.height()));
+
+    let mut patches = Vec::new();
+    while let Some(frame) = try!(reader.read_next_frame().map_err(|e| e.to_string())) {
+        patches.push(animation::Patch {
+            left: u32::from(frame.left),
+            top: u32::from(frame.top),
+            width: u32::from(frame.width),
+            height: u32::from(frame.height),
+            data: frame.buffer.to_vec(),
+            delay_ms: animation::delay_ms(u32::from(frame.delay) * 10),
+            disposal: match frame.dispose {
+                gif::DisposalMethod::Background => animation::Disposal::Background,
+                gif::DisposalMethod::Previous => animation::Disposal::Previous,
+                _ => animation::Disposal::Keep,
+            },
+            // transparent pixels let the frame before show through
+            blend: animation::Blend::Over,
+        });
+    }
+    if patches.is_empty() {
+        return Err("there are no frames".to_string());
+    }
+    let frames = animation::Animation::new(width, height, patches);
+    if frames.len() == 1 {
+        return Ok(Image::still(width, height, frames.into_data()));
+    }
+    return Ok(Image::animated(width, height, frames, plays));
+}
+
+// gif_plays is how many times a gif plays through, from its NETSCAPE2.0
+// looping extension, which counts the repeats after the first play. A gif
+// without one plays once
+fn gif_plays(contents: &[u8]) -> u32 {
+    return match gif_repeats(contents) {
+        Some(0) => 0,
+        Some(repeats) => repeats + 1,
+        None => 1,
+    };
+}
+
+// gif_repeats walks the blocks of a gif, skipping over the image data, to
+// find its NETSCAPE2.0 application extension and the repeat count in it
+fn gif_repeats(contents: &[u8]) -> Option<u32> {
+    // the header and logical screen descriptor, then the global color table
+    let mut at = match contents.get(10) {
+        Some(&flags) => 13 + color_table_size(flags),
+        None => return None,
+    };
+    while at < contents.len() {
+        match contents[at] {
+            // an extension: its label, then sub-blocks of data
+            0x21 => {
+                let label = match contents.get(at + 1) {
+                    Some(&label) => label,
+                    None => return None,
+                };
+                let data = at + 2;
+                let loop_block = data + 12;
+                if label == 0xff && contents.len() >= loop_block + 4 && contents[data] == 11 &&
+                   &contents[data + 1..loop_block] == b"NETSCAPE2.0" &&
+                   contents[loop_block] == 3 && contents[loop_block + 1] == 1 {
+                    return Some(u32::from(contents[loop_block + 2]) |
+                                u32::from(contents[loop_block + 3]) << 8);
+                }
+                at = match skip_sub_blocks(contents, data) {
+                    Some(next) => next,
+                    None => return None,
+                };
+            }
+            // an image: its descriptor and local color table, the lzw code
+            // size, then sub-blocks of data
+            0x2c => {
+                let data = match contents.get(at + 9) {
+                    Some(&flags) => at + 10 + color_table_size(flags) + 1,
+                    None => return None,
+                };
+                at = match skip_sub_blocks(contents, data) {
+                    Some(next) => next,
+                    None => return None,
+                };
+            }
+            // the trailer, or something that isn't a gif block
+            _ => return None,
+        }
+    }
+    return None;
+}
+
+// color_table_size is how many bytes the color table a gif's flags byte
+// describes takes, if it has one
+fn color_table_size(flags: u8) -> usize {
+    if flags & 0x80 == 0 {
+        return 0;
+    }
+    return 3 << ((flags & 0x07) + 1);
+}
+
+// skip_sub_blocks gives the position after a run of gif sub-blocks, each a
+// length and that many bytes, ending with an empty one
+fn skip_sub_blocks(contents: &[u8], at: usize) -> Option<usize> {
+    let mut at = at;
+    loop {
+        let size = match contents.get(at) {
+            Some(&size) => size as usize,
+            None => return None,
+        };
+        at += 1 + size;
+        if size == 0 {
+            return Some(at);
+        }
+    }
+}
+
+// decode_apng decodes each frame of an animated png as a png of its own
+fn decode_apng(contents: &[u8]) -> Result<Image, String> {
+    let (pngs, (width, height), plays) = try!(apng::split(contents));
+
+    let mut patches = Vec::with_capacity(pngs.len());
+    for (index, (png, placement)) in pngs.into_iter().enumerate() {
+        let frame = try!(decode(Format::Png, png).map_err(|e| format!("frame {}: {}", index, e)));
+        patches.push(animation::Patch {
+            left: placement.left,
+            top: placement.top,
+            width: frame.width,
+            height: frame.height,
+            data: frame.data,
+            delay_ms: placement.delay_ms,
+            disposal: placement.disposal,
+            blend: placement.blend,
+        });
+    }
+    let frames = animation::Animation::new(width, height, patches);
+    return Ok(Image::animated(width, height, frames, plays));
+}
+
+fn read_with<D>(mut decoder: D)
+                -> Result<(u32, u32, image::ColorType, image::DecodingResult), String>
+    where D: ImageDecoder
+{
+    let (width, height) = try!(decoder.dimensions().map_err(|e| e.to_string()));
+    let color = try!(decoder.colortype().map_err(|e| e.to_string()));
+    let pixels = try!(decoder.read_image().map_err(|e| e.to_string()));
+    return Ok((width, height, color, pixels));
+}
+
+// to_rgba8 converts decoded pixels of any color type and bit depth into 8
+// bit rgba, filling in an opaque alpha where the image has none
+pub fn to_rgba8(color: image::ColorType,
+                width: u32,
+                height: u32,
+                pixels: image::DecodingResult)
+                -> Result<Vec<u8>, String> {
+    let (channels, bits) = match color {
+        image::ColorType::Gray(bits) => (1, bits),
+        image::ColorType::GrayA(bits) => (2, bits),
+        image::ColorType::RGB(bits) => (3, bits),
+        image::ColorType::RGBA(bits) => (4, bits),
+        image::ColorType::Palette(_) => {
+            return Err("paletted pixels were not expanded to colors".to_string())
+        }
+    };
+    let samples = try!(to_samples8(pixels, bits, width as usize * channels));
+
+    let count = width as usize * height as usize;
+    if samples.len() < count * channels {
+        return Err(format!("expected {} pixels of data for a {}x{} image but got {}",
+                           count,
+                           width,
+                           height,
+                           samples.len() / channels));
+    }
+
+    let mut data = Vec::with_capacity(count * 4);
+    for pixel in samples.chunks(channels).take(count) {
+        match channels {
+            1 => data.extend_from_slice(&[pixel[0], pixel[0], pixel[0], 255]),
+            2 => data.extend_from_slice(&[pixel[0], pixel[0], pixel[0], pixel[1]]),
+            3 => data.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]),
+            _ => data.extend_from_slice(pixel),
+        }
+    }
+    return Ok(data);
+}
+
+// to_samples8 scales each channel value to 8 bits. Values under 8 bits are
+// packed into bytes with each row starting on a new byte
+fn to_samples8(pixels: image::DecodingResult,
+               bits: u8,
+               row_samples: usize)
+               -> Result<Vec<u8>, String> {
+    return match (pixels, bits) {
+        (image::DecodingResult::U8(bytes), 8) => Ok(bytes),
+        (image::DecodingResult::U16(words), 16) => {
+            Ok(words.iter().map(|w| (w >> 8) as u8).collect())
+        }
+        // 16 bit values handed back as bytes are big endian, so the first
+        // byte of each pair is the top half
+        (image::DecodingResult::U8(bytes), 16) => Ok(bytes.chunks(2).map(|pair| pair[0]).collect()),
+        (image::DecodingResult::U8(bytes), 1) |
+        (image::DecodingResult::U8(bytes), 2) |
+        (image::DecodingResult::U8(bytes), 4) => Ok(unpack(&bytes, bits, row_samples)),
+        (_, bits) => Err(format!("can't read {} bit color values", bits)),
+    };
+}
+
+fn unpack(bytes: &[u8], bits: u8, row_samples: usize) -> Vec<u8> {
+    let per_byte = (8 / bits) as usize;
+    let row_bytes = (row_samples + per_byte - 1) / per_byte;
+    let mask = (1u16 << bits) - 1;
+    let scale = 255 / mask;
+    let mut samples = Vec::new();
+    for row in bytes.chunks(row_bytes) {
+        for i in 0..cmp::min(row_samples, row.len() * per_byte) {
+            let shift = 8 - bits as usize * (i % per_byte + 1);
+            let value = (u16::from(row[i / per_byte]) >> shift) & mask;
+            samples.push((value * scale) as u8);
+        }
+    }
+    return samples;
+}
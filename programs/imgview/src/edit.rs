@@ -0,0 +1,341 @@
+use std::cmp;
+
+// Pixels is an image as 8 bit red, green, blue and alpha for each pixel,
+// from the top row down, for editing
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pixels {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Pixels {
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Pixels {
+        return Pixels {
+            width: width,
+            height: height,
+            data: data,
+        };
+    }
+
+    // at works in usize, as the offsets of a large image don't fit in a u32
+    fn at(&self, x: u32, y: u32) -> &[u8] {
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        return &self.data[start..start + 4];
+    }
+
+    // map builds an image of the given size, taking each pixel from the one
+    // this image has where the function says
+    fn map<F>(&self, width: u32, height: u32, from: F) -> Pixels
+        where F: Fn(u32, u32) -> (u32, u32)
+    {
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let (from_x, from_y) = from(x, y);
+                data.extend_from_slice(self.at(from_x, from_y));
+            }
+        }
+        return Pixels::new(width, height, data);
+    }
+
+    // map_colors changes each pixel on its own
+    fn map_colors<F>(&self, change: F) -> Pixels
+        where F: Fn(&[u8]) -> [u8; 4]
+    {
+        let mut data = Vec::with_capacity(self.data.len());
+        for pixel in self.data.chunks(4) {
+            data.extend_from_slice(&change(pixel));
+        }
+        return Pixels::new(self.width, self.height, data);
+    }
+}
+
+pub fn rotate_clockwise(image: &Pixels) -> Pixels {
+    let height = image.height;
+    return image.map(image.height, image.width, |x, y| (y, height - 1 - x));
+}
+
+pub fn rotate_counter_clockwise(image: &Pixels) -> Pixels {
+    let width = image.width;
+    return image.map(image.height, image.width, |x, y| (width - 1 - y, x));
+}
+
+pub fn flip_horizontal(image: &Pixels) -> Pixels {
+    let width = image.width;
+    return image.map(image.width, image.height, |x, y| (width - 1 - x, y));
+}
+
+pub fn flip_vertical(image: &Pixels) -> Pixels {
+    let height = image.height;
+    return image.map(image.width, image.height, |x, y| (x, height - 1 - y));
+}
+
+// crop cuts out a rectangle, trimmed to fit inside the image
+pub fn crop(image: &Pixels, x: u32, y: u32, width: u32, height: u32) -> Pixels {
+    let x = cmp::min(x, image.width);
+    let y = cmp::min(y, image.height);
+    let width = cmp::min(width, image.width - x);
+    let height = cmp::min(height, image.height - y);
+    return image.map(width, height, |from_x, from_y| (x + from_x, y + from_y));
+}
+
+// Adjustments change the brightness, contrast and gamma of the colors,
+// leaving alpha alone. Brightness is added to each channel (between 0 and
+// 1), contrast stretches the channels away from the middle grey, and gamma
+// above 1 lightens the darker tones
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adjustments {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub gamma: f32,
+}
+
+impl Adjustments {
+    pub fn none() -> Adjustments {
+        return Adjustments {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+        };
+    }
+}
+
+pub fn adjust(image: &Pixels, adjustments: &Adjustments) -> Pixels {
+    // each of the 256 values always comes out the same, so work them out once
+    let mut table = [0u8; 256];
+    for (value, out) in table.iter_mut().enumerate() {
+        let v = value as f32 / 255.0 + adjustments.brightness;
+        let v = (v - 0.5) * adjustments.contrast + 0.5;
+        let v = v.max(0.0).powf(1.0 / adjustments.gamma.max(0.01));
+        *out = (v.max(0.0).min(1.0) * 255.0).round() as u8;
+    }
+    return image.map_colors(|p| {
+        [table[p[0] as usize], table[p[1] as usize], table[p[2] as usize], p[3]]
+    });
+}
+
+// greyscale keeps how bright each pixel looks, weighting the channels by
+// how bright they look to the eye
+pub fn greyscale(image: &Pixels) -> Pixels {
+    return image.map_colors(|p| {
+        let grey = (0.2126 * f32::from(p[0]) + 0.7152 * f32::from(p[1]) + 0.0722 * f32::from(p[2]))
+            .round() as u8;
+        [grey, grey, grey, p[3]]
+    });
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+// isolate shows a single channel on its own, as an opaque grey
+pub fn isolate(image: &Pixels, channel: Channel) -> Pixels {
+    let index = match channel {
+        Channel::Red => 0,
+        Channel::Green => 1,
+        Channel::Blue => 2,
+        Channel::Alpha => 3,
+    };
+    return image.map_colors(|p| [p[index], p[index], p[index], 255]);
+}
+
+// Step is a change to the shape of the image
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    RotateClockwise,
+    RotateCounterClockwise,
+    FlipHorizontal,
+    FlipVertical,
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+// Edits are all the changes made to an image, which are made afresh to the
+// original each time so that none of them lose anything: the steps in
+// order, then the color changes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edits {
+    pub steps: Vec<Step>,
+    pub adjustments: Adjustments,
+    pub greyscale: bool,
+    pub channel: Option<Channel>,
+}
+
+impl Edits {
+    pub fn none() -> Edits {
+        return Edits {
+            steps: Vec::new(),
+            adjustments: Adjustments::none(),
+            greyscale: false,
+            channel: None,
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return *self == Edits::none();
+    }
+
+    pub fn apply(&self, original: &Pixels) -> Pixels {
+        let mut image = original.clone();
+        for step in self.steps.iter() {
+            image = match *step {
+                Step::RotateClockwise => rotate_clockwise(&image),
+                Step::RotateCounterClockwise => rotate_counter_clockwise(&image),
+                Step::FlipHorizontal => flip_horizontal(&image),
+                Step::FlipVertical => flip_vertical(&image),
+                Step::Crop { x, y, width, height } => crop(&image, x, y, width, height),
+            };
+        }
+        if self.adjustments != Adjustments::none() {
+            image = adjust(&image, &self.adjustments);
+        }
+        if self.greyscale {
+            image = greyscale(&image);
+        }
+        if let Some(channel) = self.channel {
+            image = isolate(&image, channel);
+        }
+        return image;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // numbered is an image whose pixels are all different, so that moving
+    // any of them shows
+    fn numbered(width: u32, height: u32) -> Pixels {
+        let mut data = Vec::new();
+        for i in 0..width * height {
+            data.extend_from_slice(&[i as u8, (i * 2) as u8, (i * 3) as u8, 255]);
+        }
+        return Pixels::new(width, height, data);
+    }
+
+    #[test]
+    fn rotating_clockwise_four_times_is_the_original() {
+        let original = numbered(3, 2);
+        let mut image = original.clone();
+        for _ in 0..4 {
+            image = rotate_clockwise(&image);
+        }
+        assert_eq!(image, original);
+    }
+
+    #[test]
+    fn rotating_clockwise_swaps_the_sides() {
+        let image = rotate_clockwise(&numbered(3, 2));
+        assert_eq!((image.width, image.height), (2, 3));
+        // the bottom-left pixel goes to the top-left
+        assert_eq!(image.at(0, 0), numbered(3, 2).at(0, 1));
+    }
+
+    #[test]
+    fn rotating_both_ways_is_the_original() {
+        let original = numbered(3, 2);
+        assert_eq!(rotate_counter_clockwise(&rotate_clockwise(&original)), original);
+    }
+
+    #[test]
+    fn flipping_twice_is_the_original() {
+        let original = numbered(3, 2);
+        assert_eq!(flip_horizontal(&flip_horizontal(&original)), original);
+        assert_eq!(flip_vertical(&flip_vertical(&original)), original);
+        assert!(flip_horizontal(&original) != original);
+    }
+
+    #[test]
+    fn crop_cuts_out_the_rectangle() {
+        let original = numbered(4, 3);
+        let image = crop(&original, 1, 1, 2, 2);
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.at(0, 0), original.at(1, 1));
+        assert_eq!(image.at(1, 1), original.at(2, 2));
+    }
+
+    #[test]
+    fn crop_past_the_edge_is_trimmed() {
+        let original = numbered(4, 3);
+        let image = crop(&original, 2, 1, 10, 10);
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.at(1, 1), original.at(3, 2));
+
+        let outside = crop(&original, 5, 5, 2, 2);
+        assert_eq!((outside.width, outside.height), (0, 0));
+        assert!(outside.data.is_empty());
+    }
+
+    #[test]
+    fn no_adjustments_leave_the_colors() {
+        let original = numbered(4, 3);
+        assert_eq!(adjust(&original, &Adjustments::none()), original);
+    }
+
+    #[test]
+    fn adjust_changes_colors_but_not_alpha() {
+        let image = Pixels::new(2, 1, vec![0, 64, 255, 100, 200, 10, 20, 30]);
+        let brighter = Adjustments { brightness: 0.2, ..Adjustments::none() };
+        assert_eq!(adjust(&image, &brighter).data, vec![51, 115, 255, 100, 251, 61, 71, 30]);
+
+        let contrast = Adjustments { contrast: 2.0, ..Adjustments::none() };
+        assert_eq!(adjust(&image, &contrast).data[..3].to_vec(), vec![0, 1, 255]);
+
+        let gamma = Adjustments { gamma: 2.0, ..Adjustments::none() };
+        assert_eq!(adjust(&image, &gamma).data[..4].to_vec(), vec![0, 128, 255, 100]);
+    }
+
+    #[test]
+    fn greyscale_weights_the_channels() {
+        let image = Pixels::new(3, 1, vec![255, 0, 0, 255, 0, 255, 0, 128, 10, 20, 30, 0]);
+        assert_eq!(greyscale(&image).data,
+                   vec![54, 54, 54, 255, 182, 182, 182, 128, 19, 19, 19, 0]);
+    }
+
+    #[test]
+    fn apply_makes_the_steps_in_order() {
+        let original = numbered(3, 2);
+        let crop_step = Step::Crop {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 3,
+        };
+        let edits = Edits { steps: vec![Step::RotateClockwise, crop_step], ..Edits::none() };
+        let edited = edits.apply(&original);
+        assert_eq!(edited, crop(&rotate_clockwise(&original), 0, 0, 1, 3));
+        assert_eq!((edited.width, edited.height), (1, 3));
+        assert!(edited != rotate_clockwise(&crop(&original, 0, 0, 1, 3)));
+    }
+
+    #[test]
+    fn apply_changes_colors_after_the_steps() {
+        let original = Pixels::new(2, 1, vec![200, 10, 20, 255, 0, 0, 0, 255]);
+        let edits = Edits {
+            steps: vec![Step::FlipHorizontal],
+            greyscale: true,
+            channel: Some(Channel::Red),
+            ..Edits::none()
+        };
+        // greyscale comes before isolating a channel, or the red would show
+        let grey = greyscale(&flip_horizontal(&original));
+        assert_eq!(edits.apply(&original), isolate(&grey, Channel::Red));
+        assert_eq!(edits.apply(&original).data, vec![0, 0, 0, 255, 51, 51, 51, 255]);
+    }
+
+    #[test]
+    fn no_edits_are_the_original() {
+        let original = numbered(3, 2);
+        assert!(Edits::none().is_empty());
+        assert_eq!(Edits::none().apply(&original), original);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::thread;
+
+use decode;
+use Error;
+
+// Loader decodes images on a background thread, so that the ones either
+// side of the current image are ready by the time they are wanted. Requests
+// that stop being wanted before their turn comes are skipped, so skipping
+// quickly through a directory doesn't leave the image on screen waiting
+// behind decodes of the ones passed
+pub struct Loader {
+    requests: mpsc::Sender<(usize, String)>,
+    // a result is None for a request that was skipped
+    results: mpsc::Receiver<(usize, Option<Result<decode::Image, Error>>)>,
+    wanted: Arc<Mutex<Vec<usize>>>,
+    pending: Vec<usize>,
+    ready: HashMap<usize, Result<decode::Image, Error>>,
+}
+
+impl Loader {
+    pub fn new() -> Loader {
+        let (request_sender, request_receiver) = mpsc::channel::<(usize, String)>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let wanted = Arc::new(Mutex::new(Vec::new()));
+        let worker_wanted = wanted.clone();
+        thread::spawn(move || {
+            for (index, filename) in request_receiver.iter() {
+                let is_wanted = match worker_wanted.lock() {
+                    Ok(wanted) => wanted.contains(&index),
+                    Err(_) => return,
+                };
+                let result = match is_wanted {
+                    true => Some(decode::load(&filename)),
+                    false => None,
+                };
+                if result_sender.send((index, result)).is_err() {
+                    return;
+                }
+            }
+        });
+        return Loader {
+            requests: request_sender,
+            results: result_receiver,
+            wanted: wanted,
+            pending: Vec::new(),
+            ready: HashMap::new(),
+        };
+    }
+
+    // request starts decoding the file unless it is already on its way. Only
+    // images keep_only was last given are decoded
+    pub fn request(&mut self, index: usize, filename: &str) {
+        if self.pending.contains(&index) || self.ready.contains_key(&index) {
+            return;
+        }
+        if self.requests.send((index, filename.to_string())).is_ok() {
+            self.pending.push(index);
+        }
+    }
+
+    // take gives back the decoded image if it has finished, without waiting
+    pub fn take(&mut self, index: usize) -> Option<Result<decode::Image, Error>> {
+        while let Ok((finished, result)) = self.results.try_recv() {
+            self.pending.retain(|p| *p != finished);
+            if let Some(result) = result {
+                self.ready.insert(finished, result);
+            }
+        }
+        return self.ready.remove(&index);
+    }
+
+    // keep_only says which images are wanted, dropping decoded images that
+    // aren't, so that they don't pile up in memory, and having the worker
+    // skip requests for them that it hasn't got to yet
+    pub fn keep_only(&mut self, wanted: &[usize]) {
+        if let Ok(mut shared) = self.wanted.lock() {
+            shared.clear();
+            shared.extend_from_slice(wanted);
+        }
+        let unwanted: Vec<usize> = self.ready
+            .keys()
+            .filter(|index| !wanted.contains(index))
+            .cloned()
+            .collect();
+        for index in unwanted {
+            self.ready.remove(&index);
+        }
+    }
+}
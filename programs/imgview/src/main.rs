@@ -1,14 +1,24 @@
+extern crate gif;
 extern crate image;
 extern crate num;
 
 extern crate graphics;
 
-use image::ImageDecoder;
+mod animation;
+mod apng;
+mod cache;
+mod decode;
+mod edit;
+mod loader;
+mod pnm;
+mod view;
 
 use std::fmt;
 use std::env;
 use std::error;
-use std::fs::File;
+use std::fs;
+use std::path;
+use std::time;
 
 fn main() {
     println!("editing an image");
@@ -23,88 +33,529 @@ fn main() {
 fn run_app() -> Result<(), Box<error::Error>> {
 
     let args: Vec<String> = env::args().collect();
-    if args.len() <= 1 {
-        return Err(Box::new(Error::ArgError {}));
-    }
-    let filename = args[1].as_str();
+    let options = try!(parse_options(&args[1..]));
+    let files = options.files;
+    println!("viewing {} images", files.len());
 
-    let f = try!(File::open(filename));
-    let mut decoder = image::png::PNGDecoder::new(f);
-    let (width, height) = try!(decoder.dimensions());
-    let im = try!(decoder.read_image());
+    let mut screen = (600, 600);
+    // images get their own textures, so the app starts with a blank one
+    let mut app = try!(graphics::App::new(screen.0,
+                                          screen.1,
+                                          "Picture Viewer",
+                                          graphics::RenderingSource::TextureRenderingSource {
+                                              tex_def: graphics::TextureSetupDefinition {
+                                                  width: 1,
+                                                  height: 1,
+                                                  data: vec![0, 0, 0, 0],
+                                              },
+                                          }));
 
-    let u8image = try!(match im {
-        image::DecodingResult::U8(u8im) => Ok(u8im),
-        image::DecodingResult::U16(_) => Err(Error::U8OnlyError {}),
-    });
+    let mut loader = loader::Loader::new();
+    let mut textures = cache::TextureCache::new(CACHED_TEXTURES);
+    let mut failed: Vec<(usize, String)> = Vec::new();
 
-    let tex_def = graphics::TextureSetupDefinition {
-        width: width,
-        height: height,
-        data: u8image,
-    };
+    let mut current = 0;
+    let mut shown: Option<usize> = None;
+    let mut pixel_ratio = app.pixel_ratio();
+    let mut view = view::View::new(1, 1, screen.0, screen.1, pixel_ratio);
+    let mut cursor = (0.0, 0.0);
+    let mut dragging = false;
+    let mut title = String::new();
+    let mut slideshow = options.slideshow;
+    let mut shown_at = time::Instant::now();
+    let mut player = animation::Player::new(0);
+    let mut edits = edit::Edits::none();
+    // the edited image and its texture, while there are any edits
+    let mut preview: Option<(edit::Pixels, graphics::TiledTexture)> = None;
+    let mut cropping = false;
+    let mut crop_start: Option<(f32, f32)> = None;
+
+    'running: loop {
+        // animation keys wait for the image to step through
+        let mut toggle_play = false;
+        let mut step: Option<bool> = None;
+        let mut edits_changed = false;
+        let mut save = false;
+        // take every waiting event, as moving the mouse sends a lot of them
+        while let Some(action) = app.handle_events() {
+            match action {
+                graphics::Action::Closed => break 'running,
+                graphics::Action::Resized(w, h) => {
+                    screen = (w, h);
+                    pixel_ratio = app.pixel_ratio();
+                    view.resize(w, h, pixel_ratio);
+                }
+                graphics::Action::KeyPressed(key) => {
+                    match key {
+                        graphics::Key::Right | graphics::Key::Space | graphics::Key::PageDown => {
+                            current = (current + 1) % files.len()
+                        }
+                        graphics::Key::Left | graphics::Key::Backspace | graphics::Key::PageUp => {
+                            current = (current + files.len() - 1) % files.len()
+                        }
+                        graphics::Key::Home => current = 0,
+                        graphics::Key::End => current = files.len() - 1,
+                        graphics::Key::S => {
+                            slideshow = match slideshow {
+                                Some(_) => None,
+                                None => Some(options.interval),
+                            };
+                            shown_at = time::Instant::now();
+                        }
+                        graphics::Key::A => toggle_play = true,
+                        graphics::Key::Period => step = Some(true),
+                        graphics::Key::Comma => step = Some(false),
+                        graphics::Key::P => app.save_screenshot("imgview"),
+                        graphics::Key::F => view.set_mode(view::Mode::Fit),
+                        graphics::Key::E => view.set_mode(view::Mode::Fill),
+                        graphics::Key::Num1 => view.set_mode(view::Mode::Actual),
+                        graphics::Key::Equal => view.zoom(ZOOM_STEP),
+                        graphics::Key::Minus => view.zoom(1.0 / ZOOM_STEP),
+                        graphics::Key::C => {
+                            cropping = !cropping;
+                            crop_start = None;
+                        }
+                        graphics::Key::W => save = true,
+                        _ => {
+                            if edit_key(key, &mut edits) {
+                                edits_changed = true;
+                            }
+                        }
+                    }
+                }
+                graphics::Action::Scrolled(amount) => {
+                    let (x, y) = cursor;
+                    view.zoom_at(x, y, ZOOM_STEP.powf(amount));
+                }
+                graphics::Action::CursorMoved(x, y) => {
+                    if dragging {
+                        let (last_x, last_y) = cursor;
+                        view.pan(x - last_x, y - last_y);
+                    }
+                    cursor = (x, y);
+                }
+                graphics::Action::MousePressed => {
+                    let (x, y) = cursor;
+                    if cropping {
+                        crop_start = Some(view.to_image(x, y));
+                    } else {
+                        dragging = true;
+                    }
+                }
+                graphics::Action::MouseReleased => {
+                    dragging = false;
+                    if let Some(start) = crop_start {
+                        let (x, y) = cursor;
+                        if let Some(crop) = crop_step(start, view.to_image(x, y)) {
+                            edits.steps.push(crop);
+                            edits_changed = true;
+                        }
+                        cropping = false;
+                        crop_start = None;
+                    }
+                }
+            }
+        }
 
-    let mut screen_width = 600;
-    let mut screen_height = 600;
+        // the slideshow moves on once the current image has been up for the
+        // interval, so slow loads don't get skipped
+        if let (Some(interval), Some(index)) = (slideshow, shown) {
+            if index == current && shown_at.elapsed() >= interval {
+                current = (current + 1) % files.len();
+            }
+        }
 
-    let mut app = try!(graphics::App::new(screen_width,
-                                          screen_height,
-                                          "Picture Viewer",
-                                          graphics::RenderingSource::TextureRenderingSource {
-                                              tex_def: tex_def,
-                                          }));
+        // the current image and those either side of it are the only ones
+        // worth decoding
+        let neighbours = [current,
+                          (current + 1) % files.len(),
+                          (current + files.len() - 1) % files.len()];
+        loader.keep_only(&neighbours);
 
-    loop {
-        match app.handle_events() {
-            Some(graphics::Action::Closed) => break,
-            Some(graphics::Action::Resized(w, h)) => {
-                screen_width = w;
-                screen_height = h;
+        let is_failed = failed.iter().any(|f| f.0 == current);
+        if !textures.contains(current) && !is_failed {
+            loader.request(current, &files[current]);
+            match loader.take(current) {
+                Some(Ok(image)) => {
+                    if let Err(err) = textures.insert(&mut app, current, image) {
+                        println!("{}: {}", files[current], err);
+                        failed.push((current, err.to_string()));
+                    }
+                }
+                Some(Err(err)) => {
+                    println!("{}", err);
+                    failed.push((current, err.to_string()));
+                }
+                None => (),
             }
-            None => (),
         }
+        // once the current image is up, decode the ones either side of it
+        if textures.contains(current) || is_failed {
+            for index in neighbours.iter() {
+                if !textures.contains(*index) && !failed.iter().any(|f| f.0 == *index) {
+                    loader.request(*index, &files[*index]);
+                }
+            }
+        }
+
+        let position = format!("[{}/{}] {}", current + 1, files.len(), files[current]);
+        let mut drawn = false;
+        let status = match (textures.get(current), failed.iter().find(|f| f.0 == current)) {
+            (Some(entry), _) => {
+                let frame_count = entry.image.frame_count();
+                // a cached texture may be left on any frame, so it is reset
+                let mut frame_changed = shown != Some(current);
+                if shown != Some(current) {
+                    view = view::View::new(entry.image.width,
+                                           entry.image.height,
+                                           screen.0,
+                                           screen.1,
+                                           pixel_ratio);
+                    shown = Some(current);
+                    shown_at = time::Instant::now();
+                    player = animation::Player::new(entry.image.plays);
+                    edits = edit::Edits::none();
+                    preview = None;
+                    cropping = false;
+                    crop_start = None;
+                }
+                if toggle_play {
+                    player.toggle_pause();
+                }
+                if let Some(forward) = step {
+                    player.step(frame_count, forward);
+                    frame_changed = true;
+                }
+                let advanced = match entry.image.animation() {
+                    Some(animation) => player.advance(animation),
+                    None => false,
+                };
+                if advanced || frame_changed {
+                    entry.image.seek(player.frame());
+                    if let Err(err) = app.update_tiled_texture(&entry.texture, entry.image.data()) {
+                        println!("{}: {}", files[current], err);
+                    }
+                    // edits are made to the frame that is showing
+                    if !edits.is_empty() {
+                        edits_changed = true;
+                    }
+                }
+
+                if edits_changed {
+                    player.pause();
+                    let original = edit::Pixels::new(entry.image.width,
+                                                     entry.image.height,
+                                                     entry.image.data().to_vec());
+                    let edited = edits.apply(&original);
+                    let size = (edited.width, edited.height);
+                    preview = match edits.is_empty() {
+                        true => None,
+                        false => {
+                            let texture = app.load_tiled_texture(edited.width,
+                                                                 edited.height,
+                                                                 &edited.data);
+                            match texture {
+                                Ok(texture) => Some((edited, texture)),
+                                Err(err) => {
+                                    println!("can't show the edits: {}", err);
+                                    None
+                                }
+                            }
+                        }
+                    };
+                    // turning or cropping the image fits it to the window again
+                    if view.image_size() != size {
+                        view = view::View::new(size.0, size.1, screen.0, screen.1, pixel_ratio);
+                    }
+                }
 
-        let (lower_x, upper_x, lower_y, upper_y) =
-            calc_position(width, height, screen_width, screen_height);
-        let rects: Vec<Box<graphics::VertexSpecable>> =
-            vec![Box::new(graphics::TexRect::new(lower_x, upper_x, lower_y, upper_y))];
+                let (texture, width, height, data) = match preview {
+                    Some((ref edited, ref texture)) => {
+                        (texture, edited.width, edited.height, &edited.data[..])
+                    }
+                    None => {
+                        let image = &entry.image;
+                        (&entry.texture, image.width, image.height, image.data())
+                    }
+                };
+                if save {
+                    match save_edited(&files[current], width, height, data) {
+                        Ok(filename) => println!("saved: {}", filename),
+                        Err(err) => println!("could not save: {}", err),
+                    }
+                }
+
+                let (lower_x, upper_x, lower_y, upper_y) = view.position();
+                app.draw_tiled(texture,
+                               lower_x,
+                               upper_x,
+                               lower_y,
+                               upper_y,
+                               view.pixel_scale(),
+                               view.pixel_scale() > 1.0);
+                drawn = true;
+                let mut status = status_line(&position, width, height, data, &view, cursor);
+                match (frame_count, player.is_playing()) {
+                    (0, _) => (),
+                    (count, true) => {
+                        status.push_str(&format!(" - frame {}/{}", player.frame() + 1, count))
+                    }
+                    (count, false) => {
+                        let frame = player.frame() + 1;
+                        status.push_str(&format!(" - frame {}/{} paused", frame, count))
+                    }
+                }
+                if !edits.is_empty() {
+                    status.push_str(" - edited");
+                }
+                match (cropping, crop_start) {
+                    (true, Some(start)) => {
+                        let (x, y) = cursor;
+                        let step = crop_step(start, view.to_image(x, y));
+                        if let Some(edit::Step::Crop { x, y, width, height }) = step {
+                            status.push_str(&format!(" - crop {}, {} {}x{}", x, y, width, height));
+                        }
+                    }
+                    (true, None) => status.push_str(" - drag to crop"),
+                    (false, _) => (),
+                }
+                status
+            }
+            (None, Some(&(_, ref reason))) => {
+                // a file that can't be shown still has its turn in the slideshow
+                if shown != Some(current) {
+                    shown = Some(current);
+                    shown_at = time::Instant::now();
+                }
+                format!("{} failed: {}", position, reason)
+            }
+            (None, None) => format!("{} loading", position),
+        };
+        let status = match slideshow {
+            Some(_) => format!("{} - slideshow", status),
+            None => status,
+        };
+        if status != title {
+            app.set_title(&status);
+            title = status;
+        }
 
-        app.draw(&rects);
+        if !drawn {
+            let nothing: Vec<Box<graphics::VertexSpecable>> = Vec::new();
+            app.draw(&nothing);
+        }
     }
     app.close();
     return Ok(());
 }
 
-fn calc_position(image_w: u32, image_h: u32, screen_w: u32, screen_h: u32) -> (f32, f32, f32, f32) {
-    let f_image_h = f32::from(image_h as u16);
-    let f_image_w = f32::from(image_w as u16);
-    let f_screen_h = f32::from(screen_h as u16);
-    let f_screen_w = f32::from(screen_w as u16);
-
-    let image_ratio = f_image_w / f_image_h;
-    let screen_ratio = f_screen_w / f_screen_h;
-
-    if image_ratio > screen_ratio {
-        let half = (f_screen_h - ((f_screen_w / f_image_w) * f_image_h)) / (f_screen_h);
-        return (-1.0, 1.0, -1.0 + half, 1.0 - half);
-    } else {
-        let half = (f_screen_w - ((f_screen_h / f_image_h) * f_image_w)) / (f_screen_w);
-        return (-1.0 + half, 1.0 - half, -1.0, 1.0);
+// CACHED_TEXTURES is how many images are kept on the graphics card at once
+const CACHED_TEXTURES: usize = 6;
+
+struct Options {
+    files: Vec<String>,
+    // the slideshow's interval, if it starts running
+    slideshow: Option<time::Duration>,
+    interval: time::Duration,
+}
+
+// parse_options reads the images and directories to view, and an optional
+// --slideshow <seconds> to start a slideshow
+fn parse_options(args: &[String]) -> Result<Options, Box<error::Error>> {
+    let mut options = Options {
+        files: Vec::new(),
+        slideshow: None,
+        interval: time::Duration::from_secs(3),
+    };
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--slideshow" {
+            let seconds: f32 = try!(rest.next().ok_or(Error::ArgError).and_then(|s| {
+                s.parse().map_err(|_| Error::ArgError)
+            }));
+            let millis = (seconds.max(0.0) * 1000.0) as u64;
+            options.interval = time::Duration::from_millis(millis);
+            options.slideshow = Some(options.interval);
+        } else if path::Path::new(arg).is_dir() {
+            options.files.extend(try!(image_files(arg)));
+        } else {
+            options.files.push(arg.clone());
+        }
     }
+    if options.files.is_empty() {
+        return Err(Box::new(Error::ArgError));
+    }
+    return Ok(options);
 }
 
+// image_files lists the files in the directory that look like images, by
+// their extensions, in name order
+fn image_files(directory: &str) -> Result<Vec<String>, Error> {
+    let extensions = ["png", "jpg", "jpeg", "gif", "bmp", "tga", "webp", "pbm", "pgm", "ppm",
+                      "pnm"];
+    let entries = try!(fs::read_dir(directory)
+        .map_err(|e| Error::ReadError(directory.to_string(), e.to_string())));
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = try!(entry.map_err(|e| Error::ReadError(directory.to_string(), e.to_string())))
+            .path();
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or(String::new());
+        if extensions.contains(&extension.as_str()) {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    files.sort();
+    return Ok(files);
+}
+
+// ZOOM_STEP is how much one notch of the scroll wheel, or a press of + or
+// -, zooms by
+const ZOOM_STEP: f32 = 1.25;
+
+// status_line describes the image, the zoom and the pixel under the cursor
+fn status_line(name: &str,
+               width: u32,
+               height: u32,
+               data: &[u8],
+               view: &view::View,
+               cursor: (f32, f32))
+               -> String {
+    let mode = match view.mode() {
+        view::Mode::Fit => " fit",
+        view::Mode::Fill => " fill",
+        view::Mode::Actual => " 1:1",
+        view::Mode::Free => "",
+    };
+    let mut status = format!("{} ({}x{}) {:.0}%{}",
+                             name,
+                             width,
+                             height,
+                             view.pixel_scale() * 100.0,
+                             mode);
+    let (x, y) = cursor;
+    if let Some((pixel_x, pixel_y)) = view.pixel_at(x, y) {
+        let start = (pixel_y as usize * width as usize + pixel_x as usize) * 4;
+        let rgba = &data[start..start + 4];
+        status.push_str(&format!(" - {}, {}: rgba({}, {}, {}, {})",
+                                 pixel_x,
+                                 pixel_y,
+                                 rgba[0],
+                                 rgba[1],
+                                 rgba[2],
+                                 rgba[3]));
+    }
+    return status;
+}
+
+// edit_key makes the edit a key is for, and says if there was one
+fn edit_key(key: graphics::Key, edits: &mut edit::Edits) -> bool {
+    let adjustments = edits.adjustments;
+    match key {
+        graphics::Key::R => edits.steps.push(edit::Step::RotateClockwise),
+        graphics::Key::L => edits.steps.push(edit::Step::RotateCounterClockwise),
+        graphics::Key::H => edits.steps.push(edit::Step::FlipHorizontal),
+        graphics::Key::V => edits.steps.push(edit::Step::FlipVertical),
+        graphics::Key::LeftBracket => edits.adjustments.brightness = adjustments.brightness - 0.05,
+        graphics::Key::RightBracket => edits.adjustments.brightness = adjustments.brightness + 0.05,
+        graphics::Key::Semicolon => {
+            edits.adjustments.contrast = (adjustments.contrast - 0.1).max(0.0)
+        }
+        graphics::Key::Apostrophe => edits.adjustments.contrast = adjustments.contrast + 0.1,
+        graphics::Key::Num9 => edits.adjustments.gamma = (adjustments.gamma - 0.1).max(0.1),
+        graphics::Key::Num0 => edits.adjustments.gamma = adjustments.gamma + 0.1,
+        graphics::Key::G => edits.greyscale = !edits.greyscale,
+        graphics::Key::I => {
+            edits.channel = match edits.channel {
+                None => Some(edit::Channel::Red),
+                Some(edit::Channel::Red) => Some(edit::Channel::Green),
+                Some(edit::Channel::Green) => Some(edit::Channel::Blue),
+                Some(edit::Channel::Blue) => Some(edit::Channel::Alpha),
+                Some(edit::Channel::Alpha) => None,
+            }
+        }
+        graphics::Key::Z => {
+            edits.steps.pop();
+        }
+        graphics::Key::X => *edits = edit::Edits::none(),
+        _ => return false,
+    }
+    return true;
+}
+
+// crop_step is the crop to the rectangle between two points in the image,
+// if it has any size
+fn crop_step(start: (f32, f32), end: (f32, f32)) -> Option<edit::Step> {
+    let left = start.0.min(end.0).max(0.0).round() as u32;
+    let right = start.0.max(end.0).max(0.0).round() as u32;
+    let top = start.1.min(end.1).max(0.0).round() as u32;
+    let bottom = start.1.max(end.1).max(0.0).round() as u32;
+    if right <= left || bottom <= top {
+        return None;
+    }
+    return Some(edit::Step::Crop {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    });
+}
+
+// save_edited writes the image as a png next to the original, named after
+// it, without overwriting anything
+fn save_edited(original: &str,
+               width: u32,
+               height: u32,
+               data: &[u8])
+               -> Result<String, Box<error::Error>> {
+    let original = path::Path::new(original);
+    let stem = original.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    let mut number = 1;
+    let mut filename = original.with_file_name(format!("{}-edited.png", stem));
+    while filename.exists() {
+        number += 1;
+        filename = original.with_file_name(format!("{}-edited-{}.png", stem, number));
+    }
+    try!(image::save_buffer(&filename, data, width, height, image::RGBA(8)));
+    return Ok(filename.to_string_lossy().into_owned());
+}
 
 #[derive(Debug)]
-enum Error {
-    U8OnlyError,
+pub enum Error {
     ArgError,
+    // the file couldn't be read, with the reason
+    ReadError(String, String),
+    // the file isn't in a format imgview knows
+    UnknownFormatError(String),
+    DecodeError {
+        filename: String,
+        format: decode::Format,
+        reason: String,
+    },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::U8OnlyError => write!(f, "can only accept u8 for the image"),
-            Error::ArgError => write!(f, "must supply png to load"),
+            Error::ArgError => {
+                write!(f,
+                       "must supply images or directories to view, and optionally \
+                        --slideshow <seconds>")
+            }
+            Error::ReadError(ref filename, ref reason) => {
+                write!(f, "can't read {}: {}", filename, reason)
+            }
+            Error::UnknownFormatError(ref filename) => {
+                write!(f,
+                       "can't tell what format {} is in (known formats are PNG, JPEG, GIF, BMP, \
+                        TGA, WebP and PNM)",
+                       filename)
+            }
+            Error::DecodeError { ref filename, ref format, ref reason } => {
+                write!(f, "can't decode {} as {}: {}", filename, format, reason)
+            }
         }
     }
 }
@@ -112,8 +563,10 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
-            Error::U8OnlyError => "can only accept u8 for the image",
-            Error::ArgError => "must supply png to load",
+            Error::ArgError => "must supply images or directories to view",
+            Error::ReadError(_, _) => "image file read error",
+            Error::UnknownFormatError(_) => "unknown image format",
+            Error::DecodeError { .. } => "image decode error",
         }
     }
 }
@@ -0,0 +1,150 @@
+use image;
+
+use std::cmp;
+
+// decode reads the netpbm formats: plain (P1-P3) and raw (P4-P6) bitmaps,
+// greymaps and pixmaps. Values are scaled from the file's maximum to 8 bits
+pub fn decode(contents: &[u8])
+              -> Result<(u32, u32, image::ColorType, image::DecodingResult), String> {
+    let mut header = Header {
+        contents: contents,
+        position: 2,
+    };
+    let kind = if contents.len() >= 2 { contents[1] } else { 0 };
+    let width = try!(header.number("width"));
+    let height = try!(header.number("height"));
+    let max = match kind {
+        b'1' | b'4' => 1,
+        _ => try!(header.number("maximum value")),
+    };
+    if max == 0 || max > 65535 {
+        return Err(format!("maximum value {} is out of range", max));
+    }
+    let (channels, color) = match kind {
+        b'1' | b'2' | b'4' | b'5' => (1, image::ColorType::Gray(8)),
+        b'3' | b'6' => (3, image::ColorType::RGB(8)),
+        _ => return Err("not a netpbm file".to_string()),
+    };
+    let count = width as usize * height as usize * channels;
+    // every value takes at least a byte (a bit in a raw bitmap), so a size
+    // that the rest of the file couldn't hold is refused before anything is
+    // set aside for it
+    let least = match kind {
+        b'4' => (width as usize + 7) / 8 * height as usize,
+        b'5' | b'6' if max > 255 => count * 2,
+        _ => count,
+    };
+    let remaining = contents.len().saturating_sub(header.position + 1);
+    if least > remaining {
+        return Err(format!("{}x{} needs at least {} bytes of pixels but there are {}",
+                           width,
+                           height,
+                           least,
+                           remaining));
+    }
+
+    // a single whitespace character separates the header from raw data
+    let data: &[u8] = if header.position < contents.len() {
+        &contents[header.position + 1..]
+    } else {
+        &[]
+    };
+    let values: Vec<u32> = match kind {
+        b'1' | b'2' | b'3' => {
+            let mut values = Vec::with_capacity(count);
+            for _ in 0..count {
+                // plain bitmap pixels don't need anything between them
+                values.push(try!(match kind {
+                    b'1' => header.bit(),
+                    _ => header.number("pixel value"),
+                }));
+            }
+            values
+        }
+        b'4' => try!(bits(data, width as usize, height as usize)),
+        _ => try!(samples(data, count, max > 255)),
+    };
+
+    let pixels: Vec<u8> = match kind {
+        // bitmaps are 1 for black
+        b'1' | b'4' => values.iter().map(|v| if *v == 0 { 255 } else { 0 }).collect(),
+        _ => values.iter().map(|v| (cmp::min(*v, max) * 255 / max) as u8).collect(),
+    };
+    return Ok((width, height, color, image::DecodingResult::U8(pixels)));
+}
+
+struct Header<'a> {
+    contents: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Header<'a> {
+    // number reads the next whitespace separated number, skipping comments
+    fn number(&mut self, name: &str) -> Result<u32, String> {
+        self.skip_space();
+        let start = self.position;
+        while self.contents.get(self.position).map_or(false, |c| (*c as char).is_digit(10)) {
+            self.position += 1;
+        }
+        let digits = String::from_utf8_lossy(&self.contents[start..self.position]).into_owned();
+        return digits.parse().map_err(|_| format!("expected the {} at byte {}", name, start));
+    }
+
+    fn bit(&mut self) -> Result<u32, String> {
+        self.skip_space();
+        let position = self.position;
+        self.position += 1;
+        return match self.contents.get(position) {
+            Some(&b'0') => Ok(0),
+            Some(&b'1') => Ok(1),
+            _ => Err(format!("expected a pixel value of 0 or 1 at byte {}", position)),
+        };
+    }
+
+    fn skip_space(&mut self) {
+        loop {
+            match self.contents.get(self.position) {
+                Some(&b'#') => {
+                    while self.contents.get(self.position).map_or(false, |c| *c != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                Some(c) if (*c as char).is_whitespace() => self.position += 1,
+                _ => return,
+            }
+        }
+    }
+}
+
+// bits reads raw bitmap rows, which are packed 8 pixels to a byte and padded
+// to a whole byte
+fn bits(data: &[u8], width: usize, height: usize) -> Result<Vec<u32>, String> {
+    let row_bytes = (width + 7) / 8;
+    if data.len() < row_bytes * height {
+        return Err("the pixel data ends early".to_string());
+    }
+    let mut values = Vec::with_capacity(width * height);
+    for row in data.chunks(row_bytes).take(height) {
+        for x in 0..width {
+            values.push(u32::from((row[x / 8] >> (7 - x % 8)) & 1));
+        }
+    }
+    return Ok(values);
+}
+
+// samples reads raw values, which take two bytes (most significant first)
+// when the maximum is over 255
+fn samples(data: &[u8], count: usize, wide: bool) -> Result<Vec<u32>, String> {
+    let size = if wide { 2 } else { 1 };
+    if data.len() < count * size {
+        return Err("the pixel data ends early".to_string());
+    }
+    return Ok(data.chunks(size)
+        .take(count)
+        .map(|v| if wide {
+            u32::from(v[0]) << 8 | u32::from(v[1])
+        } else {
+            u32::from(v[0])
+        })
+        .collect());
+}
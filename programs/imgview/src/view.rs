@@ -0,0 +1,144 @@
+// Mode is how the view keeps the image sized as the window changes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    // the whole image, as large as fits in the window
+    Fit,
+    // the window covered by the image, cutting off what spills over
+    Fill,
+    // one image pixel to one screen pixel
+    Actual,
+    // wherever it was zoomed and panned to
+    Free,
+}
+
+const MIN_SCALE: f32 = 0.01;
+const MAX_SCALE: f32 = 64.0;
+
+// View is where the image sits in the window. Both are measured from their
+// top-left corners, the image in pixels and the window in screen coordinates
+// as the cursor is: scale is how many screen coordinates an image pixel
+// covers, and center is the point in the image at the window's center
+pub struct View {
+    image: (f32, f32),
+    screen: (f32, f32),
+    // pixels to a screen coordinate, more than one on high density displays
+    pixel_ratio: f32,
+    scale: f32,
+    center: (f32, f32),
+    mode: Mode,
+}
+
+impl View {
+    pub fn new(image_w: u32, image_h: u32, screen_w: u32, screen_h: u32, pixel_ratio: f32) -> View {
+        let mut view = View {
+            image: (image_w as f32, image_h as f32),
+            screen: (screen_w as f32, screen_h as f32),
+            pixel_ratio: pixel_ratio,
+            scale: 1.0,
+            center: (0.0, 0.0),
+            mode: Mode::Fit,
+        };
+        view.set_mode(Mode::Fit);
+        return view;
+    }
+
+    // set_mode resizes and centers the image for the mode, and keeps doing
+    // so as the window is resized until the image is zoomed or panned
+    pub fn set_mode(&mut self, mode: Mode) {
+        let (image_w, image_h) = self.image;
+        let (screen_w, screen_h) = self.screen;
+        let fit_w = screen_w / image_w.max(1.0);
+        let fit_h = screen_h / image_h.max(1.0);
+        self.scale = match mode {
+            Mode::Fit => fit_w.min(fit_h),
+            Mode::Fill => fit_w.max(fit_h),
+            Mode::Actual => 1.0 / self.pixel_ratio,
+            Mode::Free => self.scale,
+        };
+        if mode != Mode::Free {
+            self.center = (image_w / 2.0, image_h / 2.0);
+        }
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> Mode {
+        return self.mode;
+    }
+
+    pub fn image_size(&self) -> (u32, u32) {
+        return (self.image.0 as u32, self.image.1 as u32);
+    }
+
+    // pixel_scale is how many pixels of the screen an image pixel covers
+    pub fn pixel_scale(&self) -> f32 {
+        return self.scale * self.pixel_ratio;
+    }
+
+    pub fn resize(&mut self, screen_w: u32, screen_h: u32, pixel_ratio: f32) {
+        self.screen = (screen_w as f32, screen_h as f32);
+        self.pixel_ratio = pixel_ratio;
+        let mode = self.mode;
+        self.set_mode(mode);
+    }
+
+    // zoom_at scales the image by the factor, keeping the image point under
+    // the screen point where it is
+    pub fn zoom_at(&mut self, screen_x: f32, screen_y: f32, factor: f32) {
+        let (image_x, image_y) = self.to_image(screen_x, screen_y);
+        self.scale = (self.scale * factor).max(MIN_SCALE).min(MAX_SCALE);
+        let (offset_x, offset_y) = self.from_center(screen_x, screen_y);
+        self.center = (image_x - offset_x / self.scale, image_y - offset_y / self.scale);
+        self.mode = Mode::Free;
+    }
+
+    // zoom scales the image about the window's center
+    pub fn zoom(&mut self, factor: f32) {
+        let (screen_w, screen_h) = self.screen;
+        self.zoom_at(screen_w / 2.0, screen_h / 2.0, factor);
+    }
+
+    // pan moves the image along with the cursor, by screen pixels
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let (center_x, center_y) = self.center;
+        self.center = (center_x - dx / self.scale, center_y - dy / self.scale);
+        self.mode = Mode::Free;
+    }
+
+    // to_image is the point in the image under a point on the screen
+    pub fn to_image(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
+        let (offset_x, offset_y) = self.from_center(screen_x, screen_y);
+        let (center_x, center_y) = self.center;
+        return (center_x + offset_x / self.scale, center_y + offset_y / self.scale);
+    }
+
+    // pixel_at is the pixel under a point on the screen, if there is one
+    pub fn pixel_at(&self, screen_x: f32, screen_y: f32) -> Option<(u32, u32)> {
+        let (x, y) = self.to_image(screen_x, screen_y);
+        let (image_w, image_h) = self.image;
+        if x < 0.0 || y < 0.0 || x >= image_w || y >= image_h {
+            return None;
+        }
+        return Some((x as u32, y as u32));
+    }
+
+    // position is where to draw the image, as the lower_x, upper_x,
+    // lower_y and upper_y of a TexRect
+    pub fn position(&self) -> (f32, f32, f32, f32) {
+        let (image_w, image_h) = self.image;
+        let (screen_w, screen_h) = self.screen;
+        let (center_x, center_y) = self.center;
+        let left = screen_w / 2.0 - center_x * self.scale;
+        let right = left + image_w * self.scale;
+        let top = screen_h / 2.0 - center_y * self.scale;
+        let bottom = top + image_h * self.scale;
+        return (left / screen_w * 2.0 - 1.0,
+                right / screen_w * 2.0 - 1.0,
+                1.0 - bottom / screen_h * 2.0,
+                1.0 - top / screen_h * 2.0);
+    }
+
+    fn from_center(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
+        let (screen_w, screen_h) = self.screen;
+        return (screen_x - screen_w / 2.0, screen_y - screen_h / 2.0);
+    }
+}
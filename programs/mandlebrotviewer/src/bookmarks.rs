@@ -0,0 +1,68 @@
+use diagnostics;
+
+use std::error;
+use std::fs;
+use std::io::Write;
+use std::path;
+
+// Bookmark is a named place in the plane to jump back to, and the
+// iterations to draw it with if it needs more than usual
+#[derive(RustcDecodable, Clone, Debug)]
+pub struct Bookmark {
+    pub name: String,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub zoom: f64,
+    pub iterations: Option<u32>,
+}
+
+// load reads a bookmarks file, a csv with name, center_x, center_y, zoom
+// and (optionally) iterations columns. A file that isn't there yet has no
+// bookmarks in it
+pub fn load(filename: &str) -> Result<Vec<Bookmark>, Box<error::Error>> {
+    if !path::Path::new(filename).exists() {
+        return Ok(Vec::new());
+    }
+    let rows = try!(diagnostics::read_csv::<Bookmark>(filename, diagnostics::no_accepted_values));
+    let mut problems = Vec::new();
+    for row in rows.iter() {
+        if !(row.value.zoom > 0.0) {
+            problems.push(diagnostics::Diagnostic::new("must be greater than zero".to_string())
+                .in_file(filename)
+                .at_row(row.number)
+                .in_column("zoom")
+                .with_value(&row.value.zoom.to_string()));
+        }
+    }
+    try!(diagnostics::Diagnostics::check(problems));
+    return Ok(rows.into_iter().map(|row| row.value).collect());
+}
+
+// save writes the bookmarks over the file, in the form load reads
+pub fn save(filename: &str, bookmarks: &[Bookmark]) -> Result<(), Box<error::Error>> {
+    let mut contents = "name,center_x,center_y,zoom,iterations\n".to_string();
+    for bookmark in bookmarks.iter() {
+        let iterations = match bookmark.iterations {
+            Some(iterations) => iterations.to_string(),
+            None => String::new(),
+        };
+        contents.push_str(&format!("{},{},{},{},{}\n",
+                                   quote(&bookmark.name),
+                                   bookmark.center_x,
+                                   bookmark.center_y,
+                                   bookmark.zoom,
+                                   iterations));
+    }
+    let mut file = try!(fs::File::create(filename));
+    try!(file.write_all(contents.as_bytes()));
+    return Ok(());
+}
+
+// quote puts a name in quotes if it has anything in it that csv would
+// otherwise take as the end of the field
+fn quote(field: &str) -> String {
+    if !field.contains(|c: char| c == ',' || c == '"' || c == '\n') {
+        return field.to_string();
+    }
+    return format!("\"{}\"", field.replace("\"", "\"\""));
+}
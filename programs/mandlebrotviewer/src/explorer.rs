@@ -0,0 +1,168 @@
+use mandelbrot;
+
+use bookmarks;
+
+// Explorer is the view being explored, along with the views it came from
+// and those it went back from, like a browser's history
+pub struct Explorer {
+    request: mandelbrot::RenderRequest,
+    home: mandelbrot::RenderRequest,
+    back: Vec<mandelbrot::RenderRequest>,
+    forward: Vec<mandelbrot::RenderRequest>,
+}
+
+impl Explorer {
+    pub fn new(request: mandelbrot::RenderRequest) -> Explorer {
+        return Explorer {
+            request: request.clone(),
+            home: request,
+            back: Vec::new(),
+            forward: Vec::new(),
+        };
+    }
+
+    pub fn request(&self) -> &mandelbrot::RenderRequest {
+        return &self.request;
+    }
+
+    // go_to shows another view, remembering this one to go back to, and
+    // forgetting the views that had been gone back from
+    pub fn go_to(&mut self, request: mandelbrot::RenderRequest) {
+        if request == self.request {
+            return;
+        }
+        let previous = self.request.clone();
+        self.back.push(previous);
+        self.forward.clear();
+        self.request = request;
+    }
+
+    // go_back goes to the view before this one, and says if there was one
+    pub fn go_back(&mut self) -> bool {
+        return match self.back.pop() {
+            Some(request) => {
+                let current = self.request.clone();
+                self.forward.push(current);
+                self.request = self.sized(request);
+                true
+            }
+            None => false,
+        };
+    }
+
+    // go_forward goes to the view last gone back from, and says if there was
+    // one
+    pub fn go_forward(&mut self) -> bool {
+        return match self.forward.pop() {
+            Some(request) => {
+                let current = self.request.clone();
+                self.back.push(current);
+                self.request = self.sized(request);
+                true
+            }
+            None => false,
+        };
+    }
+
+    pub fn go_home(&mut self) {
+        let home = self.sized(self.home.clone());
+        self.go_to(home);
+    }
+
+    pub fn jump_to(&mut self, bookmark: &bookmarks::Bookmark) {
+        let frame = self.centered(bookmark.center_x, bookmark.center_y, bookmark.zoom);
+        let mut request = self.request.clone();
+        request.frame = frame;
+        if let Some(iterations) = bookmark.iterations {
+            request.iterations = iterations;
+        }
+        self.go_to(request);
+    }
+
+    // bookmark is a bookmark of the view, with the name
+    pub fn bookmark(&self, name: &str) -> bookmarks::Bookmark {
+        let (center_x, center_y) = self.request.frame.center();
+        return bookmarks::Bookmark {
+            name: name.to_string(),
+            center_x: center_x,
+            center_y: center_y,
+            zoom: self.request.frame.zoom(),
+            iterations: Some(self.request.iterations),
+        };
+    }
+
+    // zoom_at zooms by the factor, keeping the point under the screen point
+    // where it is
+    pub fn zoom_at(&mut self, screen_x: f64, screen_y: f64, factor: f64) {
+        let frame = self.request.frame;
+        let (x, y) = frame.get_coord_for_point(screen_x, screen_y);
+        let (center_x, center_y) = frame.center();
+        let frame = self.centered(x + (center_x - x) / factor,
+                                  y + (center_y - y) / factor,
+                                  frame.zoom() * factor);
+        self.go_to_frame(frame);
+    }
+
+    // center_on moves the point under the screen point to the middle, and
+    // zooms by the factor
+    pub fn center_on(&mut self, screen_x: f64, screen_y: f64, factor: f64) {
+        let frame = self.request.frame;
+        let (x, y) = frame.get_coord_for_point(screen_x, screen_y);
+        let frame = self.centered(x, y, frame.zoom() * factor);
+        self.go_to_frame(frame);
+    }
+
+    // pan moves the view by parts of its width and height, right and down
+    pub fn pan(&mut self, across: f64, down: f64) {
+        let frame = self.request.frame;
+        let (x, y) = frame.get_coord_for_point(f64::from(frame.screen_width) * (0.5 + across),
+                                               f64::from(frame.screen_height) * (0.5 + down));
+        let frame = self.centered(x, y, frame.zoom());
+        self.go_to_frame(frame);
+    }
+
+    pub fn zoom(&mut self, factor: f64) {
+        let frame = self.request.frame;
+        let frame = self.centered(frame.center().0, frame.center().1, frame.zoom() * factor);
+        self.go_to_frame(frame);
+    }
+
+    // resize draws the view on a screen of a new size, keeping its center
+    // and zoom. This isn't a new view, so it doesn't go in the history
+    pub fn resize(&mut self, screen_width: u32, screen_height: u32) {
+        let frame = self.request.frame;
+        let (center_x, center_y) = frame.center();
+        self.request.frame = mandelbrot::Frame::centered(screen_width,
+                                                         screen_height,
+                                                         center_x,
+                                                         center_y,
+                                                         frame.zoom())
+            .with_y_axis(frame.y_axis);
+    }
+
+    fn go_to_frame(&mut self, frame: mandelbrot::Frame) {
+        let mut request = self.request.clone();
+        request.frame = frame;
+        self.go_to(request);
+    }
+
+    // centered is a view of the point at the zoom, the size of the screen
+    fn centered(&self, center_x: f64, center_y: f64, zoom: f64) -> mandelbrot::Frame {
+        let frame = self.request.frame;
+        return mandelbrot::Frame::centered(frame.screen_width,
+                                           frame.screen_height,
+                                           center_x,
+                                           center_y,
+                                           zoom)
+            .with_y_axis(frame.y_axis);
+    }
+
+    // sized is a view from the history redrawn at the screen's current size
+    fn sized(&self, request: mandelbrot::RenderRequest) -> mandelbrot::RenderRequest {
+        let mut request = request;
+        let frame = request.frame;
+        request.frame = self.centered(frame.center().0, frame.center().1, frame.zoom())
+            .with_y_axis(frame.y_axis);
+        return request;
+    }
+}
@@ -1,25 +1,83 @@
+extern crate diagnostics;
 extern crate graphics;
 extern crate mandelbrot;
+extern crate rustc_serialize;
+
+mod bookmarks;
+mod explorer;
 
 use std::env;
 use std::error;
 use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
 use std::path;
+use std::process;
+use std::thread;
+use std::time;
+
+const USAGE: &'static str = "usage:
+  mandelbrotviewer render [frame.csv] [options]   render a view to a png
+  mandelbrotviewer explore [frame.csv] [options]  explore a view in a window
+  mandelbrotviewer batch <frame.csv>... [--out <dir>] [options]
+                                                  render each frame file to a png in the directory
+  mandelbrotviewer --help                         show this help
+
+options (they change the frame file's view, if one is given):
+  --width <pixels>       image width (900)
+  --height <pixels>      image height (720)
+  --center <x>,<y>       point at the middle of the view (-0.55,0)
+  --zoom <factor>        zoom, where 1 shows 2.8 of the imaginary axis (1)
+  --iterations <count>   iterations before a point is taken to be in the set (100)
+  --palette <name>       YellowToBlue, Greyscale or Fire (YellowToBlue)
+  --out <file>           png for render to write (generated/<frame>_<width>x<height>_mandelbrot.png)
+  --bookmarks <file>     bookmarks csv for explore to read and add to (bookmarks.csv)
+
+explore keys:
+  arrows pan, = and - zoom, the wheel zooms at the cursor, a click centers on the cursor
+  [ and ] go back and forward through the views seen, Home goes to the first
+  S saves the view as a frame file and R renders it at high resolution, both in generated/
+  B bookmarks the view, 1 to 9 jump to the bookmarks and , and . step through them
+  P saves a screenshot
+
+img <frame.csv> and app are the older names of render and explore.";
+
+// OUTPUT_DIRECTORY is where renders go when they aren't told where
+const OUTPUT_DIRECTORY: &'static str = "generated";
+
+const DEFAULT_BOOKMARKS: &'static str = "bookmarks.csv";
+
+// ZOOM_STEP is how much a key press or a notch of the wheel zooms by
+const ZOOM_STEP: f64 = 1.5;
+
+// PAN_STEP is how much of the view an arrow key moves it by
+const PAN_STEP: f64 = 0.25;
+
+// HIGH_RES_SCALE is how many times the explorer's size across and down its
+// high resolution renders are
+const HIGH_RES_SCALE: u32 = 4;
 
 #[derive(Debug)]
-struct ArgError {}
+struct ArgError {
+    message: String,
+}
+
+impl ArgError {
+    fn new(message: &str) -> ArgError {
+        return ArgError { message: message.to_string() };
+    }
+}
 
 impl fmt::Display for ArgError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,
-               "mandelbrotviewer command line error, must supplier one of app or img, and a frame \
-                file to read if image")
+        write!(f, "{} (see mandelbrotviewer --help)", self.message)
     }
 }
 
 impl error::Error for ArgError {
     fn description(&self) -> &str {
-        return "usage: mandelbrotviewer [app|img frames/default.csv]";
+        return "mandelbrotviewer command line error";
     }
 
     fn cause(&self) -> Option<&error::Error> {
@@ -47,95 +105,370 @@ impl error::Error for FilepathParsingError {
 }
 
 fn main() {
-    println!("plotting mandlebrot");
     match run_main() {
-        Ok(_) => println!("ran successfully"),
-        Err(err) => println!("error: {} (reason: {})", err, err.description()),
+        Ok(_) => (),
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "error: {}", err);
+            process::exit(1);
+        }
     };
-    println!("exiting");
 }
 
 fn run_main() -> Result<(), Box<error::Error>> {
     let args: Vec<String> = env::args().collect();
     if args.len() <= 1 {
-        return Err(Box::new(ArgError {}));
+        return Err(Box::new(ArgError::new("a command is needed")));
     }
+    let rest = &args[2..];
     match args[1].as_str() {
-        "img" => {
-            match args.len() {
-                3 => gen_photo(args[2].as_str()),
-                _ => Err(Box::new(ArgError {})),
-            }
+        "--help" | "-h" | "help" => {
+            println!("{}", USAGE);
+            return Ok(());
         }
-        "app" => explore_mandelbrot(),
-        _ => Err(Box::new(ArgError {})),
+        "render" | "img" => {
+            let options = try!(parse_options(rest));
+            return render(&try!(build_request(&options, true)));
+        }
+        "explore" | "app" => {
+            let options = try!(parse_options(rest));
+            let bookmarks = options.bookmarks.clone().unwrap_or(DEFAULT_BOOKMARKS.to_string());
+            return explore_mandelbrot(try!(build_request(&options, false)), &bookmarks);
+        }
+        "batch" => return batch(&try!(parse_options(rest))),
+        command => return Err(Box::new(ArgError::new(&format!("unknown command {}", command)))),
     }
 }
 
-fn gen_photo(filename: &str) -> Result<(), Box<error::Error>> {
-    println!("reading: {}", filename);
-    let frame = try!(mandelbrot::parse_frame(filename));
+struct Options {
+    frame_files: Vec<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    center: Option<(f64, f64)>,
+    zoom: Option<f64>,
+    iterations: Option<u32>,
+    palette: Option<mandelbrot::Palette>,
+    out: Option<String>,
+    bookmarks: Option<String>,
+}
 
-    let stem: &path::Path =
-        try!(path::Path::new(filename).file_stem().ok_or(FilepathParsingError {})).as_ref();
+// parse_options reads frame files and flags, each flag with a value, in any
+// order
+fn parse_options(args: &[String]) -> Result<Options, Box<error::Error>> {
+    let mut options = Options {
+        frame_files: Vec::new(),
+        width: None,
+        height: None,
+        center: None,
+        zoom: None,
+        iterations: None,
+        palette: None,
+        out: None,
+        bookmarks: None,
+    };
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if !arg.starts_with("--") {
+            options.frame_files.push(arg.clone());
+            continue;
+        }
+        let value = try!(rest.next().ok_or(ArgError::new(&format!("{} needs a value", arg))));
+        let invalid = || ArgError::new(&format!("{} can't be {}", arg, value));
+        match arg.as_str() {
+            "--width" => options.width = Some(try!(value.parse().map_err(|_| invalid()))),
+            "--height" => options.height = Some(try!(value.parse().map_err(|_| invalid()))),
+            "--center" => options.center = Some(try!(parse_center(value).ok_or(invalid()))),
+            "--zoom" => options.zoom = Some(try!(value.parse().map_err(|_| invalid()))),
+            "--iterations" => options.iterations = Some(try!(value.parse().map_err(|_| invalid()))),
+            "--palette" => options.palette = Some(try!(parse_palette(value).ok_or(invalid()))),
+            "--out" => options.out = Some(value.clone()),
+            "--bookmarks" => options.bookmarks = Some(value.clone()),
+            _ => return Err(Box::new(ArgError::new(&format!("unknown option {}", arg)))),
+        }
+    }
+    let counts = [("--width", options.width),
+                  ("--height", options.height),
+                  ("--iterations", options.iterations)];
+    for &(flag, count) in counts.iter() {
+        if count == Some(0) {
+            return Err(Box::new(ArgError::new(&format!("{} must be greater than zero", flag))));
+        }
+    }
+    if let Some(zoom) = options.zoom {
+        if !(zoom > 0.0) {
+            return Err(Box::new(ArgError::new("--zoom must be greater than zero")));
+        }
+    }
+    return Ok(options);
+}
 
-    let prefix = path::Path::new("generated").join(stem).as_path().to_owned();
+// parse_center reads a point written as <x>,<y>
+fn parse_center(value: &str) -> Option<(f64, f64)> {
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    return match (parts[0].trim().parse(), parts[1].trim().parse()) {
+        (Ok(x), Ok(y)) => Some((x, y)),
+        _ => None,
+    };
+}
 
-    let prefix_path = try!(prefix.to_str()
-        .ok_or(FilepathParsingError {}));
+// parse_palette reads a palette's name, in any case
+fn parse_palette(value: &str) -> Option<mandelbrot::Palette> {
+    let palettes = [("yellowtoblue", mandelbrot::Palette::YellowToBlue),
+                    ("greyscale", mandelbrot::Palette::Greyscale),
+                    ("fire", mandelbrot::Palette::Fire)];
+    let name = value.to_lowercase();
+    return palettes.iter().find(|p| p.0 == name).map(|p| p.1);
+}
 
-    println!("writing with prefix: {}", prefix_path);
+// build_request starts from the frame file, if there is one, or the whole
+// set, and changes whatever the flags say to. Renders that aren't told
+// where to go are written to the output directory, named after the frame
+// file
+fn build_request(options: &Options,
+                 needs_output: bool)
+                 -> Result<mandelbrot::RenderRequest, Box<error::Error>> {
+    let mut request = match options.frame_files.len() {
+        0 => mandelbrot::RenderRequest::new(mandelbrot::Frame::centered(900, 720, -0.55, 0.0, 1.0)),
+        1 => try!(mandelbrot::parse_frame(&options.frame_files[0])),
+        _ => return Err(Box::new(ArgError::new("only one frame file can be given"))),
+    };
+    request = apply_options(request, options);
 
-    return mandelbrot::write_png(prefix_path,
-                                 mandelbrot::Frame::new(frame.screen_width,
-                                                        frame.screen_height,
-                                                        frame.x_min,
-                                                        frame.x_max,
-                                                        frame.y_min,
-                                                        frame.y_max),
-                                 frame.iterations);
+    if let Some(ref out) = options.out {
+        request = request.with_output(out);
+    }
+    if needs_output && request.output.is_none() {
+        let name = match options.frame_files.first() {
+            Some(filename) => try!(file_stem(filename)),
+            None => "mandelbrot".to_string(),
+        };
+        let output = try!(default_output(OUTPUT_DIRECTORY, &name, &request));
+        request = request.with_output(&output);
+    }
+    return Ok(request);
+}
 
+// apply_options changes the request's view, iterations and palette to those
+// the flags give. A new size, center or zoom keeps the plane's proportions,
+// and the rest of the view stays as it was
+fn apply_options(request: mandelbrot::RenderRequest,
+                 options: &Options)
+                 -> mandelbrot::RenderRequest {
+    let mut request = request;
+    let frame = request.frame;
+    if options.width.is_some() || options.height.is_some() || options.center.is_some() ||
+       options.zoom.is_some() {
+        let (center_x, center_y) = options.center.unwrap_or(frame.center());
+        request.frame = mandelbrot::Frame::centered(options.width.unwrap_or(frame.screen_width),
+                                                    options.height.unwrap_or(frame.screen_height),
+                                                    center_x,
+                                                    center_y,
+                                                    options.zoom.unwrap_or(frame.zoom()))
+            .with_y_axis(frame.y_axis);
+    }
+    if let Some(iterations) = options.iterations {
+        request = request.with_iterations(iterations);
+    }
+    if let Some(palette) = options.palette {
+        request = request.with_palette(palette);
+    }
+    return request;
 }
 
-fn build_mandelbrot_tex_def() -> graphics::TextureSetupDefinition {
-    let width: u32 = 900;
-    let height: u32 = 720;
+fn file_stem(filename: &str) -> Result<String, Box<error::Error>> {
+    let stem = try!(path::Path::new(filename).file_stem().ok_or(FilepathParsingError {}));
+    return Ok(try!(stem.to_str().ok_or(FilepathParsingError {})).to_string());
+}
 
-    let frame = mandelbrot::Frame::new(width, height, -2.3, 1.2, -1.4, 1.4);
+// default_output is <directory>/<name>_<width>x<height>_mandelbrot.png
+fn default_output(directory: &str,
+                  name: &str,
+                  request: &mandelbrot::RenderRequest)
+                  -> Result<String, Box<error::Error>> {
+    let output = path::Path::new(directory).join(format!("{}_{}x{}_mandelbrot.png",
+                                                          name,
+                                                          request.frame.screen_width,
+                                                          request.frame.screen_height));
+    let output_path = try!(output.to_str().ok_or(FilepathParsingError {}));
+    return Ok(output_path.to_string());
+}
+
+// render writes the request's png, making the directory it goes in if it
+// isn't there yet
+fn render(request: &mandelbrot::RenderRequest) -> Result<(), Box<error::Error>> {
+    let output = request.output_path();
+    if let Some(directory) = path::Path::new(&output).parent() {
+        if directory != path::Path::new("") {
+            try!(fs::create_dir_all(directory));
+        }
+    }
+    let (center_x, center_y) = request.frame.center();
+    println!("rendering {}x{} at {}, {} zoomed {}",
+             request.frame.screen_width,
+             request.frame.screen_height,
+             center_x,
+             center_y,
+             request.frame.zoom());
+    let written = try!(mandelbrot::write_png(request));
+    println!("wrote: {}", written);
+    return Ok(());
+}
 
-    let mut data: Vec<u8> = Vec::new();
-    let iterations = 25;
-    for raw_y in 0..height {
-        for raw_x in 0..width {
-            let pixel = mandelbrot::get_pixel_values(&frame, raw_x, raw_y, iterations);
-            data.append(&mut pixel.to_vec());
+// batch renders every frame file, into the --out directory if one is given,
+// carrying on past frames that fail and reporting them all at the end
+fn batch(options: &Options) -> Result<(), Box<error::Error>> {
+    if options.frame_files.is_empty() {
+        return Err(Box::new(ArgError::new("batch needs at least one frame file")));
+    }
+    let directory = options.out.clone().unwrap_or(OUTPUT_DIRECTORY.to_string());
+    let mut failed = 0;
+    for filename in options.frame_files.iter() {
+        println!("reading: {}", filename);
+        let rendered = mandelbrot::parse_frame(filename).and_then(|request| {
+            let mut request = apply_options(request, options);
+            if request.output.is_none() {
+                let output = try!(default_output(&directory, &try!(file_stem(filename)), &request));
+                request = request.with_output(&output);
+            }
+            return render(&request);
+        });
+        if let Err(err) = rendered {
+            let _ = writeln!(io::stderr(), "error: {}", err);
+            failed += 1;
         }
     }
+    if failed > 0 {
+        let message = format!("{} of {} frames failed", failed, options.frame_files.len());
+        return Err(From::from(message));
+    }
+    return Ok(());
+}
 
+fn build_mandelbrot_tex_def(request: &mandelbrot::RenderRequest)
+                            -> graphics::TextureSetupDefinition {
     return graphics::TextureSetupDefinition {
-        width: width,
-        height: height,
-        data: data,
+        width: request.frame.screen_width,
+        height: request.frame.screen_height,
+        data: mandelbrot::gen_png(request).into_raw(),
     };
-
 }
 
-fn explore_mandelbrot() -> Result<(), Box<error::Error>> {
-    let mandelbrot_tex_def = build_mandelbrot_tex_def();
-    let mut app = try!(graphics::App::new(900,
-                                          720,
-                                          "Parallax Client Demo",
+
+// explore_mandelbrot shows the view in a window to move around in, redrawing
+// it whenever it changes
+fn explore_mandelbrot(request: mandelbrot::RenderRequest,
+                      bookmarks_file: &str)
+                      -> Result<(), Box<error::Error>> {
+    let mut saved = try!(bookmarks::load(bookmarks_file));
+    let mut explorer = explorer::Explorer::new(request);
+    let mut app = try!(graphics::App::new(explorer.request().frame.screen_width,
+                                          explorer.request().frame.screen_height,
+                                          "Mandelbrot Explorer",
                                           graphics::RenderingSource::TextureRenderingSource {
-                                              tex_def: mandelbrot_tex_def,
+                                              tex_def: build_mandelbrot_tex_def(explorer.request()),
                                           }));
     let rects: Vec<Box<graphics::VertexSpecable>> =
         vec![Box::new(graphics::TexRect::new(-1.0, 1.0, -1.0, 1.0))];
 
-    loop {
-        match app.handle_events() {
-            Some(graphics::Action::Closed) => break,
-            Some(_) => (),
-            None => (),
+    // the view on the screen, and its texture once it isn't the first
+    let mut shown = explorer.request().clone();
+    let mut texture: Option<graphics::Texture> = None;
+    let mut cursor = (0.0, 0.0);
+    // the bookmark last jumped to, for stepping through them
+    let mut bookmark: Option<usize> = None;
+    let mut title = String::new();
+
+    'running: loop {
+        // take every waiting event, so a burst of them is drawn once
+        while let Some(action) = app.handle_events() {
+            match action {
+                graphics::Action::Closed => break 'running,
+                graphics::Action::Resized(w, h) => explorer.resize(w, h),
+                graphics::Action::KeyPressed(key) => {
+                    match key {
+                        graphics::Key::Left => explorer.pan(-PAN_STEP, 0.0),
+                        graphics::Key::Right => explorer.pan(PAN_STEP, 0.0),
+                        graphics::Key::Up => explorer.pan(0.0, -PAN_STEP),
+                        graphics::Key::Down => explorer.pan(0.0, PAN_STEP),
+                        graphics::Key::Equal => explorer.zoom(ZOOM_STEP),
+                        graphics::Key::Minus => explorer.zoom(1.0 / ZOOM_STEP),
+                        graphics::Key::LeftBracket => {
+                            if !explorer.go_back() {
+                                println!("no earlier view to go back to");
+                            }
+                        }
+                        graphics::Key::RightBracket => {
+                            if !explorer.go_forward() {
+                                println!("no later view to go forward to");
+                            }
+                        }
+                        graphics::Key::Home => explorer.go_home(),
+                        graphics::Key::S => report(save_view(explorer.request()), "save the view"),
+                        graphics::Key::R => render_in_background(explorer.request().clone()),
+                        graphics::Key::B => {
+                            let name = format!("bookmark {}", saved.len() + 1);
+                            saved.push(explorer.bookmark(&name));
+                            match bookmarks::save(bookmarks_file, &saved) {
+                                Ok(_) => println!("added {} to {}", name, bookmarks_file),
+                                Err(err) => println!("could not save the bookmarks: {}", err),
+                            }
+                        }
+                        graphics::Key::Period if !saved.is_empty() => {
+                            let next = bookmark.map(|i| (i + 1) % saved.len()).unwrap_or(0);
+                            bookmark = jump(&mut explorer, &saved, next).or(bookmark);
+                        }
+                        graphics::Key::Comma if !saved.is_empty() => {
+                            let previous = bookmark.map(|i| (i + saved.len() - 1) % saved.len())
+                                .unwrap_or(saved.len() - 1);
+                            bookmark = jump(&mut explorer, &saved, previous).or(bookmark);
+                        }
+                        graphics::Key::P => app.save_screenshot("mandelbrot"),
+                        _ => {
+                            if let Some(number) = bookmark_number(key) {
+                                bookmark = jump(&mut explorer, &saved, number - 1).or(bookmark);
+                            }
+                        }
+                    }
+                }
+                graphics::Action::Scrolled(amount) => {
+                    let (x, y) = cursor;
+                    explorer.zoom_at(x, y, ZOOM_STEP.powf(f64::from(amount)));
+                }
+                graphics::Action::CursorMoved(x, y) => cursor = (f64::from(x), f64::from(y)),
+                graphics::Action::MousePressed => {
+                    let (x, y) = cursor;
+                    explorer.center_on(x, y, 1.0);
+                }
+                graphics::Action::MouseReleased => (),
+            }
+        }
+
+        if *explorer.request() != shown {
+            shown = explorer.request().clone();
+            match app.load_texture(build_mandelbrot_tex_def(&shown)) {
+                Ok(loaded) => texture = Some(loaded),
+                Err(err) => {
+                    println!("could not show the view: {}", err);
+                    if let Some(ref texture) = texture {
+                        app.bind_texture(texture);
+                    }
+                }
+            }
+        }
+
+        let (center_x, center_y) = shown.frame.center();
+        let (cursor_x, cursor_y) = shown.frame.get_coord_for_point(cursor.0, cursor.1);
+        let status = format!("Mandelbrot Explorer - {}, {} zoomed {} - cursor at {}, {}",
+                             center_x,
+                             center_y,
+                             shown.frame.zoom(),
+                             cursor_x,
+                             cursor_y);
+        if status != title {
+            app.set_title(&status);
+            title = status;
         }
 
         app.draw(&rects);
@@ -143,3 +476,86 @@ fn explore_mandelbrot() -> Result<(), Box<error::Error>> {
     app.close();
     return Ok(());
 }
+
+// jump goes to the bookmark, if there is one at the index, and gives back
+// the index if there was
+fn jump(explorer: &mut explorer::Explorer,
+        saved: &[bookmarks::Bookmark],
+        index: usize)
+        -> Option<usize> {
+    return match saved.get(index) {
+        Some(bookmark) => {
+            println!("jumped to {}", bookmark.name);
+            explorer.jump_to(bookmark);
+            Some(index)
+        }
+        None => {
+            println!("no bookmark {}", index + 1);
+            None
+        }
+    };
+}
+
+fn bookmark_number(key: graphics::Key) -> Option<usize> {
+    let numbers = [graphics::Key::Num1,
+                   graphics::Key::Num2,
+                   graphics::Key::Num3,
+                   graphics::Key::Num4,
+                   graphics::Key::Num5,
+                   graphics::Key::Num6,
+                   graphics::Key::Num7,
+                   graphics::Key::Num8,
+                   graphics::Key::Num9];
+    return numbers.iter().position(|&number| number == key).map(|i| i + 1);
+}
+
+// save_view writes the view as a frame file in the output directory, for
+// render, explore or batch to read back
+fn save_view(request: &mandelbrot::RenderRequest) -> Result<(), Box<error::Error>> {
+    try!(fs::create_dir_all(OUTPUT_DIRECTORY));
+    let name = format!("view-{}.csv", try!(timestamp()));
+    let filename = path::Path::new(OUTPUT_DIRECTORY).join(name);
+    let filename = try!(filename.to_str().ok_or(FilepathParsingError {}));
+    try!(mandelbrot::write_frame(request, filename));
+    println!("saved view: {}", filename);
+    return Ok(());
+}
+
+// render_in_background renders the view at a high resolution on a thread of
+// its own, as it takes a while, so that the window carries on meanwhile
+fn render_in_background(request: mandelbrot::RenderRequest) {
+    println!("rendering the view {} times bigger in the background", HIGH_RES_SCALE);
+    thread::spawn(move || report(render_high_res(&request), "render the view"));
+}
+
+// render_high_res renders the view HIGH_RES_SCALE times bigger across and
+// down, supersampled if it isn't already, into the output directory
+fn render_high_res(request: &mandelbrot::RenderRequest) -> Result<(), Box<error::Error>> {
+    let frame = request.frame;
+    let (center_x, center_y) = frame.center();
+    let mut request = request.clone();
+    request.frame = mandelbrot::Frame::centered(frame.screen_width * HIGH_RES_SCALE,
+                                                frame.screen_height * HIGH_RES_SCALE,
+                                                center_x,
+                                                center_y,
+                                                frame.zoom())
+        .with_y_axis(frame.y_axis);
+    if request.supersampling.samples == 1 {
+        request.supersampling = mandelbrot::Supersampling::grid(2).with_adaptive(true);
+    }
+    let name = format!("view-{}", try!(timestamp()));
+    let output = try!(default_output(OUTPUT_DIRECTORY, &name, &request));
+    return render(&request.with_output(&output));
+}
+
+// timestamp is the milliseconds since the epoch, to name saved views by
+fn timestamp() -> Result<u64, Box<error::Error>> {
+    let since_epoch = try!(time::SystemTime::now().duration_since(time::UNIX_EPOCH));
+    return Ok(since_epoch.as_secs() * 1000 + (since_epoch.subsec_nanos() / 1000000) as u64);
+}
+
+fn report(result: Result<(), Box<error::Error>>, action: &str) {
+    if let Err(err) = result {
+        println!("could not {}: {}", action, err);
+    }
+}
@@ -0,0 +1,330 @@
+use graphics;
+
+use std;
+
+use sceneplotlib::fileformat;
+use sceneplotlib::keyframe;
+
+pub trait UpdateSpecable: graphics::VertexSpecable + graphics::Updateable {}
+impl<T> UpdateSpecable for T where T: graphics::VertexSpecable + graphics::Updateable {}
+
+// Layout is what a scene's rows become: shape rows are built into shapes to
+// draw, and group rows into nodes of a scene graph that the shapes in them
+// are attached to
+pub struct Layout {
+    shapes: Vec<Box<UpdateSpecable>>,
+    rows: Vec<Row>,
+    graph: graphics::SceneGraph,
+    // groups are the scene graph nodes in the order their rows come in
+    groups: Vec<usize>,
+}
+
+enum Row {
+    // the index of the shape, and whether it was set to be shown
+    Shape(usize, bool),
+    Group(usize),
+}
+
+impl Layout {
+    pub fn new(sources: &[fileformat::ShapeSource]) -> Result<Layout, Box<std::error::Error>> {
+        let mut layout = Layout {
+            shapes: Vec::new(),
+            rows: Vec::new(),
+            graph: graphics::SceneGraph::new(),
+            groups: Vec::new(),
+        };
+        let mut names: Vec<(&str, usize)> = Vec::new();
+        for (i, s) in sources.iter().enumerate() {
+            // parents were checked when the scene was loaded, so a name that
+            // isn't found can only mean there is no parent
+            let parent = s.parent
+                .as_ref()
+                .and_then(|parent| names.iter().find(|&&(name, _)| name == parent.as_str()))
+                .map(|&(_, node)| node)
+                .unwrap_or(layout.graph.root());
+            let visible = s.visible.unwrap_or(true);
+            if let fileformat::ShapeType::Group = s.shape {
+                let node = layout.graph.add_node(parent, s.x, s.y);
+                layout.graph.set_visible(node, visible);
+                if let Some(ref name) = s.name {
+                    names.push((name, node));
+                }
+                layout.groups.push(node);
+                layout.rows.push(Row::Group(node));
+            } else {
+                // only paths can fail, when their outline crosses itself
+                let mut shape = try!(build_shape(s)
+                    .map_err(|e| format!("shape {}: {}", i + 1, e)));
+                if let Some(ref gradient) = s.fill {
+                    shape.update_fill(build_fill(gradient));
+                }
+                let index = layout.shapes.len();
+                layout.shapes.push(shape);
+                layout.graph.attach(parent, index);
+                layout.rows.push(Row::Shape(index, visible));
+            }
+        }
+        return Ok(layout);
+    }
+
+    // animate moves every shape and group to where its motions and keyframe
+    // tracks put it at the given time
+    pub fn animate(&mut self,
+                   sources: &[fileformat::ShapeSource],
+                   tracks: &[keyframe::Track],
+                   time: f32) {
+        for (i, s) in sources.iter().enumerate() {
+            let new_x = s.x_scale * s.x_func.value_at(time + s.x_offset, s.x_cycle_size);
+            let new_y = s.y_scale * s.y_func.value_at(time + s.y_offset, s.y_cycle_size);
+            let pose = keyframe::pose_at(tracks, i, time);
+
+            match self.rows[i] {
+                Row::Shape(index, _) => {
+                    let shape = &mut self.shapes[index];
+                    shape.update_offset(new_x, new_y);
+                    shape.update_transform(transform_at(s, time));
+                    apply_pose(&mut **shape, s, &pose);
+                }
+                Row::Group(node) => {
+                    // groups only have a position and a transform to animate
+                    self.graph.set_position(node,
+                                            pose.x.unwrap_or(s.x) + new_x,
+                                            pose.y.unwrap_or(s.y) + new_y);
+                    let mut transform = transform_at(s, time);
+                    if let Some(degrees) = pose.rotation {
+                        transform.rotation = degrees.to_radians();
+                    }
+                    self.graph.set_transform(node, transform);
+                }
+            }
+        }
+    }
+
+    // toggle_group shows or hides the nth group (counting from 0) along with
+    // everything in it
+    pub fn toggle_group(&mut self, n: usize) {
+        if let Some(&node) = self.groups.get(n) {
+            let visible = self.graph.is_visible(node);
+            self.graph.set_visible(node, !visible);
+        }
+    }
+
+    pub fn draw(&mut self, app: &mut graphics::App) {
+        let in_shown_group = self.graph.update(&mut self.shapes);
+        let mut shown: Vec<Box<&UpdateSpecable>> = Vec::new();
+        for row in self.rows.iter() {
+            if let &Row::Shape(index, visible) = row {
+                if visible && in_shown_group[index] {
+                    shown.push(Box::new(&*self.shapes[index]));
+                }
+            }
+        }
+        app.draw(&shown);
+    }
+}
+
+fn build_shape(s: &fileformat::ShapeSource) -> Result<Box<UpdateSpecable>, Box<std::error::Error>> {
+    let points = s.points.clone().map(|p| p.0).unwrap_or(Vec::new());
+    let shape: Box<UpdateSpecable> = match s.shape {
+        fileformat::ShapeType::Rect => {
+            Box::new(graphics::SimpleRect::new(s.x, s.y, s.width, s.height, s.red, s.green, s.blue))
+        }
+        fileformat::ShapeType::Triangle => {
+            Box::new(graphics::SimpleTriangle::new(s.x,
+                                                   s.y,
+                                                   s.width,
+                                                   s.height,
+                                                   s.red,
+                                                   s.green,
+                                                   s.blue))
+        }
+        fileformat::ShapeType::Ellipse => {
+            Box::new(graphics::Ellipse::new(s.x,
+                                            s.y,
+                                            s.width,
+                                            s.height,
+                                            s.segments.unwrap_or(32),
+                                            s.red,
+                                            s.green,
+                                            s.blue))
+        }
+        fileformat::ShapeType::Circle => {
+            Box::new(graphics::Ellipse::circle(s.x,
+                                               s.y,
+                                               s.width,
+                                               s.segments.unwrap_or(32),
+                                               s.red,
+                                               s.green,
+                                               s.blue))
+        }
+        fileformat::ShapeType::Polygon => {
+            Box::new(graphics::RegularPolygon::new(s.x,
+                                                   s.y,
+                                                   s.width,
+                                                   s.height,
+                                                   s.segments.unwrap_or(6),
+                                                   s.red,
+                                                   s.green,
+                                                   s.blue))
+        }
+        fileformat::ShapeType::Convex => {
+            Box::new(graphics::ConvexPolygon::new(s.x,
+                                                  s.y,
+                                                  s.width,
+                                                  s.height,
+                                                  points,
+                                                  s.red,
+                                                  s.green,
+                                                  s.blue))
+        }
+        fileformat::ShapeType::Line => {
+            let join = match s.join.unwrap_or(fileformat::Join::Miter) {
+                fileformat::Join::Miter => graphics::LineJoin::Miter,
+                fileformat::Join::Bevel => graphics::LineJoin::Bevel,
+                fileformat::Join::Round => graphics::LineJoin::Round,
+            };
+            let cap = match s.cap.unwrap_or(fileformat::Cap::Butt) {
+                fileformat::Cap::Butt => graphics::LineCap::Butt,
+                fileformat::Cap::Square => graphics::LineCap::Square,
+                fileformat::Cap::Round => graphics::LineCap::Round,
+            };
+            Box::new(graphics::Polyline::new(s.x,
+                                             s.y,
+                                             s.width,
+                                             s.height,
+                                             points,
+                                             s.thickness.unwrap_or(0.02),
+                                             join,
+                                             cap,
+                                             s.red,
+                                             s.green,
+                                             s.blue))
+        }
+        fileformat::ShapeType::RoundedRect => {
+            // corners default to a quarter of the shorter side
+            let radius = s.radius.unwrap_or(s.width.min(s.height) / 4.0);
+            Box::new(graphics::RoundedRect::new(s.x,
+                                                s.y,
+                                                s.width,
+                                                s.height,
+                                                radius,
+                                                s.red,
+                                                s.green,
+                                                s.blue))
+        }
+        fileformat::ShapeType::Group => unreachable!("groups are scene graph nodes, not shapes"),
+        fileformat::ShapeType::Path => {
+            let path = build_path(s.path.as_ref().map(|p| &p.0[..]).unwrap_or(&[]));
+            Box::new(try!(graphics::FilledPath::new(s.x,
+                                                    s.y,
+                                                    s.width,
+                                                    s.height,
+                                                    &path,
+                                                    s.red,
+                                                    s.green,
+                                                    s.blue)))
+        }
+    };
+    return Ok(shape);
+}
+
+fn build_fill(gradient: &fileformat::Gradient) -> graphics::Fill {
+    let color = |(red, green, blue, alpha): fileformat::Rgba| {
+        graphics::Color {
+            red: red,
+            green: green,
+            blue: blue,
+            alpha: alpha,
+        }
+    };
+    let stops = |stops: &Vec<(f32, fileformat::Rgba)>| {
+        stops.iter().map(|&(at, rgba)| (at, color(rgba))).collect()
+    };
+    match gradient {
+        &fileformat::Gradient::Linear { start, end, stops: ref s } => {
+            graphics::Fill::Linear {
+                start: start,
+                end: end,
+                stops: stops(s),
+            }
+        }
+        &fileformat::Gradient::Radial { center, radius, stops: ref s } => {
+            graphics::Fill::Radial {
+                center: center,
+                radius: radius,
+                stops: stops(s),
+            }
+        }
+        &fileformat::Gradient::Corners(ref corners) => {
+            graphics::Fill::Corners([color(corners[0]),
+                                     color(corners[1]),
+                                     color(corners[2]),
+                                     color(corners[3])])
+        }
+    }
+}
+
+fn build_path(commands: &[fileformat::PathCommand]) -> graphics::Path {
+    let mut path = graphics::Path::new();
+    for command in commands.iter() {
+        path = match command {
+            &fileformat::PathCommand::MoveTo(x, y) => path.move_to(x, y),
+            &fileformat::PathCommand::LineTo(x, y) => path.line_to(x, y),
+            &fileformat::PathCommand::QuadTo(cx, cy, x, y) => path.quad_to(cx, cy, x, y),
+            &fileformat::PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) => {
+                path.cubic_to(c1x, c1y, c2x, c2y, x, y)
+            }
+            &fileformat::PathCommand::Close => path.close(),
+        };
+    }
+    return path;
+}
+
+// transform_at turns and grows a shape by its rotation and grow motions,
+// about its pivot
+fn transform_at(s: &fileformat::ShapeSource, time: f32) -> graphics::Transform2D {
+    let mut transform = graphics::Transform2D::identity()
+        .about(s.pivot_x.unwrap_or(0.0), s.pivot_y.unwrap_or(0.0));
+    if let Some(ref func) = s.rotation_func {
+        let degrees = s.rotation_scale.unwrap_or(0.0) *
+                      func.value_at(time + s.rotation_offset.unwrap_or(0.0),
+                                    s.rotation_cycle_size.unwrap_or(1.0));
+        transform = transform.rotated(degrees.to_radians());
+    }
+    if let Some(ref func) = s.grow_func {
+        let growth = func.value_at(time + s.grow_offset.unwrap_or(0.0),
+                                   s.grow_cycle_size.unwrap_or(1.0));
+        transform = transform.scaled(1.0 + s.grow_x.unwrap_or(0.0) * growth,
+                                     1.0 + s.grow_y.unwrap_or(0.0) * growth);
+    }
+    return transform;
+}
+
+// apply_pose sets whatever the keyframe tracks animate, using the values from
+// the scene file for the parts of a position, size or color they leave out
+fn apply_pose(shape: &mut UpdateSpecable,
+              s: &fileformat::ShapeSource,
+              pose: &keyframe::Pose) {
+    if pose.x.is_some() || pose.y.is_some() {
+        shape.update_position(pose.x.unwrap_or(s.x), pose.y.unwrap_or(s.y));
+    }
+    if pose.width.is_some() || pose.height.is_some() {
+        shape.update_size(pose.width.unwrap_or(s.width),
+                          pose.height.unwrap_or(s.height));
+    }
+    if let Some(degrees) = pose.rotation {
+        shape.update_rotation(degrees.to_radians());
+    }
+    if pose.red.is_some() || pose.green.is_some() || pose.blue.is_some() ||
+       pose.alpha.is_some() {
+        shape.update_color(color_channel(pose.red, s.red),
+                           color_channel(pose.green, s.green),
+                           color_channel(pose.blue, s.blue),
+                           color_channel(pose.alpha, 255));
+    }
+}
+
+fn color_channel(value: Option<f32>, default: u8) -> u8 {
+    return value.map(|v| v.max(0.0).min(255.0).round() as u8).unwrap_or(default);
+}
+
@@ -1,8 +1,13 @@
 extern crate graphics;
 extern crate sceneplotlib;
 
+mod layout;
+
+use std::io::Write;
+
+use sceneplotlib::clock;
 use sceneplotlib::fileformat;
-use sceneplotlib::types;
+use sceneplotlib::watch;
 
 #[derive(Debug)]
 struct ArgError {}
@@ -15,7 +20,9 @@ impl std::fmt::Display for ArgError {
 
 impl std::error::Error for ArgError {
     fn description(&self) -> &str {
-        return "must supply the filename to read from";
+        return "usage: sceneplot <scene.csv|scene.toml> [--fixed-fps <fps>] [--record <dir>] | \
+                sceneplot render <scene.csv|scene.toml> --frames <n> --out <dir> [--fps <fps>] \
+                [--size <width>x<height>] | sceneplot convert <scene.csv> <scene.toml>";
     }
 
     fn cause(&self) -> Option<&std::error::Error> {
@@ -23,9 +30,6 @@ impl std::error::Error for ArgError {
     }
 }
 
-trait UpdateSpecable: graphics::VertexSpecable + graphics::Updateable {}
-impl<T> UpdateSpecable for T where T: graphics::VertexSpecable + graphics::Updateable {}
-
 fn main() {
     println!("hello from a client");
     match run_app() {
@@ -35,73 +39,266 @@ fn main() {
     println!("exiting");
 }
 
+// RECORD_FPS is the frame rate recordings and renders advance at when they
+// aren't given one
+const RECORD_FPS: f32 = 30.0;
+
+struct Options {
+    filename: String,
+    fixed_fps: Option<f32>,
+    record: Option<String>,
+    frames: Option<u32>,
+    fps: Option<f32>,
+    size: Option<(u32, u32)>,
+    out: Option<String>,
+}
+
+// parse_options reads a scene filename followed by any of the given flags,
+// each with a value, in any order
+fn parse_options(args: &[String], flags: &[&str]) -> Result<Options, Box<std::error::Error>> {
+    if args.len() < 1 {
+        return Err(Box::new(ArgError {}));
+    }
+    let mut options = Options {
+        filename: args[0].clone(),
+        fixed_fps: None,
+        record: None,
+        frames: None,
+        fps: None,
+        size: None,
+        out: None,
+    };
+    let mut rest = args[1..].iter();
+    loop {
+        let (flag, value) = match (rest.next(), rest.next()) {
+            (None, _) => return Ok(options),
+            (Some(flag), Some(value)) if flags.contains(&flag.as_str()) => (flag.as_str(), value),
+            _ => return Err(Box::new(ArgError {})),
+        };
+        match flag {
+            "--fixed-fps" => options.fixed_fps = Some(try!(parse_rate(value))),
+            "--record" => options.record = Some(value.clone()),
+            "--frames" => options.frames = Some(try!(value.parse())),
+            "--fps" => options.fps = Some(try!(parse_rate(value))),
+            "--size" => options.size = Some(try!(parse_size(value))),
+            "--out" => options.out = Some(value.clone()),
+            _ => return Err(Box::new(ArgError {})),
+        }
+    }
+}
+
+// parse_rate reads a frame rate, which has to be more than zero for each
+// frame to take a time
+fn parse_rate(value: &str) -> Result<f32, Box<std::error::Error>> {
+    let fps: f32 = try!(value.parse());
+    if !(fps > 0.0 && fps.is_finite()) {
+        return Err(Box::new(ArgError {}));
+    }
+    return Ok(fps);
+}
+
+// parse_size reads a size written as <width>x<height>
+fn parse_size(value: &str) -> Result<(u32, u32), Box<std::error::Error>> {
+    let parts: Vec<&str> = value.split('x').collect();
+    if parts.len() != 2 {
+        return Err(Box::new(ArgError {}));
+    }
+    return Ok((try!(parts[0].parse()), try!(parts[1].parse())));
+}
+
+// frame_filename is where the numbered frame goes in a recording
+fn frame_filename(directory: &str, frame: u32) -> String {
+    return std::path::Path::new(directory)
+        .join(format!("frame-{:05}.png", frame))
+        .to_string_lossy()
+        .into_owned();
+}
+
 fn run_app() -> Result<(), Box<std::error::Error>> {
     let args: Vec<String> = std::env::args().collect();
-    let filename: &str = try!(match args.len() {
-        2 => Ok(args[1].as_str()),
-        _ => Err(Box::new(ArgError {})),
-    });
+    if args.len() == 4 && args[1] == "convert" {
+        return convert(args[2].as_str(), args[3].as_str());
+    }
+    if args.len() > 1 && args[1] == "render" {
+        return render(try!(parse_options(&args[2..], &["--frames", "--fps", "--size", "--out"])));
+    }
+    let options = try!(parse_options(&args[1..], &["--fixed-fps", "--record"]));
+    let filename = options.filename.as_str();
+    let mut clock = match (options.fixed_fps, &options.record) {
+        (Some(fps), _) => clock::Clock::fixed_step(1.0 / fps),
+        (None, &Some(_)) => clock::Clock::fixed_step(1.0 / RECORD_FPS),
+        (None, &None) => clock::Clock::real_time(),
+    };
+    match options.record {
+        Some(ref directory) => try!(std::fs::create_dir_all(directory)),
+        None => (),
+    }
 
     println!("reading: {}", filename);
-    let shape_sources = try!(fileformat::parse_shape_source(filename));
-
-
-    let mut rects: Vec<Box<UpdateSpecable>> = std::vec::Vec::new();
-    for shape_source in shape_sources.iter() {
-        match shape_source.shape {
-            fileformat::ShapeType::Rect => {
-                rects.push(Box::new(graphics::SimpleRect::new(shape_source.x,
-                                                              shape_source.y,
-                                                              shape_source.width,
-                                                              shape_source.height,
-                                                              shape_source.red,
-                                                              shape_source.green,
-                                                              shape_source.blue)))
-            }
-            fileformat::ShapeType::Triangle => {
-                rects.push(Box::new(graphics::SimpleTriangle::new(shape_source.x,
-                                                                  shape_source.y,
-                                                                  shape_source.width,
-                                                                  shape_source.height,
-                                                                  shape_source.red,
-                                                                  shape_source.green,
-                                                                  shape_source.blue)))
-            }
-        }
-    }
+    let mut scene = try!(fileformat::load_scene(filename));
+    say_keyframes(filename, &scene);
+    let mut layout = try!(layout::Layout::new(&scene.shapes));
 
-    let mut app = try!(graphics::App::new(600,
-                                          600,
-                                          "Parallax Client Demo",
+    let mut app = try!(graphics::App::new(scene.settings.width,
+                                          scene.settings.height,
+                                          &scene.settings.title,
                                           graphics::RenderingSource::ColorRenderingSource));
-    let mut iteration = 0;
-    loop {
-        for i in 0..shape_sources.len() {
-            let ref s = shape_sources[i];
+    let (red, green, blue) = scene.settings.background;
+    app.set_background(red, green, blue);
+    app.set_keep_frames(options.record.is_some());
+
+    let mut frame: u32 = 0;
+    let mut watcher = watch::FileWatcher::new(fileformat::scene_files(filename),
+                                              std::time::Duration::from_millis(500));
+    'running: loop {
+        if watcher.changed() {
+            reload(filename, &mut scene, &mut layout, &mut app);
+        }
 
-            let new_x = s.x_scale * operate(s.x_func, iteration + s.x_offset, s.x_cycle_size);
-            let new_y = s.y_scale * operate(s.y_func, iteration + s.y_offset, s.y_cycle_size);
+        let time = clock.tick();
+        layout.animate(&scene.shapes, &scene.tracks, time);
 
-            rects[i].update_offset(new_x, new_y);
+        // take every waiting event, as moving the mouse sends a lot of them
+        while let Some(action) = app.handle_events() {
+            match action {
+                graphics::Action::Closed => break 'running,
+                graphics::Action::KeyPressed(key) => {
+                    match (key, group_key(key)) {
+                        (_, Some(n)) => layout.toggle_group(n),
+                        (graphics::Key::P, None) => app.save_screenshot("sceneplot"),
+                        (_, None) => control_clock(&mut clock, key),
+                    }
+                }
+                _ => (),
+            }
         }
 
-        match app.handle_events() {
-            Some(graphics::Action::Closed) => break,
-            Some(_) => (),
+        layout.draw(&mut app);
+
+        match options.record {
+            Some(ref directory) => try!(app.save_frame(&frame_filename(directory, frame))),
             None => (),
         }
+        frame += 1;
+    }
+    app.close();
+    return Ok(());
+}
+
+// reload swaps in the scene file's new contents, leaving the clock alone so
+// the animation carries on from where it was. If the file no longer loads,
+// the last good scene stays up and the error goes in the title bar until a
+// later change fixes it. The window keeps the size it was opened with
+fn reload(filename: &str,
+          scene: &mut fileformat::Scene,
+          layout: &mut layout::Layout,
+          app: &mut graphics::App) {
+    match fileformat::load_scene(filename)
+        .and_then(|s| layout::Layout::new(&s.shapes).map(|l| (s, l))) {
+        Ok((reloaded, reloaded_layout)) => {
+            println!("reloaded: {}", filename);
+            *layout = reloaded_layout;
+            app.set_title(&reloaded.settings.title);
+            let (red, green, blue) = reloaded.settings.background;
+            app.set_background(red, green, blue);
+            *scene = reloaded;
+        }
+        Err(err) => {
+            println!("could not reload {}, keeping the last scene:\n{}", filename, err);
+            let message = err.to_string();
+            let first_line = message.lines().next().unwrap_or("");
+            app.set_title(&format!("{} [reload failed: {}]", scene.settings.title, first_line));
+        }
+    }
+}
 
-        iteration += 1;
+// render draws the scene's first frames without a window and saves them as
+// a numbered png sequence. Each frame's time comes straight from its number,
+// so the same scene always renders to the same files
+fn render(options: Options) -> Result<(), Box<std::error::Error>> {
+    let frames = try!(options.frames.ok_or(ArgError {}));
+    let directory = try!(options.out.ok_or(ArgError {}));
+    let fps = options.fps.unwrap_or(RECORD_FPS);
 
-        app.draw(&rects);
+    println!("rendering: {}", options.filename);
+    let scene = try!(fileformat::load_scene(&options.filename));
+    say_keyframes(&options.filename, &scene);
+    let mut layout = try!(layout::Layout::new(&scene.shapes));
+    let (width, height) = options.size.unwrap_or((scene.settings.width, scene.settings.height));
+
+    let source = graphics::RenderingSource::ColorRenderingSource;
+    let mut app = try!(graphics::App::new_offscreen(width, height, source));
+    let (red, green, blue) = scene.settings.background;
+    app.set_background(red, green, blue);
+
+    try!(std::fs::create_dir_all(&directory));
+    for frame in 0..frames {
+        layout.animate(&scene.shapes, &scene.tracks, frame as f32 / fps);
+        layout.draw(&mut app);
+        try!(app.save_frame(&frame_filename(&directory, frame)));
     }
+    println!("rendered {} frames to {}", frames, directory);
     app.close();
     return Ok(());
 }
 
-pub fn operate(func: types::MathFunc, iteration: u16, u_cycle_size: u16) -> f32 {
-    let cycle_size = f32::from(u_cycle_size);
-    let tick = f32::from(iteration) % cycle_size;
+// convert rewrites a scene (normally a legacy csv) in the structured format
+fn convert(from: &str, to: &str) -> Result<(), Box<std::error::Error>> {
+    println!("converting {} to {}", from, to);
+    let scene = try!(fileformat::load_scene(from));
+    say_keyframes(from, &scene);
+    let mut out = try!(std::fs::File::create(to));
+    try!(out.write_all(fileformat::to_scene_file(&scene).as_bytes()));
+    return Ok(());
+}
+
+// say_keyframes tells where a csv scene's keyframes were read from, as they
+// are in a file of their own that is easily left behind when the scene moves
+fn say_keyframes(filename: &str, scene: &fileformat::Scene) {
+    if filename.ends_with(".toml") {
+        return;
+    }
+    let keyframes = fileformat::keyframes_filename(filename);
+    match std::path::Path::new(&keyframes).exists() {
+        true => println!("keyframes: {} ({} tracks)", keyframes, scene.tracks.len()),
+        false => println!("no keyframes: there is no {}", keyframes),
+    }
+}
+
+// group_key is which group the number keys 1 to 9 show and hide
+fn group_key(key: graphics::Key) -> Option<usize> {
+    let keys = [graphics::Key::Num1,
+                graphics::Key::Num2,
+                graphics::Key::Num3,
+                graphics::Key::Num4,
+                graphics::Key::Num5,
+                graphics::Key::Num6,
+                graphics::Key::Num7,
+                graphics::Key::Num8,
+                graphics::Key::Num9];
+    return keys.iter().position(|k| *k == key);
+}
 
-    return func.plot_with_max(tick, cycle_size);
+// control_clock maps the playback keys onto the animation clock:
+// space pauses/resumes, left/right seek by a second, up/down double/halve
+// the speed, and home restarts from the beginning at normal speed
+fn control_clock(clock: &mut clock::Clock, key: graphics::Key) {
+    match key {
+        graphics::Key::Space => clock.toggle_pause(),
+        graphics::Key::Left => clock.skip(-1.0),
+        graphics::Key::Right => clock.skip(1.0),
+        graphics::Key::Up => {
+            let speed = clock.speed() * 2.0;
+            clock.set_speed(speed);
+        }
+        graphics::Key::Down => {
+            let speed = clock.speed() / 2.0;
+            clock.set_speed(speed);
+        }
+        graphics::Key::Home => {
+            clock.seek(0.0);
+            clock.set_speed(1.0);
+        }
+        _ => (),
+    }
 }